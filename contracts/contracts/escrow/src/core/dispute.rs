@@ -26,7 +26,7 @@ impl DisputeManager {
         let mut escrow = EscrowManager::get_escrow(e)?;
         let contract_address = e.current_contract_address();
 
-        let token_client = TokenClient::new(&e, &escrow.trustline.address);
+        let token_client = TokenClient::new(e, &escrow.trustline.address);
         let current_balance = token_client.balance(&contract_address);
 
         let mut total: i128 = 0;
@@ -67,7 +67,7 @@ impl DisputeManager {
             if amount <= 0 {
                 continue;
             }
-            let fee_share = (amount * (total_fees as i128)) / total;
+            let fee_share = (amount * total_fees) / total;
             let net_amount = amount - fee_share;
             if net_amount > 0 {
                 token_client.transfer(&contract_address, &addr, &net_amount);
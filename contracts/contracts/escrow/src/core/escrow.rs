@@ -38,7 +38,7 @@ impl EscrowManager {
         let balance = token_client.balance(signer);
         validate_fund_escrow_conditions(amount, balance, &stored_escrow, expected_escrow)?;
 
-        token_client.transfer(signer, &e.current_contract_address(), &amount);
+        token_client.transfer(signer, e.current_contract_address(), &amount);
         Ok(())
     }
 
@@ -63,7 +63,7 @@ impl EscrowManager {
         }
 
         let fee_result =
-            FeeCalculator::calculate_standard_fees(escrow.amount as i128, escrow.platform_fee)?;
+            FeeCalculator::calculate_standard_fees(escrow.amount, escrow.platform_fee)?;
 
         token_client.transfer(
             &contract_address,
@@ -140,9 +140,9 @@ impl EscrowManager {
     }
 
     pub fn get_escrow(e: &Env) -> Result<Escrow, ContractError> {
-        Ok(e.storage()
+        e.storage()
             .instance()
             .get(&DataKey::Escrow)
-            .ok_or(ContractError::EscrowNotFound)?)
+            .ok_or(ContractError::EscrowNotFound)
     }
 }
@@ -1,13 +1,35 @@
-use soroban_sdk::{contract, contractimpl, Address, Env};
+use soroban_sdk::{contract, contractimpl, Address, Bytes, BytesN, Env, String, Vec};
 
-use crate::core::{AdminManager, DisputeManager, OrderManager};
+use crate::core::{
+    AdManager, AdminManager, ArchiveManager, CircuitBreakerManager, ComplianceManager,
+    DisputeManager, EscrowManager, FeeCharge, FeeManager, FillHistoryManager, InsuranceManager,
+    LedgerManager, MarketStatsManager, MigrationManager, OfferManager, OperatorManager,
+    OrderManager, ReferralManager, RegistryManager, ResolverManager,
+};
 use crate::error::ContractError;
 use crate::events::handler::{
-    DisputeResolved, FiatPaymentConfirmed, FiatPaymentDisputed, FiatPaymentSubmitted,
-    FiatTransferTimeout, Initialized, OrderCancelled, OrderCreated, OrderTaken, PausedEvt,
-    UnpausedEvt,
+    AdClosed, AdCreated, AdFilled, AdUpdated, AddressBlocked, AddressUnblocked, AppealDecided,
+    ArchiveRetentionUpdated, CircuitBreakerConfigUpdated, CircuitBreakerReset, CurrencyUpdated,
+    DisputeResolved, EmergencyCancelled, EmergencyDelayUpdated, EmergencyTriggered,
+    EmergencyWithdrawal, EscrowSettingsUpdated, FeeCharged, FeeConfigUpdated, FiatDeadlineExtended,
+    FiatPaymentConfirmed, FiatPaymentDisputed, FiatPaymentSubmitted, FiatTransferTimeout,
+    FillEscrowOpened, Initialized, InsuranceClaimApproved, InsuranceClaimClosed,
+    InsuranceClaimFiled, InsuranceConfigUpdated, InsuranceFunded, MaxFiatExtensionUpdated,
+    Migrated, OfferAccepted, OfferClosed, OfferMade, OperatorRevoked, OperatorUpdated,
+    OrderArchived, OrderCancelled, OrderCreated, OrderFrozen, OrderTaken, OrderUnfrozen,
+    PauseFlagsUpdated, PausedEvt, PaymentMethodUpdated, ReferralRewardsClaimed, ReferrerRegistered,
+    ResolverStakeConfigUpdated, ResolverStakeUpdated, ResolverStakeWithdrawn, RulingAppealed,
+    RulingRecorded, SettlementFinalized, SettlementHeld, SettlementHoldUpdated, SurplusSwept,
+    TimeoutBoundsUpdated, TimeoutOverrideUpdated, UnpausedEvt, Upgraded, EVENT_VERSION,
+};
+use crate::storage::types::{
+    AdParams, BreakerConfig, BreakerStatus, ClaimStatus, Config, CurrencyInfo, EmergencyState,
+    EscrowSettings, FeeConfig, FiatCurrency, FillEscrow, FillRecord, InsuranceClaim,
+    InsuranceConfig, MarketStats, MerchantAd, Offer, OperatorGrant, OperatorUsage, Order,
+    OrderFill, OrderParams, OrderStatus, OrderSummary, OrderVisibility, PauseFlags, PaymentMethod,
+    PaymentMethodInfo, ReferralStats, ResolverStake, ResolverStakeConfig, Ruling, SolvencyReport,
+    TimeoutBounds, TimeoutScope,
 };
-use crate::storage::types::{Config, FiatCurrency, Order, PaymentMethod};
 
 #[contract]
 pub struct P2PContract;
@@ -21,6 +43,7 @@ impl P2PContract {
         admin: Address,
         dispute_resolver: Address,
         pauser: Address,
+        compliance: Address,
         token: Address,
         max_duration_secs: u64,
         filler_payment_timeout_secs: u64,
@@ -31,16 +54,21 @@ impl P2PContract {
             admin,
             dispute_resolver,
             pauser,
+            compliance,
             token,
             max_duration_secs,
             filler_payment_timeout_secs,
         )?;
 
         Initialized {
+            version: EVENT_VERSION,
             admin: config.admin,
             dispute_resolver: config.dispute_resolver,
             pauser: config.pauser,
+            compliance: config.compliance,
             token: config.token,
+            max_duration_secs: config.max_duration_secs,
+            filler_payment_timeout_secs: config.filler_payment_timeout_secs,
         }
         .publish(&e);
 
@@ -49,212 +77,1510 @@ impl P2PContract {
 
     pub fn pause(e: Env, caller: Address) -> Result<(), ContractError> {
         AdminManager::pause(&e, caller.clone())?;
-        PausedEvt { by: caller }.publish(&e);
+        PausedEvt {
+            version: EVENT_VERSION,
+            by: caller,
+        }
+        .publish(&e);
         Ok(())
     }
 
     pub fn unpause(e: Env, caller: Address) -> Result<(), ContractError> {
         AdminManager::unpause(&e, caller.clone())?;
-        UnpausedEvt { by: caller }.publish(&e);
+        UnpausedEvt {
+            version: EVENT_VERSION,
+            by: caller,
+        }
+        .publish(&e);
         Ok(())
     }
 
-    pub fn create_order(
+    pub fn set_pause_flags(
         e: Env,
         caller: Address,
-        fiat_currency: FiatCurrency,
-        payment_method: PaymentMethod,
-        from_crypto: bool,
-        amount: i128,
-        exchange_rate: i128,
-        duration_secs: u64,
-    ) -> Result<u64, ContractError> {
-        let order = OrderManager::create_order(
-            &e,
-            caller,
-            fiat_currency,
-            payment_method,
-            from_crypto,
-            amount,
-            exchange_rate,
-            duration_secs,
-        )?;
+        flags: PauseFlags,
+    ) -> Result<(), ContractError> {
+        AdminManager::set_pause_flags(&e, caller.clone(), flags.clone())?;
+        PauseFlagsUpdated {
+            version: EVENT_VERSION,
+            by: caller,
+            new_orders: flags.new_orders,
+            takes: flags.takes,
+            settlements: flags.settlements,
+            disputes: flags.disputes,
+        }
+        .publish(&e);
+        Ok(())
+    }
 
-        OrderCreated {
-            order_id: order.order_id,
-            creator: order.creator,
-            amount: order.amount,
-            from_crypto: order.from_crypto,
+    pub fn set_circuit_breaker_config(
+        e: Env,
+        caller: Address,
+        breaker_config: BreakerConfig,
+    ) -> Result<(), ContractError> {
+        CircuitBreakerManager::set_config(&e, caller, breaker_config.clone())?;
+        CircuitBreakerConfigUpdated {
+            version: EVENT_VERSION,
+            window_secs: breaker_config.window_secs,
+            max_dispute_bps: breaker_config.max_dispute_bps,
+            min_samples: breaker_config.min_samples,
         }
         .publish(&e);
+        Ok(())
+    }
 
-        Ok(order.order_id)
+    /// Pauser only. Reopens takes in a currency the circuit breaker tripped.
+    pub fn reset_circuit_breaker(
+        e: Env,
+        caller: Address,
+        currency: FiatCurrency,
+    ) -> Result<(), ContractError> {
+        CircuitBreakerManager::reset(&e, caller.clone(), currency.clone())?;
+        CircuitBreakerReset {
+            version: EVENT_VERSION,
+            currency,
+            reset_by: caller,
+        }
+        .publish(&e);
+        Ok(())
     }
 
-    pub fn create_order_cli(
+    pub fn set_emergency_delay(
         e: Env,
         caller: Address,
-        fiat_currency_code: u32,
-        payment_method_code: u32,
-        from_crypto: bool,
-        amount: i128,
-        exchange_rate: i128,
-        duration_secs: u64,
-    ) -> Result<u64, ContractError> {
-        let fiat_currency = FiatCurrency::from_code(fiat_currency_code);
-        let payment_method = PaymentMethod::from_code(payment_method_code);
+        delay_secs: u64,
+    ) -> Result<(), ContractError> {
+        AdminManager::set_emergency_delay(&e, caller.clone(), delay_secs)?;
+        EmergencyDelayUpdated {
+            version: EVENT_VERSION,
+            by: caller,
+            delay_secs,
+        }
+        .publish(&e);
+        Ok(())
+    }
 
-        let order = OrderManager::create_order(
-            &e,
-            caller,
-            fiat_currency,
-            payment_method,
-            from_crypto,
-            amount,
-            exchange_rate,
-            duration_secs,
-        )?;
+    /// Number of ledgers archived order summaries are kept for.
+    pub fn set_archive_retention(
+        e: Env,
+        caller: Address,
+        ledgers: u32,
+    ) -> Result<(), ContractError> {
+        ArchiveManager::set_retention(&e, caller.clone(), ledgers)?;
+        ArchiveRetentionUpdated {
+            version: EVENT_VERSION,
+            by: caller,
+            ledgers,
+        }
+        .publish(&e);
+        Ok(())
+    }
 
-        OrderCreated {
-            order_id: order.order_id,
-            creator: order.creator,
-            amount: order.amount,
-            from_crypto: order.from_crypto,
+    pub fn trigger_emergency(e: Env, caller: Address) -> Result<(), ContractError> {
+        let emergency = AdminManager::trigger_emergency(&e, caller.clone())?;
+        EmergencyTriggered {
+            version: EVENT_VERSION,
+            by: caller,
+            triggered_at: emergency.triggered_at,
+            exit_at: emergency.exit_at,
         }
         .publish(&e);
+        Ok(())
+    }
 
-        Ok(order.order_id)
+    pub fn cancel_emergency(e: Env, caller: Address) -> Result<(), ContractError> {
+        AdminManager::cancel_emergency(&e, caller.clone())?;
+        EmergencyCancelled {
+            version: EVENT_VERSION,
+            by: caller,
+        }
+        .publish(&e);
+        Ok(())
     }
 
-    pub fn cancel_order(e: Env, caller: Address, order_id: u64) -> Result<(), ContractError> {
-        let order = OrderManager::cancel_order(&e, caller.clone(), order_id)?;
-        OrderCancelled {
+    pub fn emergency_withdraw(e: Env, caller: Address, order_id: u64) -> Result<(), ContractError> {
+        let (order, amount) = OrderManager::emergency_withdraw(&e, caller.clone(), order_id)?;
+        EmergencyWithdrawal {
+            version: EVENT_VERSION,
             order_id,
-            cancelled_by: caller,
+            recipient: caller,
+            token: order.token,
+            amount,
+            status: order.status,
         }
         .publish(&e);
+        Ok(())
+    }
 
-        let _ = order;
+    pub fn upgrade(
+        e: Env,
+        caller: Address,
+        new_wasm_hash: BytesN<32>,
+    ) -> Result<(), ContractError> {
+        MigrationManager::upgrade(&e, caller.clone(), new_wasm_hash.clone())?;
+        Upgraded {
+            version: EVENT_VERSION,
+            new_wasm_hash,
+            upgraded_by: caller,
+        }
+        .publish(&e);
         Ok(())
     }
 
-    pub fn take_order(e: Env, caller: Address, order_id: u64) -> Result<(), ContractError> {
-        let _order = OrderManager::take_order(&e, caller.clone(), order_id)?;
-        OrderTaken {
-            order_id,
-            filler: caller,
+    pub fn migrate(e: Env, caller: Address, limit: u32) -> Result<u32, ContractError> {
+        let (from_version, to_version) = MigrationManager::migrate(&e, caller, limit)?;
+        if from_version != to_version {
+            Migrated {
+                version: EVENT_VERSION,
+                from_version,
+                to_version,
+            }
+            .publish(&e);
+        }
+        Ok(to_version)
+    }
+
+    pub fn sweep_surplus(
+        e: Env,
+        caller: Address,
+        token: Address,
+        to: Address,
+    ) -> Result<i128, ContractError> {
+        let amount = LedgerManager::sweep_surplus(&e, caller.clone(), token.clone(), to.clone())?;
+        SurplusSwept {
+            version: EVENT_VERSION,
+            token,
+            to,
+            amount,
+            swept_by: caller,
+        }
+        .publish(&e);
+        Ok(amount)
+    }
+
+    pub fn set_currency(
+        e: Env,
+        caller: Address,
+        currency: FiatCurrency,
+        iso_code: String,
+        decimals: u32,
+        enabled: bool,
+    ) -> Result<(), ContractError> {
+        let info =
+            RegistryManager::set_currency(&e, caller, currency, iso_code, decimals, enabled)?;
+        CurrencyUpdated {
+            version: EVENT_VERSION,
+            currency: info.currency,
+            iso_code: info.iso_code,
+            decimals: info.decimals,
+            enabled: info.enabled,
         }
         .publish(&e);
         Ok(())
     }
 
-    pub fn take_order_with_amount(
+    pub fn set_payment_method(
         e: Env,
         caller: Address,
-        order_id: u64,
-        fill_amount: i128,
+        method: PaymentMethod,
+        name: String,
+        currencies: Vec<FiatCurrency>,
+        enabled: bool,
     ) -> Result<(), ContractError> {
-        let _order =
-            OrderManager::take_order_with_amount(&e, caller.clone(), order_id, fill_amount)?;
-        OrderTaken {
-            order_id,
-            filler: caller,
+        let info =
+            RegistryManager::set_payment_method(&e, caller, method, name, currencies, enabled)?;
+        PaymentMethodUpdated {
+            version: EVENT_VERSION,
+            method: info.method,
+            name: info.name,
+            currencies: info.currencies,
+            enabled: info.enabled,
         }
         .publish(&e);
         Ok(())
     }
 
-    pub fn submit_fiat_payment(
+    pub fn set_timeout_override(
         e: Env,
         caller: Address,
-        order_id: u64,
+        scope: TimeoutScope,
+        timeout_secs: Option<u64>,
     ) -> Result<(), ContractError> {
-        let _order = OrderManager::submit_fiat_payment(&e, caller.clone(), order_id)?;
-        FiatPaymentSubmitted {
-            order_id,
-            submitted_by: caller,
+        AdminManager::set_timeout_override(&e, caller, scope.clone(), timeout_secs)?;
+        TimeoutOverrideUpdated {
+            version: EVENT_VERSION,
+            scope,
+            timeout_secs,
         }
         .publish(&e);
         Ok(())
     }
 
-    pub fn execute_fiat_transfer_timeout(
+    pub fn set_timeout_bounds(
         e: Env,
         caller: Address,
-        order_id: u64,
+        min_secs: u64,
+        max_secs: u64,
     ) -> Result<(), ContractError> {
-        let (order, refund_amount) =
-            OrderManager::execute_fiat_transfer_timeout(&e, caller.clone(), order_id)?;
-        let (refunded_to, normalized_refund_amount) = if order.from_crypto {
-            (None, 0)
-        } else {
-            (Some(caller.clone()), refund_amount)
-        };
+        let bounds = AdminManager::set_timeout_bounds(&e, caller, min_secs, max_secs)?;
+        TimeoutBoundsUpdated {
+            version: EVENT_VERSION,
+            min_secs: bounds.min_secs,
+            max_secs: bounds.max_secs,
+        }
+        .publish(&e);
+        Ok(())
+    }
 
-        FiatTransferTimeout {
-            order_id,
-            executed_by: caller,
-            refunded_to,
-            refund_amount: normalized_refund_amount,
+    pub fn set_settlement_hold(
+        e: Env,
+        caller: Address,
+        payment_method: PaymentMethod,
+        hold_secs: Option<u64>,
+    ) -> Result<(), ContractError> {
+        AdminManager::set_settlement_hold(&e, caller, payment_method.clone(), hold_secs)?;
+        SettlementHoldUpdated {
+            version: EVENT_VERSION,
+            payment_method,
+            hold_secs,
         }
         .publish(&e);
         Ok(())
     }
 
-    pub fn confirm_fiat_payment(
+    pub fn set_max_fiat_extension(
         e: Env,
         caller: Address,
-        order_id: u64,
+        max_secs: u64,
     ) -> Result<(), ContractError> {
-        let _order = OrderManager::confirm_fiat_payment(&e, caller.clone(), order_id)?;
-        FiatPaymentConfirmed {
-            order_id,
-            confirmed_by: caller,
+        AdminManager::set_max_fiat_extension(&e, caller, max_secs)?;
+        MaxFiatExtensionUpdated {
+            version: EVENT_VERSION,
+            max_secs,
         }
         .publish(&e);
         Ok(())
     }
 
-    pub fn dispute_fiat_payment(
+    /// Settles new fills through per-fill Trustless Work escrows, or back
+    /// through the contract balance when `settings` is `None`.
+    pub fn set_escrow_settings(
         e: Env,
         caller: Address,
-        order_id: u64,
+        settings: Option<EscrowSettings>,
     ) -> Result<(), ContractError> {
-        let _order = DisputeManager::dispute_fiat_payment(&e, caller.clone(), order_id)?;
-        FiatPaymentDisputed {
-            order_id,
-            disputed_by: caller,
+        EscrowManager::set_settings(&e, caller, settings.clone())?;
+        EscrowSettingsUpdated {
+            version: EVENT_VERSION,
+            factory: settings.as_ref().map(|settings| settings.factory.clone()),
+            wasm_hash: settings.as_ref().map(|settings| settings.wasm_hash.clone()),
+            trustless_work: settings.map(|settings| settings.trustless_work),
         }
         .publish(&e);
         Ok(())
     }
 
-    pub fn resolve_dispute(
+    pub fn set_fee_config(
         e: Env,
         caller: Address,
-        order_id: u64,
-        fiat_transfer_confirmed: bool,
+        fee_config: FeeConfig,
     ) -> Result<(), ContractError> {
-        let _order =
-            DisputeManager::resolve_dispute(&e, caller.clone(), order_id, fiat_transfer_confirmed)?;
-        DisputeResolved {
+        FeeManager::set_fee_config(&e, caller, fee_config.clone())?;
+        FeeConfigUpdated {
+            version: EVENT_VERSION,
+            fee_bps: fee_config.fee_bps,
+            referral_share_bps: fee_config.referral_share_bps,
+            fee_recipient: fee_config.fee_recipient,
+        }
+        .publish(&e);
+        Ok(())
+    }
+
+    /// Binds the caller to the referrer who earns a share of the fees the
+    /// caller pays. A trader can register a referrer only once.
+    pub fn register_referrer(
+        e: Env,
+        caller: Address,
+        referrer: Address,
+    ) -> Result<(), ContractError> {
+        ReferralManager::register_referrer(&e, caller.clone(), referrer.clone())?;
+        ReferrerRegistered {
+            version: EVENT_VERSION,
+            trader: caller,
+            referrer,
+        }
+        .publish(&e);
+        Ok(())
+    }
+
+    pub fn claim_referral_rewards(e: Env, caller: Address) -> Result<i128, ContractError> {
+        let amount = ReferralManager::claim_referral_rewards(&e, caller.clone())?;
+        ReferralRewardsClaimed {
+            version: EVENT_VERSION,
+            referrer: caller,
+            amount,
+        }
+        .publish(&e);
+        Ok(amount)
+    }
+
+    pub fn set_insurance_config(
+        e: Env,
+        caller: Address,
+        insurance_config: InsuranceConfig,
+    ) -> Result<(), ContractError> {
+        InsuranceManager::set_insurance_config(&e, caller, insurance_config.clone())?;
+        InsuranceConfigUpdated {
+            version: EVENT_VERSION,
+            fee_share_bps: insurance_config.fee_share_bps,
+            approvers: insurance_config.approvers,
+            threshold: insurance_config.threshold,
+            max_claim: insurance_config.max_claim,
+        }
+        .publish(&e);
+        Ok(())
+    }
+
+    /// Claims up to the configured maximum from the insurance pool for a
+    /// loss on an order the caller created or filled.
+    pub fn file_insurance_claim(
+        e: Env,
+        caller: Address,
+        order_id: u64,
+        amount: i128,
+    ) -> Result<InsuranceClaim, ContractError> {
+        let claim = InsuranceManager::file_claim(&e, caller, order_id, amount)?;
+        InsuranceClaimFiled {
+            version: EVENT_VERSION,
             order_id,
-            resolved_by: caller,
-            fiat_transfer_confirmed,
+            claim_id: claim.claim_id,
+            claimant: claim.claimant.clone(),
+            amount,
+        }
+        .publish(&e);
+        Ok(claim)
+    }
+
+    /// Approves a pending claim. The approval that reaches the threshold
+    /// pays the claim out of the pool.
+    pub fn approve_insurance_claim(
+        e: Env,
+        caller: Address,
+        claim_id: u64,
+    ) -> Result<InsuranceClaim, ContractError> {
+        let claim = InsuranceManager::approve_claim(&e, caller.clone(), claim_id)?;
+        InsuranceClaimApproved {
+            version: EVENT_VERSION,
+            order_id: claim.order_id,
+            claim_id,
+            approver: caller,
+            approvals: claim.approvals.len(),
+        }
+        .publish(&e);
+        if claim.status == ClaimStatus::Paid {
+            publish_claim_closed(&e, &claim, claim.amount);
+        }
+        Ok(claim)
+    }
+
+    pub fn reject_insurance_claim(
+        e: Env,
+        caller: Address,
+        claim_id: u64,
+    ) -> Result<InsuranceClaim, ContractError> {
+        let claim = InsuranceManager::reject_claim(&e, caller, claim_id)?;
+        publish_claim_closed(&e, &claim, 0);
+        Ok(claim)
+    }
+
+    pub fn set_resolver_stake_config(
+        e: Env,
+        caller: Address,
+        stake_config: ResolverStakeConfig,
+    ) -> Result<(), ContractError> {
+        ResolverManager::set_stake_config(&e, caller, stake_config.clone())?;
+        ResolverStakeConfigUpdated {
+            version: EVENT_VERSION,
+            min_stake: stake_config.min_stake,
+            unbonding_secs: stake_config.unbonding_secs,
+            appeal_window_secs: stake_config.appeal_window_secs,
+            slash_bps: stake_config.slash_bps,
+            appeal_court: stake_config.appeal_court,
         }
         .publish(&e);
         Ok(())
     }
 
-    pub fn get_order(e: Env, order_id: u64) -> Result<Order, ContractError> {
-        OrderManager::get_order(&e, order_id)
+    pub fn stake_resolver(e: Env, caller: Address, amount: i128) -> Result<(), ContractError> {
+        ResolverManager::stake(&e, caller.clone(), amount)?;
+        publish_resolver_stake(&e, &caller);
+        Ok(())
     }
 
-    pub fn get_order_count(e: Env) -> Result<u64, ContractError> {
-        AdminManager::get_order_count(&e)
+    /// Starts the unbonding period for part of the caller's stake.
+    pub fn unbond_resolver_stake(
+        e: Env,
+        caller: Address,
+        amount: i128,
+    ) -> Result<(), ContractError> {
+        ResolverManager::unbond(&e, caller.clone(), amount)?;
+        publish_resolver_stake(&e, &caller);
+        Ok(())
     }
 
-    pub fn get_config(e: Env) -> Result<Config, ContractError> {
-        AdminManager::get_config(&e)
+    pub fn withdraw_resolver_stake(e: Env, caller: Address) -> Result<i128, ContractError> {
+        let amount = ResolverManager::withdraw(&e, caller.clone())?;
+        ResolverStakeWithdrawn {
+            version: EVENT_VERSION,
+            resolver: caller,
+            amount,
+        }
+        .publish(&e);
+        Ok(amount)
+    }
+
+    /// Grants or replaces an operator's scoped permissions on the caller's
+    /// orders.
+    pub fn set_operator(
+        e: Env,
+        owner: Address,
+        operator: Address,
+        grant: OperatorGrant,
+    ) -> Result<(), ContractError> {
+        OperatorManager::set_operator(&e, owner.clone(), operator.clone(), grant.clone())?;
+        OperatorUpdated {
+            version: EVENT_VERSION,
+            owner,
+            operator,
+            scopes: grant.scopes,
+            expires_at: grant.expires_at,
+            daily_volume_cap: grant.daily_volume_cap,
+        }
+        .publish(&e);
+        Ok(())
+    }
+
+    pub fn revoke_operator(e: Env, owner: Address, operator: Address) -> Result<(), ContractError> {
+        OperatorManager::revoke_operator(&e, owner.clone(), operator.clone())?;
+        OperatorRevoked {
+            version: EVENT_VERSION,
+            owner,
+            operator,
+        }
+        .publish(&e);
+        Ok(())
+    }
+
+    pub fn block_address(e: Env, caller: Address, address: Address) -> Result<(), ContractError> {
+        ComplianceManager::block_address(&e, caller.clone(), address.clone())?;
+        AddressBlocked {
+            version: EVENT_VERSION,
+            address,
+            blocked_by: caller,
+        }
+        .publish(&e);
+        Ok(())
+    }
+
+    pub fn unblock_address(e: Env, caller: Address, address: Address) -> Result<(), ContractError> {
+        ComplianceManager::unblock_address(&e, caller.clone(), address.clone())?;
+        AddressUnblocked {
+            version: EVENT_VERSION,
+            address,
+            unblocked_by: caller,
+        }
+        .publish(&e);
+        Ok(())
+    }
+
+    pub fn freeze_order(e: Env, caller: Address, order_id: u64) -> Result<(), ContractError> {
+        let order = ComplianceManager::freeze_order(&e, caller.clone(), order_id)?;
+        OrderFrozen {
+            version: EVENT_VERSION,
+            order_id,
+            frozen_by: caller,
+            status: order.status,
+        }
+        .publish(&e);
+        Ok(())
+    }
+
+    pub fn unfreeze_order(e: Env, caller: Address, order_id: u64) -> Result<(), ContractError> {
+        let order = ComplianceManager::unfreeze_order(&e, caller.clone(), order_id)?;
+        OrderUnfrozen {
+            version: EVENT_VERSION,
+            order_id,
+            unfrozen_by: caller,
+            status: order.status,
+        }
+        .publish(&e);
+        Ok(())
+    }
+
+    pub fn create_order(
+        e: Env,
+        caller: Address,
+        fiat_currency: FiatCurrency,
+        payment_method: PaymentMethod,
+        from_crypto: bool,
+        amount: i128,
+        exchange_rate: i128,
+        duration_secs: u64,
+        requested_timeout_secs: Option<u64>,
+        client_ref: Option<BytesN<32>>,
+        visibility: OrderVisibility,
+    ) -> Result<u64, ContractError> {
+        let params = OrderParams {
+            fiat_currency,
+            payment_method,
+            from_crypto,
+            amount,
+            exchange_rate,
+            duration_secs,
+            requested_timeout_secs,
+            client_ref: client_ref.clone(),
+            visibility,
+        };
+        let (order, created) = OrderManager::create_order(&e, caller.clone(), caller, params)?;
+
+        if created {
+            order_created(&order, client_ref).publish(&e);
+        }
+
+        Ok(order.order_id)
+    }
+
+    /// Creates an order for `owner` from one of its operators. `from_crypto`
+    /// deposits are drawn from the owner's token allowance to the contract.
+    pub fn create_order_for(
+        e: Env,
+        operator: Address,
+        owner: Address,
+        params: OrderParams,
+    ) -> Result<u64, ContractError> {
+        let client_ref = params.client_ref.clone();
+        let (order, created) = OrderManager::create_order(&e, operator, owner, params)?;
+
+        if created {
+            order_created(&order, client_ref).publish(&e);
+        }
+
+        Ok(order.order_id)
+    }
+
+    /// Creates several orders with a single deposit. Returns each item's
+    /// order id, `None` for items skipped when not `all_or_nothing`.
+    pub fn create_orders(
+        e: Env,
+        caller: Address,
+        orders: Vec<OrderParams>,
+        all_or_nothing: bool,
+    ) -> Result<Vec<Option<u64>>, ContractError> {
+        let (order_ids, created) =
+            OrderManager::create_orders(&e, caller, orders.clone(), all_or_nothing)?;
+
+        for order in created.iter() {
+            let client_ref = orders
+                .iter()
+                .zip(order_ids.iter())
+                .find(|(_, order_id)| *order_id == Some(order.order_id))
+                .and_then(|(params, _)| params.client_ref);
+            order_created(&order, client_ref).publish(&e);
+        }
+
+        Ok(order_ids)
+    }
+
+    pub fn create_order_cli(
+        e: Env,
+        caller: Address,
+        fiat_currency_code: u32,
+        payment_method_code: u32,
+        from_crypto: bool,
+        amount: i128,
+        exchange_rate: i128,
+        duration_secs: u64,
+    ) -> Result<u64, ContractError> {
+        let params = OrderParams {
+            fiat_currency: FiatCurrency::from_code(fiat_currency_code),
+            payment_method: PaymentMethod::from_code(payment_method_code),
+            from_crypto,
+            amount,
+            exchange_rate,
+            duration_secs,
+            requested_timeout_secs: None,
+            client_ref: None,
+            visibility: OrderVisibility::Public,
+        };
+        let (order, _) = OrderManager::create_order(&e, caller.clone(), caller, params)?;
+
+        order_created(&order, None).publish(&e);
+
+        Ok(order.order_id)
+    }
+
+    pub fn cancel_order(e: Env, caller: Address, order_id: u64) -> Result<(), ContractError> {
+        let (order, _) = OrderManager::cancel_order(&e, caller.clone(), order_id)?;
+        publish_order_cancelled(&e, &caller, order);
+        Ok(())
+    }
+
+    /// Cancels several orders with one refund per creator. Returns the ids
+    /// cancelled, which skip failing orders when not `all_or_nothing`.
+    pub fn cancel_orders(
+        e: Env,
+        caller: Address,
+        order_ids: Vec<u64>,
+        all_or_nothing: bool,
+    ) -> Result<Vec<u64>, ContractError> {
+        let cancelled = OrderManager::cancel_orders(&e, caller.clone(), order_ids, all_or_nothing)?;
+
+        let mut cancelled_ids = Vec::new(&e);
+        for order in cancelled.iter() {
+            cancelled_ids.push_back(order.order_id);
+            publish_order_cancelled(&e, &caller, order);
+        }
+        Ok(cancelled_ids)
+    }
+
+    /// Cancels the caller's open orders among the next `limit` order ids from
+    /// `cursor`. Returns the cursor to continue from, `None` when done.
+    pub fn cancel_all_my_orders(
+        e: Env,
+        caller: Address,
+        cursor: u64,
+        limit: u32,
+    ) -> Result<Option<u64>, ContractError> {
+        let (cancelled, next_cursor) =
+            OrderManager::cancel_all_my_orders(&e, caller.clone(), cursor, limit)?;
+
+        for order in cancelled.iter() {
+            publish_order_cancelled(&e, &caller, order);
+        }
+        Ok(next_cursor)
+    }
+
+    pub fn take_order(e: Env, caller: Address, order_id: u64) -> Result<(), ContractError> {
+        let order = OrderManager::take_order(&e, caller, order_id)?;
+        publish_order_taken(&e, &order, taker_deposit(&order))?;
+        Ok(())
+    }
+
+    pub fn take_order_with_amount(
+        e: Env,
+        caller: Address,
+        order_id: u64,
+        fill_amount: i128,
+    ) -> Result<(), ContractError> {
+        let order = OrderManager::take_order_with_amount(&e, caller, order_id, fill_amount, None)?;
+        publish_order_taken(&e, &order, taker_deposit(&order))?;
+        Ok(())
+    }
+
+    pub fn take_private_order(
+        e: Env,
+        caller: Address,
+        order_id: u64,
+        fill_amount: i128,
+        secret: Bytes,
+    ) -> Result<(), ContractError> {
+        let order =
+            OrderManager::take_order_with_amount(&e, caller, order_id, fill_amount, Some(secret))?;
+        publish_order_taken(&e, &order, taker_deposit(&order))?;
+        Ok(())
+    }
+
+    pub fn take_best_orders(
+        e: Env,
+        caller: Address,
+        fiat_currency: FiatCurrency,
+        payment_method: PaymentMethod,
+        from_crypto: bool,
+        total_amount: i128,
+        worst_rate: i128,
+        order_ids: Vec<u64>,
+    ) -> Result<Vec<OrderFill>, ContractError> {
+        let fills = OrderManager::take_best_orders(
+            &e,
+            caller,
+            fiat_currency,
+            payment_method,
+            from_crypto,
+            total_amount,
+            worst_rate,
+            order_ids,
+        )?;
+
+        for fill in fills.iter() {
+            let order = OrderManager::get_order(&e, fill.order_id)?;
+            publish_order_taken(&e, &order, taker_deposit(&order))?;
+        }
+
+        Ok(fills)
+    }
+
+    /// Posts a standing ad. Each take opens a single-fill order drawn from
+    /// the ad's inventory, which `from_crypto` ads deposit up front.
+    pub fn create_ad(e: Env, caller: Address, params: AdParams) -> Result<u64, ContractError> {
+        let ad = AdManager::create_ad(&e, caller, params)?;
+        AdCreated {
+            version: EVENT_VERSION,
+            ad_id: ad.ad_id,
+            merchant: ad.merchant,
+            from_crypto: ad.from_crypto,
+            fiat_currency: ad.fiat_currency,
+            payment_methods: ad.payment_methods,
+            exchange_rate: ad.exchange_rate,
+            inventory: ad.inventory,
+            min_fill: ad.min_fill,
+            max_fill: ad.max_fill,
+            status: ad.status,
+        }
+        .publish(&e);
+        Ok(ad.ad_id)
+    }
+
+    /// Takes `amount` from an ad and returns the id of the order opened for
+    /// the fill, which then settles like any taken order.
+    pub fn take_ad(
+        e: Env,
+        caller: Address,
+        ad_id: u64,
+        amount: i128,
+        payment_method: PaymentMethod,
+    ) -> Result<u64, ContractError> {
+        let (ad, order) = AdManager::take_ad(&e, caller.clone(), ad_id, amount, payment_method)?;
+        // The ad's inventory funds the order, so nothing is deposited for it.
+        OrderCreated {
+            deposited: 0,
+            status: OrderStatus::AwaitingFiller,
+            ..order_created(&order, None)
+        }
+        .publish(&e);
+        publish_order_taken(&e, &order, taker_deposit(&order))?;
+        AdFilled {
+            version: EVENT_VERSION,
+            ad_id,
+            order_id: order.order_id,
+            taker: caller,
+            amount,
+            inventory: ad.inventory,
+            status: ad.status,
+        }
+        .publish(&e);
+        Ok(order.order_id)
+    }
+
+    pub fn restock_ad(
+        e: Env,
+        caller: Address,
+        ad_id: u64,
+        amount: i128,
+    ) -> Result<(), ContractError> {
+        let ad = AdManager::restock_ad(&e, caller, ad_id, amount)?;
+        publish_ad_updated(&e, &ad);
+        Ok(())
+    }
+
+    pub fn pause_ad(e: Env, caller: Address, ad_id: u64) -> Result<(), ContractError> {
+        let ad = AdManager::pause_ad(&e, caller, ad_id)?;
+        publish_ad_updated(&e, &ad);
+        Ok(())
+    }
+
+    pub fn resume_ad(e: Env, caller: Address, ad_id: u64) -> Result<(), ContractError> {
+        let ad = AdManager::resume_ad(&e, caller, ad_id)?;
+        publish_ad_updated(&e, &ad);
+        Ok(())
+    }
+
+    pub fn close_ad(e: Env, caller: Address, ad_id: u64) -> Result<(), ContractError> {
+        let (ad, refund_amount) = AdManager::close_ad(&e, caller, ad_id)?;
+        AdClosed {
+            version: EVENT_VERSION,
+            ad_id,
+            merchant: ad.merchant,
+            refund_amount,
+        }
+        .publish(&e);
+        Ok(())
+    }
+
+    /// Offers to fill `amount` of an open order at `exchange_rate` for
+    /// `valid_for_secs`. Takers of orders that sell fiat deposit the crypto
+    /// with the offer. An order holds at most 20 open offers, 3 per taker;
+    /// a full order refunds its expired offers first.
+    pub fn make_offer(
+        e: Env,
+        caller: Address,
+        order_id: u64,
+        amount: i128,
+        exchange_rate: i128,
+        valid_for_secs: u64,
+    ) -> Result<u64, ContractError> {
+        let (offer, expired) =
+            OfferManager::make_offer(&e, caller, order_id, amount, exchange_rate, valid_for_secs)?;
+        for expired_offer in expired.iter() {
+            publish_offer_closed(&e, &expired_offer);
+        }
+        OfferMade {
+            version: EVENT_VERSION,
+            order_id,
+            offer_id: offer.offer_id,
+            taker: offer.taker,
+            amount: offer.amount,
+            exchange_rate: offer.exchange_rate,
+            deposited: offer.deposited,
+            expires_at: offer.expires_at,
+        }
+        .publish(&e);
+        Ok(offer.offer_id)
+    }
+
+    /// Creator only. Starts the offered fill at the offered rate.
+    pub fn accept_offer(e: Env, caller: Address, offer_id: u64) -> Result<(), ContractError> {
+        let (offer, order) = OfferManager::accept_offer(&e, caller, offer_id)?;
+        // The taker deposited when making the offer.
+        publish_order_taken(&e, &order, 0)?;
+        OfferAccepted {
+            version: EVENT_VERSION,
+            order_id: offer.order_id,
+            offer_id,
+            taker: offer.taker,
+            amount: offer.amount,
+            exchange_rate: offer.exchange_rate,
+        }
+        .publish(&e);
+        Ok(())
+    }
+
+    /// Creator only. Refunds the taker's deposit.
+    pub fn reject_offer(e: Env, caller: Address, offer_id: u64) -> Result<(), ContractError> {
+        let offer = OfferManager::reject_offer(&e, caller, offer_id)?;
+        publish_offer_closed(&e, &offer);
+        Ok(())
+    }
+
+    /// Anyone. Refunds an offer that expired or whose order no longer takes
+    /// fills.
+    pub fn refund_offer(e: Env, offer_id: u64) -> Result<(), ContractError> {
+        let offer = OfferManager::refund_offer(&e, offer_id)?;
+        publish_offer_closed(&e, &offer);
+        Ok(())
+    }
+
+    pub fn submit_fiat_payment(
+        e: Env,
+        caller: Address,
+        order_id: u64,
+    ) -> Result<(), ContractError> {
+        let order = OrderManager::submit_fiat_payment(&e, caller.clone(), order_id)?;
+        FiatPaymentSubmitted {
+            version: EVENT_VERSION,
+            order_id,
+            submitted_by: caller,
+            creator: order.creator,
+            filler: order.filler.ok_or(ContractError::MissingFiller)?,
+            fill_amount: order
+                .active_fill_amount
+                .ok_or(ContractError::MissingActiveFill)?,
+            status: order.status,
+        }
+        .publish(&e);
+        Ok(())
+    }
+
+    /// Lets the party who could claim the timeout give the fiat payer
+    /// more time, up to the configured total per fill.
+    pub fn extend_fiat_deadline(
+        e: Env,
+        caller: Address,
+        order_id: u64,
+        new_deadline: u64,
+    ) -> Result<(), ContractError> {
+        let (_, previous_deadline) =
+            OrderManager::extend_fiat_deadline(&e, caller.clone(), order_id, new_deadline)?;
+        FiatDeadlineExtended {
+            version: EVENT_VERSION,
+            order_id,
+            extended_by: caller,
+            previous_deadline,
+            new_deadline,
+        }
+        .publish(&e);
+        Ok(())
+    }
+
+    pub fn execute_fiat_transfer_timeout(
+        e: Env,
+        caller: Address,
+        order_id: u64,
+    ) -> Result<(), ContractError> {
+        let (order, settlement) =
+            OrderManager::execute_fiat_transfer_timeout(&e, caller.clone(), order_id)?;
+
+        FiatTransferTimeout {
+            version: EVENT_VERSION,
+            order_id,
+            executed_by: caller,
+            creator: order.creator,
+            filler: settlement.filler,
+            token: order.token,
+            refunded_to: settlement.paid_to,
+            refund_amount: settlement.amount,
+            remaining_amount: order.remaining_amount,
+            status: order.status,
+        }
+        .publish(&e);
+        Ok(())
+    }
+
+    pub fn confirm_fiat_payment(
+        e: Env,
+        caller: Address,
+        order_id: u64,
+    ) -> Result<(), ContractError> {
+        let (order, settlement) = OrderManager::confirm_fiat_payment(&e, caller.clone(), order_id)?;
+        let Some(settlement) = settlement else {
+            SettlementHeld {
+                version: EVENT_VERSION,
+                order_id,
+                confirmed_by: caller,
+                filler: order.filler.ok_or(ContractError::MissingFiller)?,
+                amount: order
+                    .active_fill_amount
+                    .ok_or(ContractError::MissingActiveFill)?,
+                release_at: OrderManager::get_settlement_release(&e, order_id)
+                    .ok_or(ContractError::InvalidOrderStatus)?,
+            }
+            .publish(&e);
+            return Ok(());
+        };
+
+        FiatPaymentConfirmed {
+            version: EVENT_VERSION,
+            order_id,
+            confirmed_by: caller,
+            creator: order.creator,
+            filler: settlement.filler,
+            token: order.token,
+            released_to: settlement.paid_to.ok_or(ContractError::MissingFiller)?,
+            amount_released: settlement.amount,
+            filled_amount: order.filled_amount,
+            remaining_amount: order.remaining_amount,
+            status: order.status,
+        }
+        .publish(&e);
+        publish_fee_charged(&e, order_id, settlement.fee);
+        Ok(())
+    }
+
+    /// Anyone. Releases a fill held in `Settling` once its hold is over.
+    pub fn finalize_settlement(e: Env, order_id: u64) -> Result<(), ContractError> {
+        let (order, settlement) = OrderManager::finalize_settlement(&e, order_id)?;
+        SettlementFinalized {
+            version: EVENT_VERSION,
+            order_id,
+            creator: order.creator,
+            filler: settlement.filler,
+            token: order.token,
+            released_to: settlement.paid_to.ok_or(ContractError::MissingFiller)?,
+            amount_released: settlement.amount,
+            filled_amount: order.filled_amount,
+            remaining_amount: order.remaining_amount,
+            status: order.status,
+        }
+        .publish(&e);
+        publish_fee_charged(&e, order_id, settlement.fee);
+        Ok(())
+    }
+
+    pub fn dispute_fiat_payment(
+        e: Env,
+        caller: Address,
+        order_id: u64,
+    ) -> Result<(), ContractError> {
+        let order = DisputeManager::dispute_fiat_payment(&e, caller.clone(), order_id)?;
+        FiatPaymentDisputed {
+            version: EVENT_VERSION,
+            order_id,
+            disputed_by: caller,
+            creator: order.creator,
+            filler: order.filler.ok_or(ContractError::MissingFiller)?,
+            fill_amount: order
+                .active_fill_amount
+                .ok_or(ContractError::MissingActiveFill)?,
+            status: order.status,
+        }
+        .publish(&e);
+        Ok(())
+    }
+
+    pub fn resolve_dispute(
+        e: Env,
+        caller: Address,
+        order_id: u64,
+        fiat_transfer_confirmed: bool,
+    ) -> Result<(), ContractError> {
+        let (order, settlement, ruling) =
+            DisputeManager::resolve_dispute(&e, caller.clone(), order_id, fiat_transfer_confirmed)?;
+        DisputeResolved {
+            version: EVENT_VERSION,
+            order_id,
+            resolved_by: caller,
+            fiat_transfer_confirmed,
+            creator: order.creator,
+            filler: settlement.filler,
+            token: order.token,
+            paid_to: settlement.paid_to,
+            amount: settlement.amount,
+            filled_amount: order.filled_amount,
+            remaining_amount: order.remaining_amount,
+            status: order.status,
+        }
+        .publish(&e);
+        publish_fee_charged(&e, order_id, settlement.fee);
+        if let Some(ruling) = ruling {
+            RulingRecorded {
+                version: EVENT_VERSION,
+                order_id,
+                ruling_id: ruling.ruling_id,
+                resolver: ruling.resolver,
+                losing_party: ruling.losing_party,
+                appeal_deadline: ruling.appeal_deadline,
+            }
+            .publish(&e);
+        }
+        Ok(())
+    }
+
+    /// Appeals a ruling against the caller before its appeal deadline.
+    pub fn appeal_ruling(e: Env, caller: Address, ruling_id: u64) -> Result<(), ContractError> {
+        let ruling = ResolverManager::appeal(&e, caller.clone(), ruling_id)?;
+        RulingAppealed {
+            version: EVENT_VERSION,
+            order_id: ruling.order_id,
+            ruling_id,
+            appellant: caller,
+        }
+        .publish(&e);
+        Ok(())
+    }
+
+    /// Admin or appeal court. Overturning a ruling slashes the resolver's
+    /// stake to the appellant; with an insurance pool configured, slashed
+    /// stake beyond the ruled amount goes to the pool instead. Returns the
+    /// amount slashed.
+    pub fn decide_appeal(
+        e: Env,
+        caller: Address,
+        ruling_id: u64,
+        overturn: bool,
+    ) -> Result<i128, ContractError> {
+        let (ruling, slashed, forfeited) =
+            ResolverManager::decide_appeal(&e, caller.clone(), ruling_id, overturn)?;
+        AppealDecided {
+            version: EVENT_VERSION,
+            order_id: ruling.order_id,
+            ruling_id,
+            decided_by: caller,
+            status: ruling.status,
+            resolver: ruling.resolver.clone(),
+            slashed,
+            paid_to: ruling.losing_party,
+        }
+        .publish(&e);
+        publish_resolver_stake(&e, &ruling.resolver);
+        if forfeited > 0 {
+            let config = AdminManager::get_config(&e)?;
+            InsuranceFunded {
+                version: EVENT_VERSION,
+                order_id: ruling.order_id,
+                pool_balance: InsuranceManager::get_pool_balance(&e, config.token.clone()),
+                token: config.token,
+                amount: forfeited,
+            }
+            .publish(&e);
+        }
+        Ok(slashed)
+    }
+
+    /// Compacts a completed, cancelled or refunded order into its summary,
+    /// refunding offers still open on it. Callable by anyone.
+    pub fn archive_order(e: Env, order_id: u64) -> Result<(), ContractError> {
+        let (summary, refunded) = ArchiveManager::archive_order(&e, order_id)?;
+        for offer in refunded.iter() {
+            publish_offer_closed(&e, &offer);
+        }
+        OrderArchived {
+            version: EVENT_VERSION,
+            order_id,
+            status: summary.status,
+            archived_at: summary.archived_at,
+        }
+        .publish(&e);
+        Ok(())
+    }
+
+    /// Returns archived orders rebuilt from their summary.
+    pub fn get_order(e: Env, order_id: u64) -> Result<Order, ContractError> {
+        ArchiveManager::find_order(&e, order_id)
+    }
+
+    /// Every fill of an order, oldest first, kept after the order is
+    /// archived.
+    pub fn get_order_fills(e: Env, order_id: u64) -> Vec<FillRecord> {
+        FillHistoryManager::get_order_fills(&e, order_id)
+    }
+
+    /// Settled volume, trade count, last rate and recent hourly volume
+    /// weighted rates for a token and fiat currency.
+    pub fn get_market_stats(e: Env, token: Address, currency: FiatCurrency) -> Option<MarketStats> {
+        MarketStatsManager::get_market_stats(&e, token, currency)
+    }
+
+    pub fn get_order_summary(e: Env, order_id: u64) -> Result<OrderSummary, ContractError> {
+        ArchiveManager::get_order_summary(&e, order_id)
+    }
+
+    pub fn get_archive_retention(e: Env) -> u32 {
+        ArchiveManager::get_retention(&e)
+    }
+
+    pub fn get_order_by_ref(
+        e: Env,
+        creator: Address,
+        client_ref: BytesN<32>,
+    ) -> Result<Order, ContractError> {
+        OrderManager::get_order_by_ref(&e, creator, client_ref)
+    }
+
+    pub fn get_open_orders(e: Env, start: u64, limit: u32) -> Result<Vec<Order>, ContractError> {
+        OrderManager::get_open_orders(&e, start, limit)
+    }
+
+    pub fn get_order_count(e: Env) -> Result<u64, ContractError> {
+        AdminManager::get_order_count(&e)
+    }
+
+    pub fn get_config(e: Env) -> Result<Config, ContractError> {
+        AdminManager::get_config(&e)
+    }
+
+    pub fn get_pause_flags(e: Env) -> PauseFlags {
+        AdminManager::get_pause_flags(&e)
+    }
+
+    pub fn get_emergency(e: Env) -> Option<EmergencyState> {
+        AdminManager::get_emergency(&e)
+    }
+
+    pub fn get_schema_version(e: Env) -> u32 {
+        MigrationManager::get_schema_version(&e)
+    }
+
+    pub fn get_currencies(e: Env) -> Vec<CurrencyInfo> {
+        RegistryManager::get_currencies(&e)
+    }
+
+    pub fn get_payment_methods(e: Env) -> Vec<PaymentMethodInfo> {
+        RegistryManager::get_payment_methods(&e)
+    }
+
+    pub fn get_timeout_override(e: Env, scope: TimeoutScope) -> Option<u64> {
+        AdminManager::get_timeout_override(&e, scope)
+    }
+
+    pub fn get_settlement_hold(e: Env, payment_method: PaymentMethod) -> Option<u64> {
+        AdminManager::get_settlement_hold(&e, &payment_method)
+    }
+
+    /// When a fill in `Settling` can be finalized.
+    pub fn get_settlement_release(e: Env, order_id: u64) -> Option<u64> {
+        OrderManager::get_settlement_release(&e, order_id)
+    }
+
+    pub fn get_max_fiat_extension(e: Env) -> u64 {
+        AdminManager::get_max_fiat_extension(&e)
+    }
+
+    pub fn get_timeout_bounds(e: Env) -> Option<TimeoutBounds> {
+        AdminManager::get_timeout_bounds(&e)
+    }
+
+    pub fn get_payment_timeout(
+        e: Env,
+        payment_method: PaymentMethod,
+        fiat_currency: FiatCurrency,
+    ) -> Result<u64, ContractError> {
+        AdminManager::get_payment_timeout(&e, &payment_method, &fiat_currency)
+    }
+
+    pub fn get_escrow_settings(e: Env) -> Option<EscrowSettings> {
+        EscrowManager::get_settings(&e)
+    }
+
+    pub fn get_fill_escrow(e: Env, order_id: u64) -> Option<FillEscrow> {
+        EscrowManager::get_fill_escrow(&e, order_id)
+    }
+
+    pub fn get_offer(e: Env, offer_id: u64) -> Result<Offer, ContractError> {
+        OfferManager::get_offer(&e, offer_id)
+    }
+
+    /// Open offers on an order, oldest first.
+    pub fn get_order_offers(e: Env, order_id: u64) -> Vec<Offer> {
+        OfferManager::get_order_offers(&e, order_id)
+    }
+
+    pub fn get_ad(e: Env, ad_id: u64) -> Result<MerchantAd, ContractError> {
+        AdManager::get_ad(&e, ad_id)
+    }
+
+    pub fn get_merchant_ads(e: Env, merchant: Address) -> Vec<MerchantAd> {
+        AdManager::get_merchant_ads(&e, merchant)
+    }
+
+    /// The ad an order was opened for, if any.
+    pub fn get_order_ad(e: Env, order_id: u64) -> Option<u64> {
+        AdManager::get_order_ad(&e, order_id)
+    }
+
+    /// Disputes open on the merchant's ad fills.
+    pub fn get_merchant_open_disputes(e: Env, merchant: Address) -> u32 {
+        AdManager::get_open_disputes(&e, merchant)
+    }
+
+    pub fn get_fee_config(e: Env) -> Option<FeeConfig> {
+        FeeManager::get_fee_config(&e)
+    }
+
+    pub fn get_referrer(e: Env, trader: Address) -> Option<Address> {
+        ReferralManager::get_referrer(&e, trader)
+    }
+
+    /// Traders referred, fees earned to date and the balance left to claim.
+    pub fn get_referral_stats(e: Env, referrer: Address) -> ReferralStats {
+        ReferralManager::get_referral_stats(&e, referrer)
+    }
+
+    pub fn get_insurance_config(e: Env) -> Option<InsuranceConfig> {
+        InsuranceManager::get_insurance_config(&e)
+    }
+
+    pub fn get_insurance_pool(e: Env, token: Address) -> i128 {
+        InsuranceManager::get_pool_balance(&e, token)
+    }
+
+    pub fn get_insurance_claim(e: Env, claim_id: u64) -> Result<InsuranceClaim, ContractError> {
+        InsuranceManager::get_claim(&e, claim_id)
+    }
+
+    /// Every insurance claim filed on the order, oldest first.
+    pub fn get_order_claims(e: Env, order_id: u64) -> Vec<InsuranceClaim> {
+        InsuranceManager::get_order_claims(&e, order_id)
+    }
+
+    pub fn get_resolver_stake_config(e: Env) -> Option<ResolverStakeConfig> {
+        ResolverManager::get_stake_config(&e)
+    }
+
+    pub fn get_resolver_stake(e: Env, resolver: Address) -> ResolverStake {
+        ResolverManager::get_stake(&e, resolver)
+    }
+
+    /// Whether `resolver` can currently rule on disputes. Contracts that
+    /// share the dispute resolver can call this before accepting a ruling.
+    pub fn is_eligible_resolver(e: Env, resolver: Address) -> bool {
+        ResolverManager::is_eligible(&e, resolver)
+    }
+
+    pub fn get_ruling(e: Env, ruling_id: u64) -> Result<Ruling, ContractError> {
+        ResolverManager::get_ruling(&e, ruling_id)
+    }
+
+    pub fn get_operator(e: Env, owner: Address, operator: Address) -> Option<OperatorGrant> {
+        OperatorManager::get_operator(&e, owner, operator)
+    }
+
+    pub fn get_operators(e: Env, owner: Address) -> Vec<Address> {
+        OperatorManager::get_operators(&e, owner)
+    }
+
+    /// Volume the operator used on its last active UTC day.
+    pub fn get_operator_usage(e: Env, owner: Address, operator: Address) -> OperatorUsage {
+        OperatorManager::get_usage(&e, owner, operator)
+    }
+
+    pub fn get_circuit_breaker_config(e: Env) -> Option<BreakerConfig> {
+        CircuitBreakerManager::get_config(&e)
+    }
+
+    /// Outcome counts in the currency's current window and, if takes are
+    /// suspended, when the breaker tripped.
+    pub fn get_circuit_breaker_status(e: Env, currency: FiatCurrency) -> BreakerStatus {
+        CircuitBreakerManager::get_status(&e, currency)
+    }
+
+    pub fn check_solvency(e: Env, token: Address) -> SolvencyReport {
+        LedgerManager::check_solvency(&e, token)
+    }
+
+    pub fn is_blocked(e: Env, address: Address) -> bool {
+        ComplianceManager::is_blocked(&e, address)
+    }
+}
+
+fn order_created(order: &Order, client_ref: Option<BytesN<32>>) -> OrderCreated {
+    let deposited = if order.from_crypto { order.amount } else { 0 };
+    OrderCreated {
+        version: EVENT_VERSION,
+        order_id: order.order_id,
+        creator: order.creator.clone(),
+        token: order.token.clone(),
+        amount: order.amount,
+        deposited,
+        exchange_rate: order.exchange_rate,
+        from_crypto: order.from_crypto,
+        fiat_currency: order.fiat_currency.clone(),
+        payment_method: order.payment_method.clone(),
+        deadline: order.deadline,
+        status: order.status.clone(),
+        client_ref,
+    }
+}
+
+/// Builds the event for an order that was just taken. The filler's deposit
+/// is the fill amount unless the creator already escrowed the crypto.
+/// What the taker deposits when taking `order`: the fill itself unless the
+/// creator already deposited the crypto.
+fn taker_deposit(order: &Order) -> i128 {
+    if order.from_crypto {
+        0
+    } else {
+        order.active_fill_amount.unwrap_or_default()
+    }
+}
+
+fn order_taken(order: &Order, deposited: i128) -> Result<OrderTaken, ContractError> {
+    let fill_amount = order
+        .active_fill_amount
+        .ok_or(ContractError::MissingActiveFill)?;
+    Ok(OrderTaken {
+        version: EVENT_VERSION,
+        order_id: order.order_id,
+        creator: order.creator.clone(),
+        filler: order.filler.clone().ok_or(ContractError::MissingFiller)?,
+        token: order.token.clone(),
+        fill_amount,
+        deposited,
+        remaining_amount: order.remaining_amount,
+        fiat_transfer_deadline: order.fiat_transfer_deadline.unwrap_or_default(),
+        status: order.status.clone(),
+    })
+}
+
+fn publish_order_taken(e: &Env, order: &Order, deposited: i128) -> Result<(), ContractError> {
+    order_taken(order, deposited)?.publish(e);
+    if let Some(fill_escrow) = EscrowManager::get_fill_escrow(e, order.order_id) {
+        FillEscrowOpened {
+            version: EVENT_VERSION,
+            order_id: order.order_id,
+            escrow: fill_escrow.escrow,
+            amount: order.active_fill_amount.unwrap_or_default(),
+        }
+        .publish(e);
+    }
+    Ok(())
+}
+
+fn publish_fee_charged(e: &Env, order_id: u64, fee: Option<FeeCharge>) {
+    if let Some(fee) = fee {
+        FeeCharged {
+            version: EVENT_VERSION,
+            order_id,
+            payer: fee.payer,
+            fee: fee.fee,
+            referrer: fee.referrer,
+            referral_amount: fee.referral_amount,
+        }
+        .publish(e);
+        if fee.insurance_amount > 0 {
+            InsuranceFunded {
+                version: EVENT_VERSION,
+                order_id,
+                pool_balance: InsuranceManager::get_pool_balance(e, fee.token.clone()),
+                token: fee.token,
+                amount: fee.insurance_amount,
+            }
+            .publish(e);
+        }
+    }
+}
+
+fn publish_order_cancelled(e: &Env, caller: &Address, order: Order) {
+    let refund_amount = OrderManager::cancel_refund(&order);
+    let refunded_to = if refund_amount > 0 {
+        Some(order.creator)
+    } else {
+        None
+    };
+
+    OrderCancelled {
+        version: EVENT_VERSION,
+        order_id: order.order_id,
+        cancelled_by: caller.clone(),
+        token: order.token,
+        refunded_to,
+        refund_amount,
+        status: order.status,
+    }
+    .publish(e);
+}
+
+fn publish_resolver_stake(e: &Env, resolver: &Address) {
+    let stake = ResolverManager::get_stake(e, resolver.clone());
+    ResolverStakeUpdated {
+        version: EVENT_VERSION,
+        resolver: resolver.clone(),
+        staked: stake.staked,
+        unbonding: stake.unbonding,
+        unbonds_at: stake.unbonds_at,
+    }
+    .publish(e);
+}
+
+fn publish_claim_closed(e: &Env, claim: &InsuranceClaim, amount_paid: i128) {
+    InsuranceClaimClosed {
+        version: EVENT_VERSION,
+        order_id: claim.order_id,
+        claim_id: claim.claim_id,
+        claimant: claim.claimant.clone(),
+        amount_paid,
+        status: claim.status.clone(),
+        pool_balance: InsuranceManager::get_pool_balance(e, claim.token.clone()),
+    }
+    .publish(e);
+}
+
+fn publish_ad_updated(e: &Env, ad: &MerchantAd) {
+    AdUpdated {
+        version: EVENT_VERSION,
+        ad_id: ad.ad_id,
+        inventory: ad.inventory,
+        status: ad.status.clone(),
+    }
+    .publish(e);
+}
+
+fn publish_offer_closed(e: &Env, offer: &Offer) {
+    OfferClosed {
+        version: EVENT_VERSION,
+        order_id: offer.order_id,
+        offer_id: offer.offer_id,
+        taker: offer.taker.clone(),
+        refund_amount: offer.deposited,
+        status: offer.status.clone(),
     }
+    .publish(e);
 }
@@ -0,0 +1,338 @@
+use soroban_sdk::{Address, Env, Vec};
+
+use crate::core::admin::AdminManager;
+use crate::core::ledger::LedgerManager;
+use crate::core::order::OrderManager;
+use crate::core::validators::ad::{
+    ensure_ad_active, ensure_ad_open, ensure_merchant, ensure_within_active_hours,
+    validate_ad_fill, validate_ad_params,
+};
+use crate::core::validators::admin::{ensure_not_paused, PausableOperation};
+use crate::core::validators::compliance::ensure_not_blocked;
+use crate::error::ContractError;
+use crate::storage::types::{
+    AdParams, AdStatus, DataKey, MerchantAd, Order, OrderStatus, PaymentMethod,
+};
+
+/// Open ads a merchant can run at once, which bounds the ads paused when
+/// their disputes pile up. Posting more fails with `TooManyAds`.
+const MAX_ADS_PER_MERCHANT: u32 = 10;
+
+/// Standing merchant ads. Each take opens a single-fill order for the taker
+/// and draws its amount from the ad's inventory, so fills settle, time out
+/// and get disputed like any other order. Fills that do not complete go
+/// back to the inventory. Ads pause themselves when the inventory cannot
+/// cover the minimum fill or the merchant has more open disputes on ad
+/// fills than the ad allows.
+pub struct AdManager;
+
+impl AdManager {
+    pub fn create_ad(
+        e: &Env,
+        caller: Address,
+        params: AdParams,
+    ) -> Result<MerchantAd, ContractError> {
+        caller.require_auth();
+        let config = AdminManager::get_config(e)?;
+        ensure_not_paused(e, &config, PausableOperation::NewOrders)?;
+        ensure_not_blocked(e, &caller)?;
+        validate_ad_params(e, &params)?;
+
+        let mut ad_ids = Self::merchant_ad_ids(e, &caller);
+        if ad_ids.len() >= MAX_ADS_PER_MERCHANT {
+            return Err(ContractError::TooManyAds);
+        }
+
+        if params.from_crypto {
+            LedgerManager::deposit(e, &config.token, &caller, params.inventory)?;
+        }
+
+        let ad_id: u64 = e.storage().instance().get(&DataKey::AdCount).unwrap_or(0);
+        let next = ad_id.checked_add(1).ok_or(ContractError::Overflow)?;
+        e.storage().instance().set(&DataKey::AdCount, &next);
+
+        let mut ad = MerchantAd {
+            ad_id,
+            merchant: caller.clone(),
+            token: config.token,
+            from_crypto: params.from_crypto,
+            fiat_currency: params.fiat_currency,
+            payment_methods: params.payment_methods,
+            exchange_rate: params.exchange_rate,
+            inventory: params.inventory,
+            min_fill: params.min_fill,
+            max_fill: params.max_fill,
+            active_from_secs: params.active_from_secs,
+            active_to_secs: params.active_to_secs,
+            max_open_disputes: params.max_open_disputes,
+            status: AdStatus::Active,
+            created_at: e.ledger().timestamp(),
+        };
+        Self::refresh_status(e, &mut ad);
+        Self::store_ad(e, &ad);
+
+        ad_ids.push_back(ad_id);
+        Self::store_merchant_ad_ids(e, caller, &ad_ids);
+
+        Ok(ad)
+    }
+
+    /// Opens and takes an order for `amount` of the ad's inventory.
+    pub fn take_ad(
+        e: &Env,
+        caller: Address,
+        ad_id: u64,
+        amount: i128,
+        payment_method: PaymentMethod,
+    ) -> Result<(MerchantAd, Order), ContractError> {
+        caller.require_auth();
+        let config = AdminManager::get_config(e)?;
+        ensure_not_paused(e, &config, PausableOperation::Takes)?;
+
+        let mut ad = Self::get_ad(e, ad_id)?;
+        ensure_ad_active(&ad)?;
+        ensure_not_blocked(e, &ad.merchant)?;
+        ensure_within_active_hours(&ad, e.ledger().timestamp())?;
+        validate_ad_fill(&ad, amount, &payment_method)?;
+
+        let order = OrderManager::open_ad_fill(e, &config, &ad, caller, payment_method, amount)?;
+        e.storage()
+            .instance()
+            .set(&DataKey::AdOrder(order.order_id), &ad_id);
+
+        ad.inventory = ad
+            .inventory
+            .checked_sub(amount)
+            .ok_or(ContractError::Underflow)?;
+        Self::refresh_status(e, &mut ad);
+        Self::store_ad(e, &ad);
+
+        Ok((ad, order))
+    }
+
+    /// Adds inventory, reactivating an ad that ran out.
+    pub fn restock_ad(
+        e: &Env,
+        caller: Address,
+        ad_id: u64,
+        amount: i128,
+    ) -> Result<MerchantAd, ContractError> {
+        caller.require_auth();
+        let config = AdminManager::get_config(e)?;
+        ensure_not_paused(e, &config, PausableOperation::NewOrders)?;
+
+        let mut ad = Self::get_ad(e, ad_id)?;
+        ensure_merchant(&ad, &caller)?;
+        ensure_ad_open(&ad)?;
+        if amount <= 0 {
+            return Err(ContractError::InvalidAmount);
+        }
+
+        if ad.from_crypto {
+            LedgerManager::deposit(e, &config.token, &caller, amount)?;
+        }
+        ad.inventory = ad
+            .inventory
+            .checked_add(amount)
+            .ok_or(ContractError::Overflow)?;
+        Self::refresh_status(e, &mut ad);
+        Self::store_ad(e, &ad);
+
+        Ok(ad)
+    }
+
+    pub fn pause_ad(e: &Env, caller: Address, ad_id: u64) -> Result<MerchantAd, ContractError> {
+        caller.require_auth();
+
+        let mut ad = Self::get_ad(e, ad_id)?;
+        ensure_merchant(&ad, &caller)?;
+        ensure_ad_active(&ad)?;
+
+        ad.status = AdStatus::PausedByMerchant;
+        Self::store_ad(e, &ad);
+
+        Ok(ad)
+    }
+
+    /// Reactivates a paused ad once it has inventory for a minimum fill and
+    /// the merchant's open disputes are within the ad's limit.
+    pub fn resume_ad(e: &Env, caller: Address, ad_id: u64) -> Result<MerchantAd, ContractError> {
+        caller.require_auth();
+
+        let mut ad = Self::get_ad(e, ad_id)?;
+        ensure_merchant(&ad, &caller)?;
+        ensure_ad_open(&ad)?;
+        if ad.inventory < ad.min_fill {
+            return Err(ContractError::AdOutOfInventory);
+        }
+        if Self::get_open_disputes(e, ad.merchant.clone()) > ad.max_open_disputes {
+            return Err(ContractError::TooManyOpenDisputes);
+        }
+
+        ad.status = AdStatus::Active;
+        Self::store_ad(e, &ad);
+
+        Ok(ad)
+    }
+
+    /// Closes the ad and returns a `from_crypto` ad's inventory. Fills
+    /// already taken settle normally; any that come back are refunded.
+    pub fn close_ad(
+        e: &Env,
+        caller: Address,
+        ad_id: u64,
+    ) -> Result<(MerchantAd, i128), ContractError> {
+        caller.require_auth();
+        let config = AdminManager::get_config(e)?;
+
+        let mut ad = Self::get_ad(e, ad_id)?;
+        ensure_merchant(&ad, &caller)?;
+        ensure_ad_open(&ad)?;
+
+        let refund_amount = if ad.from_crypto { ad.inventory } else { 0 };
+        if refund_amount > 0 {
+            LedgerManager::payout(e, &config.token, &ad.merchant, refund_amount)?;
+        }
+
+        ad.inventory = 0;
+        ad.status = AdStatus::Closed;
+        Self::store_ad(e, &ad);
+
+        let mut ad_ids = Self::merchant_ad_ids(e, &ad.merchant);
+        if let Some(index) = ad_ids.first_index_of(ad_id) {
+            ad_ids.remove(index);
+        }
+        Self::store_merchant_ad_ids(e, ad.merchant.clone(), &ad_ids);
+
+        Ok((ad, refund_amount))
+    }
+
+    /// Cancels an ad fill's order that reopened after a timeout or a lost
+    /// dispute and returns its amount to the ad, or to the merchant once
+    /// the ad is closed. Other orders are left alone.
+    pub fn return_fill(e: &Env, order: &mut Order) -> Result<(), ContractError> {
+        if order.status != OrderStatus::AwaitingFiller {
+            return Ok(());
+        }
+        let Some(ad_id) = Self::get_order_ad(e, order.order_id) else {
+            return Ok(());
+        };
+
+        let mut ad = Self::get_ad(e, ad_id)?;
+        if ad.status == AdStatus::Closed {
+            if ad.from_crypto && order.remaining_amount > 0 {
+                LedgerManager::payout(e, &order.token, &ad.merchant, order.remaining_amount)?;
+            }
+        } else {
+            ad.inventory = ad
+                .inventory
+                .checked_add(order.remaining_amount)
+                .ok_or(ContractError::Overflow)?;
+            Self::refresh_status(e, &mut ad);
+            Self::store_ad(e, &ad);
+        }
+
+        order.status = OrderStatus::Cancelled;
+        Ok(())
+    }
+
+    /// Counts a dispute on an ad fill against the merchant and pauses the
+    /// merchant's ads whose limit it passes.
+    pub fn dispute_opened(e: &Env, order: &Order) {
+        if Self::get_order_ad(e, order.order_id).is_none() {
+            return;
+        }
+
+        let open_disputes = Self::get_open_disputes(e, order.creator.clone()).saturating_add(1);
+        Self::store_open_disputes(e, order.creator.clone(), open_disputes);
+
+        for ad_id in Self::merchant_ad_ids(e, &order.creator).iter() {
+            if let Ok(mut ad) = Self::get_ad(e, ad_id) {
+                if ad.status == AdStatus::Active && open_disputes > ad.max_open_disputes {
+                    ad.status = AdStatus::TooManyDisputes;
+                    Self::store_ad(e, &ad);
+                }
+            }
+        }
+    }
+
+    /// Ads paused for disputes stay paused until the merchant resumes them.
+    pub fn dispute_closed(e: &Env, order: &Order) {
+        if Self::get_order_ad(e, order.order_id).is_none() {
+            return;
+        }
+
+        let open_disputes = Self::get_open_disputes(e, order.creator.clone()).saturating_sub(1);
+        Self::store_open_disputes(e, order.creator.clone(), open_disputes);
+    }
+
+    pub fn get_ad(e: &Env, ad_id: u64) -> Result<MerchantAd, ContractError> {
+        e.storage()
+            .instance()
+            .get(&DataKey::Ad(ad_id))
+            .ok_or(ContractError::AdNotFound)
+    }
+
+    /// The merchant's ads that are not closed.
+    pub fn get_merchant_ads(e: &Env, merchant: Address) -> Vec<MerchantAd> {
+        let mut ads = Vec::new(e);
+        for ad_id in Self::merchant_ad_ids(e, &merchant).iter() {
+            if let Ok(ad) = Self::get_ad(e, ad_id) {
+                ads.push_back(ad);
+            }
+        }
+        ads
+    }
+
+    pub fn get_order_ad(e: &Env, order_id: u64) -> Option<u64> {
+        e.storage().instance().get(&DataKey::AdOrder(order_id))
+    }
+
+    pub fn get_open_disputes(e: &Env, merchant: Address) -> u32 {
+        e.storage()
+            .persistent()
+            .get(&DataKey::MerchantOpenDisputes(merchant))
+            .unwrap_or(0)
+    }
+
+    /// Moves an ad between `Active` and its automatic pauses. Pauses set by
+    /// the merchant and closed ads are left as they are.
+    fn refresh_status(e: &Env, ad: &mut MerchantAd) {
+        if ad.status != AdStatus::Active && ad.status != AdStatus::OutOfInventory {
+            return;
+        }
+
+        ad.status = if ad.inventory < ad.min_fill {
+            AdStatus::OutOfInventory
+        } else if Self::get_open_disputes(e, ad.merchant.clone()) > ad.max_open_disputes {
+            AdStatus::TooManyDisputes
+        } else {
+            AdStatus::Active
+        };
+    }
+
+    fn merchant_ad_ids(e: &Env, merchant: &Address) -> Vec<u64> {
+        e.storage()
+            .persistent()
+            .get(&DataKey::MerchantAds(merchant.clone()))
+            .unwrap_or(Vec::new(e))
+    }
+
+    fn store_merchant_ad_ids(e: &Env, merchant: Address, ad_ids: &Vec<u64>) {
+        let key = DataKey::MerchantAds(merchant);
+        let max_ttl = e.storage().max_ttl();
+        e.storage().persistent().set(&key, ad_ids);
+        e.storage().persistent().extend_ttl(&key, max_ttl, max_ttl);
+    }
+
+    fn store_open_disputes(e: &Env, merchant: Address, open_disputes: u32) {
+        let key = DataKey::MerchantOpenDisputes(merchant);
+        let max_ttl = e.storage().max_ttl();
+        e.storage().persistent().set(&key, &open_disputes);
+        e.storage().persistent().extend_ttl(&key, max_ttl, max_ttl);
+    }
+
+    fn store_ad(e: &Env, ad: &MerchantAd) {
+        e.storage().instance().set(&DataKey::Ad(ad.ad_id), ad);
+    }
+}
@@ -1,8 +1,20 @@
 use soroban_sdk::{Address, Env};
 
-use crate::core::validators::admin::{ensure_pauser, validate_initialize_inputs};
+use crate::core::migration::MigrationManager;
+use crate::core::registry::RegistryManager;
+use crate::core::validators::admin::{
+    ensure_admin, ensure_pauser, validate_initialize_inputs, validate_max_fiat_extension,
+    validate_settlement_hold, validate_timeout_bounds,
+};
 use crate::error::ContractError;
-use crate::storage::types::{Config, DataKey};
+use crate::storage::types::{
+    Config, DataKey, EmergencyKey, EmergencyState, FiatCurrency, PauseFlags, PaymentMethod,
+    TimeoutBounds, TimeoutScope,
+};
+
+/// Time between the pauser triggering emergency mode and depositors being
+/// able to withdraw, unless the admin configures another delay.
+const DEFAULT_EMERGENCY_DELAY_SECS: u64 = 86_400;
 
 pub struct AdminManager;
 
@@ -12,6 +24,7 @@ impl AdminManager {
         admin: Address,
         dispute_resolver: Address,
         pauser: Address,
+        compliance: Address,
         token: Address,
         max_duration_secs: u64,
         filler_payment_timeout_secs: u64,
@@ -26,6 +39,7 @@ impl AdminManager {
             admin,
             dispute_resolver,
             pauser,
+            compliance,
             token,
             max_duration_secs,
             filler_payment_timeout_secs,
@@ -34,6 +48,8 @@ impl AdminManager {
 
         e.storage().instance().set(&DataKey::Config, &config);
         e.storage().instance().set(&DataKey::OrderCount, &0u64);
+        RegistryManager::seed_defaults(e);
+        MigrationManager::set_current_schema_version(e);
 
         Ok(config)
     }
@@ -68,6 +84,223 @@ impl AdminManager {
         Ok(())
     }
 
+    pub fn set_pause_flags(
+        e: &Env,
+        caller: Address,
+        flags: PauseFlags,
+    ) -> Result<(), ContractError> {
+        caller.require_auth();
+        let config = Self::get_config(e)?;
+        ensure_pauser(&config, &caller)?;
+
+        e.storage().instance().set(&DataKey::PauseFlags, &flags);
+
+        Ok(())
+    }
+
+    pub fn get_pause_flags(e: &Env) -> PauseFlags {
+        e.storage()
+            .instance()
+            .get(&DataKey::PauseFlags)
+            .unwrap_or_default()
+    }
+
+    pub fn set_emergency_delay(
+        e: &Env,
+        caller: Address,
+        delay_secs: u64,
+    ) -> Result<(), ContractError> {
+        caller.require_auth();
+        let config = Self::get_config(e)?;
+        ensure_admin(&config, &caller)?;
+        if delay_secs == 0 {
+            return Err(ContractError::InvalidTimeout);
+        }
+
+        e.storage()
+            .instance()
+            .set(&DataKey::Emergency(EmergencyKey::Delay), &delay_secs);
+
+        Ok(())
+    }
+
+    /// Pauses every pausable operation and schedules the emergency exit.
+    pub fn trigger_emergency(e: &Env, caller: Address) -> Result<EmergencyState, ContractError> {
+        caller.require_auth();
+        let config = Self::get_config(e)?;
+        ensure_pauser(&config, &caller)?;
+        if e.storage()
+            .instance()
+            .has(&DataKey::Emergency(EmergencyKey::State))
+        {
+            return Err(ContractError::EmergencyAlreadyActive);
+        }
+
+        let delay_secs: u64 = e
+            .storage()
+            .instance()
+            .get(&DataKey::Emergency(EmergencyKey::Delay))
+            .unwrap_or(DEFAULT_EMERGENCY_DELAY_SECS);
+        let now = e.ledger().timestamp();
+        let emergency = EmergencyState {
+            triggered_at: now,
+            exit_at: now + delay_secs,
+        };
+        e.storage()
+            .instance()
+            .set(&DataKey::Emergency(EmergencyKey::State), &emergency);
+
+        Ok(emergency)
+    }
+
+    pub fn cancel_emergency(e: &Env, caller: Address) -> Result<(), ContractError> {
+        caller.require_auth();
+        let config = Self::get_config(e)?;
+        ensure_pauser(&config, &caller)?;
+        if !e
+            .storage()
+            .instance()
+            .has(&DataKey::Emergency(EmergencyKey::State))
+        {
+            return Err(ContractError::EmergencyNotActive);
+        }
+
+        e.storage()
+            .instance()
+            .remove(&DataKey::Emergency(EmergencyKey::State));
+
+        Ok(())
+    }
+
+    pub fn get_emergency(e: &Env) -> Option<EmergencyState> {
+        e.storage()
+            .instance()
+            .get(&DataKey::Emergency(EmergencyKey::State))
+    }
+
+    /// Sets the settlement timeout for a scope, or removes it when
+    /// `timeout_secs` is `None`.
+    pub fn set_timeout_override(
+        e: &Env,
+        caller: Address,
+        scope: TimeoutScope,
+        timeout_secs: Option<u64>,
+    ) -> Result<(), ContractError> {
+        caller.require_auth();
+        let config = Self::get_config(e)?;
+        ensure_admin(&config, &caller)?;
+
+        let key = DataKey::TimeoutOverride(scope);
+        match timeout_secs {
+            Some(0) => return Err(ContractError::InvalidTimeout),
+            Some(timeout_secs) => e.storage().instance().set(&key, &timeout_secs),
+            None => e.storage().instance().remove(&key),
+        }
+
+        Ok(())
+    }
+
+    pub fn get_timeout_override(e: &Env, scope: TimeoutScope) -> Option<u64> {
+        e.storage().instance().get(&DataKey::TimeoutOverride(scope))
+    }
+
+    pub fn set_timeout_bounds(
+        e: &Env,
+        caller: Address,
+        min_secs: u64,
+        max_secs: u64,
+    ) -> Result<TimeoutBounds, ContractError> {
+        caller.require_auth();
+        let config = Self::get_config(e)?;
+        ensure_admin(&config, &caller)?;
+        validate_timeout_bounds(min_secs, max_secs)?;
+
+        let bounds = TimeoutBounds { min_secs, max_secs };
+        e.storage().instance().set(&DataKey::TimeoutBounds, &bounds);
+
+        Ok(bounds)
+    }
+
+    pub fn get_timeout_bounds(e: &Env) -> Option<TimeoutBounds> {
+        e.storage().instance().get(&DataKey::TimeoutBounds)
+    }
+
+    /// Caps the total a fill's fiat deadline can be extended by. Zero, the
+    /// default, disables extensions.
+    pub fn set_max_fiat_extension(
+        e: &Env,
+        caller: Address,
+        max_secs: u64,
+    ) -> Result<(), ContractError> {
+        caller.require_auth();
+        let config = Self::get_config(e)?;
+        ensure_admin(&config, &caller)?;
+        validate_max_fiat_extension(max_secs, &config)?;
+
+        e.storage()
+            .instance()
+            .set(&DataKey::MaxFiatExtension, &max_secs);
+
+        Ok(())
+    }
+
+    /// Holds confirmed fills paid with `payment_method` in `Settling` for
+    /// `hold_secs` before releasing them. `None` releases on confirmation.
+    pub fn set_settlement_hold(
+        e: &Env,
+        caller: Address,
+        payment_method: PaymentMethod,
+        hold_secs: Option<u64>,
+    ) -> Result<(), ContractError> {
+        caller.require_auth();
+        let config = Self::get_config(e)?;
+        ensure_admin(&config, &caller)?;
+        validate_settlement_hold(&hold_secs, &config)?;
+
+        let key = DataKey::SettlementHold(payment_method);
+        match hold_secs {
+            Some(hold_secs) => e.storage().instance().set(&key, &hold_secs),
+            None => e.storage().instance().remove(&key),
+        }
+
+        Ok(())
+    }
+
+    pub fn get_settlement_hold(e: &Env, payment_method: &PaymentMethod) -> Option<u64> {
+        e.storage()
+            .instance()
+            .get(&DataKey::SettlementHold(payment_method.clone()))
+    }
+
+    pub fn get_max_fiat_extension(e: &Env) -> u64 {
+        e.storage()
+            .instance()
+            .get(&DataKey::MaxFiatExtension)
+            .unwrap_or(0)
+    }
+
+    /// Settlement timeout for a market: the most specific admin override,
+    /// falling back to `filler_payment_timeout_secs`.
+    pub fn get_payment_timeout(
+        e: &Env,
+        payment_method: &PaymentMethod,
+        fiat_currency: &FiatCurrency,
+    ) -> Result<u64, ContractError> {
+        let config = Self::get_config(e)?;
+        let scopes = [
+            TimeoutScope::Market(payment_method.clone(), fiat_currency.clone()),
+            TimeoutScope::PaymentMethod(payment_method.clone()),
+            TimeoutScope::Currency(fiat_currency.clone()),
+        ];
+        for scope in scopes {
+            if let Some(timeout_secs) = Self::get_timeout_override(e, scope) {
+                return Ok(timeout_secs);
+            }
+        }
+
+        Ok(config.filler_payment_timeout_secs)
+    }
+
     pub fn get_config(e: &Env) -> Result<Config, ContractError> {
         e.storage()
             .instance()
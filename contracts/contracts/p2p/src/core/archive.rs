@@ -0,0 +1,131 @@
+use soroban_sdk::{Address, Env, Vec};
+
+use crate::core::admin::AdminManager;
+use crate::core::offer::OfferManager;
+use crate::core::order::OrderManager;
+use crate::core::validators::admin::ensure_admin;
+use crate::core::validators::order::ensure_archivable;
+use crate::error::ContractError;
+use crate::storage::types::{DataKey, Offer, Order, OrderSummary, OrderVisibility};
+
+/// How long a summary is kept unless the admin configures another period:
+/// about 30 days of 5 second ledgers.
+const DEFAULT_ARCHIVE_RETENTION_LEDGERS: u32 = 518_400;
+
+/// Compacts finished orders into `OrderSummary` records in temporary
+/// storage, so they stop paying rent as part of the contract instance and
+/// expire once the retention period has passed.
+pub struct ArchiveManager;
+
+impl ArchiveManager {
+    pub fn set_retention(e: &Env, caller: Address, ledgers: u32) -> Result<(), ContractError> {
+        caller.require_auth();
+        let config = AdminManager::get_config(e)?;
+        ensure_admin(&config, &caller)?;
+        if ledgers == 0 || ledgers > e.storage().max_ttl() {
+            return Err(ContractError::InvalidRetention);
+        }
+
+        e.storage()
+            .instance()
+            .set(&DataKey::ArchiveRetention, &ledgers);
+
+        Ok(())
+    }
+
+    pub fn get_retention(e: &Env) -> u32 {
+        e.storage()
+            .instance()
+            .get(&DataKey::ArchiveRetention)
+            .unwrap_or(DEFAULT_ARCHIVE_RETENTION_LEDGERS)
+    }
+
+    /// Replaces a completed, cancelled or refunded order with its summary
+    /// and drops the rest of its instance entries, refunding offers still
+    /// open on it. Anyone may call it. Returns the summary and the refunded
+    /// offers.
+    pub fn archive_order(
+        e: &Env,
+        order_id: u64,
+    ) -> Result<(OrderSummary, Vec<Offer>), ContractError> {
+        let config = AdminManager::get_config(e)?;
+        let order = OrderManager::get_order(e, order_id)?;
+        ensure_archivable(&order)?;
+        let refunded = OfferManager::refund_order_offers(e, &config.token, order_id)?;
+
+        let summary = OrderSummary {
+            order_id,
+            creator: order.creator,
+            token: order.token,
+            amount: order.amount,
+            filled_amount: order.filled_amount,
+            exchange_rate: order.exchange_rate,
+            from_crypto: order.from_crypto,
+            fiat_currency: order.fiat_currency,
+            payment_method: order.payment_method,
+            status: order.status,
+            created_at: order.created_at,
+            deadline: order.deadline,
+            archived_at: e.ledger().timestamp(),
+        };
+
+        let key = DataKey::OrderSummary(order_id);
+        let retention = Self::get_retention(e);
+        e.storage().temporary().set(&key, &summary);
+        e.storage()
+            .temporary()
+            .extend_ttl(&key, retention, retention);
+        for key in [
+            DataKey::Order(order_id),
+            DataKey::FillRate(order_id),
+            DataKey::FillExtension(order_id),
+            DataKey::FillEscrow(order_id),
+            DataKey::AdOrder(order_id),
+            DataKey::SettlementRelease(order_id),
+            DataKey::OrderOffers(order_id),
+        ] {
+            e.storage().instance().remove(&key);
+        }
+
+        Ok((summary, refunded))
+    }
+
+    pub fn get_order_summary(e: &Env, order_id: u64) -> Result<OrderSummary, ContractError> {
+        e.storage()
+            .temporary()
+            .get(&DataKey::OrderSummary(order_id))
+            .ok_or(ContractError::OrderNotFound)
+    }
+
+    /// Looks up a live order, falling back to its archived summary. Fields
+    /// the summary does not keep are left empty, as on any finished order.
+    pub fn find_order(e: &Env, order_id: u64) -> Result<Order, ContractError> {
+        if let Ok(order) = OrderManager::get_order(e, order_id) {
+            return Ok(order);
+        }
+
+        let summary = Self::get_order_summary(e, order_id)?;
+        Ok(Order {
+            order_id: summary.order_id,
+            creator: summary.creator,
+            filler: None,
+            token: summary.token,
+            amount: summary.amount,
+            remaining_amount: summary.amount - summary.filled_amount,
+            filled_amount: summary.filled_amount,
+            active_fill_amount: None,
+            exchange_rate: summary.exchange_rate,
+            from_crypto: summary.from_crypto,
+            fiat_currency: summary.fiat_currency,
+            payment_method: summary.payment_method,
+            status: summary.status,
+            created_at: summary.created_at,
+            deadline: summary.deadline,
+            fiat_transfer_deadline: None,
+            frozen: false,
+            visibility: OrderVisibility::Public,
+            requested_timeout_secs: None,
+            payment_timeout_secs: None,
+        })
+    }
+}
@@ -0,0 +1,196 @@
+use soroban_sdk::{Address, Env, Vec};
+
+use crate::core::admin::AdminManager;
+use crate::core::validators::admin::{ensure_admin, ensure_pauser};
+use crate::core::validators::breaker::{
+    ensure_not_tripped, validate_breaker_config, WINDOW_BUCKETS,
+};
+use crate::error::ContractError;
+use crate::events::handler::{CircuitBreakerTripped, EVENT_VERSION};
+use crate::storage::types::{
+    BreakerConfig, BreakerKey, BreakerStatus, CurrencyActivity, DataKey, FiatCurrency,
+    OutcomeBucket,
+};
+
+pub enum FillEvent {
+    Dispute,
+    Timeout,
+    Settlement,
+}
+
+/// Suspends takes in a currency when disputes spike, as they do when stolen
+/// bank accounts are used to pay fiat. Disputes, timeouts and settlements
+/// are counted per currency in slices of the configured window; the window
+/// rolls forward one slice at a time. Only the pauser lifts a suspension.
+/// Nothing is counted until the admin configures the breaker. Managers count
+/// outcomes where they happen, so a trip is published from here. The config
+/// and the per-currency activity both live in instance storage, since every
+/// take reads them and the activity stays bounded by the window's slices.
+pub struct CircuitBreakerManager;
+
+impl CircuitBreakerManager {
+    pub fn set_config(
+        e: &Env,
+        caller: Address,
+        breaker_config: BreakerConfig,
+    ) -> Result<(), ContractError> {
+        caller.require_auth();
+        let config = AdminManager::get_config(e)?;
+        ensure_admin(&config, &caller)?;
+        validate_breaker_config(&breaker_config)?;
+
+        e.storage().instance().set(
+            &DataKey::CircuitBreaker(BreakerKey::Config),
+            &breaker_config,
+        );
+
+        Ok(())
+    }
+
+    pub fn get_config(e: &Env) -> Option<BreakerConfig> {
+        e.storage()
+            .instance()
+            .get(&DataKey::CircuitBreaker(BreakerKey::Config))
+    }
+
+    /// Counts a fill outcome in the currency and publishes
+    /// `CircuitBreakerTripped` when this outcome trips the breaker.
+    pub fn record(e: &Env, currency: &FiatCurrency, event: FillEvent) -> Result<(), ContractError> {
+        let Some(breaker_config) = Self::get_config(e) else {
+            return Ok(());
+        };
+
+        let now = e.ledger().timestamp();
+        let bucket_secs = breaker_config.window_secs / WINDOW_BUCKETS;
+        let start = now - now % bucket_secs;
+        let mut activity = Self::get_activity(e, currency).unwrap_or(Self::no_activity(e));
+
+        let mut bucket = match activity.buckets.last() {
+            Some(last) if last.start == start => {
+                activity.buckets.pop_back();
+                last
+            }
+            _ => OutcomeBucket {
+                start,
+                disputes: 0,
+                timeouts: 0,
+                settlements: 0,
+            },
+        };
+        let count = match event {
+            FillEvent::Dispute => &mut bucket.disputes,
+            FillEvent::Timeout => &mut bucket.timeouts,
+            FillEvent::Settlement => &mut bucket.settlements,
+        };
+        *count = count.checked_add(1).ok_or(ContractError::Overflow)?;
+        activity.buckets.push_back(bucket);
+        Self::roll_window(&mut activity, breaker_config.window_secs, now);
+
+        let status = Self::summarize(currency, &activity);
+        let samples = status.disputes as u64 + status.timeouts as u64 + status.settlements as u64;
+        let tripped = activity.tripped_at.is_none()
+            && samples >= breaker_config.min_samples as u64
+            && status.disputes as u64 * 10_000 > breaker_config.max_dispute_bps as u64 * samples;
+        if tripped {
+            activity.tripped_at = Some(now);
+        }
+        Self::store_activity(e, currency, &activity);
+
+        if tripped {
+            CircuitBreakerTripped {
+                version: EVENT_VERSION,
+                currency: status.currency,
+                disputes: status.disputes,
+                timeouts: status.timeouts,
+                settlements: status.settlements,
+                tripped_at: now,
+            }
+            .publish(e);
+        }
+
+        Ok(())
+    }
+
+    /// Lifts the suspension and starts the currency's window afresh.
+    pub fn reset(e: &Env, caller: Address, currency: FiatCurrency) -> Result<(), ContractError> {
+        caller.require_auth();
+        let config = AdminManager::get_config(e)?;
+        ensure_pauser(&config, &caller)?;
+
+        let activity = Self::get_activity(e, &currency);
+        if ensure_not_tripped(&activity).is_ok() {
+            return Err(ContractError::BreakerNotTripped);
+        }
+
+        e.storage()
+            .instance()
+            .remove(&DataKey::CircuitBreaker(BreakerKey::Activity(currency)));
+
+        Ok(())
+    }
+
+    pub fn ensure_takes_open(e: &Env, currency: &FiatCurrency) -> Result<(), ContractError> {
+        ensure_not_tripped(&Self::get_activity(e, currency))
+    }
+
+    pub fn get_status(e: &Env, currency: FiatCurrency) -> BreakerStatus {
+        let mut activity = Self::get_activity(e, &currency).unwrap_or(Self::no_activity(e));
+        // Slices stay stored until the next outcome rolls them out.
+        if let Some(breaker_config) = Self::get_config(e) {
+            Self::roll_window(
+                &mut activity,
+                breaker_config.window_secs,
+                e.ledger().timestamp(),
+            );
+        }
+        Self::summarize(&currency, &activity)
+    }
+
+    fn no_activity(e: &Env) -> CurrencyActivity {
+        CurrencyActivity {
+            buckets: Vec::new(e),
+            tripped_at: None,
+        }
+    }
+
+    /// Drops slices that started a full window ago or earlier.
+    fn roll_window(activity: &mut CurrencyActivity, window_secs: u64, now: u64) {
+        while let Some(first) = activity.buckets.first() {
+            if first.start + window_secs > now {
+                break;
+            }
+            activity.buckets.pop_front();
+        }
+    }
+
+    fn summarize(currency: &FiatCurrency, activity: &CurrencyActivity) -> BreakerStatus {
+        let mut status = BreakerStatus {
+            currency: currency.clone(),
+            disputes: 0,
+            timeouts: 0,
+            settlements: 0,
+            tripped_at: activity.tripped_at,
+        };
+        for bucket in activity.buckets.iter() {
+            status.disputes = status.disputes.saturating_add(bucket.disputes);
+            status.timeouts = status.timeouts.saturating_add(bucket.timeouts);
+            status.settlements = status.settlements.saturating_add(bucket.settlements);
+        }
+        status
+    }
+
+    fn get_activity(e: &Env, currency: &FiatCurrency) -> Option<CurrencyActivity> {
+        e.storage()
+            .instance()
+            .get(&DataKey::CircuitBreaker(BreakerKey::Activity(
+                currency.clone(),
+            )))
+    }
+
+    fn store_activity(e: &Env, currency: &FiatCurrency, activity: &CurrencyActivity) {
+        e.storage().instance().set(
+            &DataKey::CircuitBreaker(BreakerKey::Activity(currency.clone())),
+            activity,
+        );
+    }
+}
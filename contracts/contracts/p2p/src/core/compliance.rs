@@ -0,0 +1,102 @@
+use soroban_sdk::{Address, Env};
+
+use crate::core::ad::AdManager;
+use crate::core::admin::AdminManager;
+use crate::core::breaker::{CircuitBreakerManager, FillEvent};
+use crate::core::escrow::EscrowManager;
+use crate::core::order::OrderManager;
+use crate::core::validators::admin::ensure_compliance;
+use crate::core::validators::compliance::{ensure_freezable, ensure_frozen, is_blocked};
+use crate::error::ContractError;
+use crate::storage::types::{DataKey, Order, OrderStatus};
+
+pub struct ComplianceManager;
+
+impl ComplianceManager {
+    pub fn block_address(e: &Env, caller: Address, address: Address) -> Result<(), ContractError> {
+        caller.require_auth();
+        let config = AdminManager::get_config(e)?;
+        ensure_compliance(&config, &caller)?;
+
+        if is_blocked(e, &address) {
+            return Err(ContractError::AlreadyBlocked);
+        }
+
+        let key = DataKey::Blocked(address);
+        let max_ttl = e.storage().max_ttl();
+        e.storage().persistent().set(&key, &true);
+        e.storage().persistent().extend_ttl(&key, max_ttl, max_ttl);
+
+        Ok(())
+    }
+
+    pub fn unblock_address(
+        e: &Env,
+        caller: Address,
+        address: Address,
+    ) -> Result<(), ContractError> {
+        caller.require_auth();
+        let config = AdminManager::get_config(e)?;
+        ensure_compliance(&config, &caller)?;
+
+        if !is_blocked(e, &address) {
+            return Err(ContractError::NotBlocked);
+        }
+
+        e.storage().persistent().remove(&DataKey::Blocked(address));
+
+        Ok(())
+    }
+
+    /// Freezes an order with a blocked party. An active fill is moved to
+    /// `Disputed` so the held funds can only leave through a resolver ruling.
+    pub fn freeze_order(e: &Env, caller: Address, order_id: u64) -> Result<Order, ContractError> {
+        caller.require_auth();
+        let config = AdminManager::get_config(e)?;
+        ensure_compliance(&config, &caller)?;
+
+        let mut order = OrderManager::get_order(e, order_id)?;
+        ensure_freezable(e, &order)?;
+
+        if order.status == OrderStatus::AwaitingPayment
+            || order.status == OrderStatus::AwaitingConfirmation
+            || order.status == OrderStatus::Settling
+        {
+            if let Some(fill_escrow) = EscrowManager::get_fill_escrow(e, order_id) {
+                EscrowManager::dispute(e, &fill_escrow);
+            }
+            order.status = OrderStatus::Disputed;
+            e.storage()
+                .instance()
+                .remove(&DataKey::SettlementRelease(order_id));
+            AdManager::dispute_opened(e, &order);
+            CircuitBreakerManager::record(e, &order.fiat_currency, FillEvent::Dispute)?;
+        }
+        order.frozen = true;
+        e.storage()
+            .instance()
+            .set(&DataKey::Order(order.order_id), &order);
+
+        Ok(order)
+    }
+
+    pub fn unfreeze_order(e: &Env, caller: Address, order_id: u64) -> Result<Order, ContractError> {
+        caller.require_auth();
+        let config = AdminManager::get_config(e)?;
+        ensure_compliance(&config, &caller)?;
+
+        let mut order = OrderManager::get_order(e, order_id)?;
+        ensure_frozen(&order)?;
+
+        order.frozen = false;
+        e.storage()
+            .instance()
+            .set(&DataKey::Order(order.order_id), &order);
+
+        Ok(order)
+    }
+
+    pub fn is_blocked(e: &Env, address: Address) -> bool {
+        is_blocked(e, &address)
+    }
+}
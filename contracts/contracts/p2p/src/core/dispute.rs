@@ -1,13 +1,23 @@
-use soroban_sdk::token::Client as TokenClient;
 use soroban_sdk::{Address, Env};
 
+use crate::core::ad::AdManager;
 use crate::core::admin::AdminManager;
-use crate::core::order::OrderManager;
-use crate::core::validators::admin::{ensure_dispute_resolver, ensure_not_paused};
+use crate::core::breaker::{CircuitBreakerManager, FillEvent};
+use crate::core::escrow::EscrowManager;
+use crate::core::fees::FeeManager;
+use crate::core::history::FillHistoryManager;
+use crate::core::ledger::LedgerManager;
+use crate::core::order::{OrderManager, Settlement};
+use crate::core::resolver::ResolverManager;
+use crate::core::stats::MarketStatsManager;
+use crate::core::validators::admin::{
+    ensure_dispute_resolver, ensure_not_paused, PausableOperation,
+};
+use crate::core::validators::compliance::ensure_not_frozen;
 use crate::core::validators::dispute::{ensure_disputable, ensure_disputed};
 use crate::core::validators::order::{ensure_active_fill_amount, ensure_creator, ensure_filler};
 use crate::error::ContractError;
-use crate::storage::types::{DataKey, Order, OrderStatus};
+use crate::storage::types::{DataKey, FillOutcome, Order, OrderStatus, Ruling};
 
 pub struct DisputeManager;
 
@@ -19,18 +29,31 @@ impl DisputeManager {
     ) -> Result<Order, ContractError> {
         caller.require_auth();
         let config = AdminManager::get_config(e)?;
-        ensure_not_paused(&config)?;
+        ensure_not_paused(e, &config, PausableOperation::Disputes)?;
 
         let mut order = OrderManager::get_order(e, order_id)?;
         ensure_disputable(&order)?;
+        ensure_not_frozen(&order)?;
 
-        if order.from_crypto {
+        // The fiat payer disputes a missing confirmation; during a settlement
+        // hold the fiat receiver disputes a payment that was reversed.
+        let payer_disputes = order.status == OrderStatus::AwaitingConfirmation;
+        if order.from_crypto == payer_disputes {
             ensure_filler(&order, &caller)?;
         } else {
             ensure_creator(&order, &caller)?;
         }
 
+        if let Some(fill_escrow) = EscrowManager::get_fill_escrow(e, order_id) {
+            EscrowManager::dispute(e, &fill_escrow);
+        }
+        e.storage()
+            .instance()
+            .remove(&DataKey::SettlementRelease(order_id));
+
         order.status = OrderStatus::Disputed;
+        AdManager::dispute_opened(e, &order);
+        CircuitBreakerManager::record(e, &order.fiat_currency, FillEvent::Dispute)?;
         e.storage()
             .instance()
             .set(&DataKey::Order(order.order_id), &order);
@@ -43,18 +66,28 @@ impl DisputeManager {
         caller: Address,
         order_id: u64,
         fiat_transfer_confirmed: bool,
-    ) -> Result<Order, ContractError> {
+    ) -> Result<(Order, Settlement, Option<Ruling>), ContractError> {
         caller.require_auth();
         let config = AdminManager::get_config(e)?;
         ensure_dispute_resolver(&config, &caller)?;
-        ensure_not_paused(&config)?;
+        if !ResolverManager::is_eligible(e, caller.clone()) {
+            return Err(ContractError::Unauthorized);
+        }
 
         let mut order = OrderManager::get_order(e, order_id)?;
         ensure_disputed(&order)?;
         let active_fill_amount = ensure_active_fill_amount(&order)?;
+        let filler = order.filler.clone().ok_or(ContractError::MissingFiller)?;
 
-        let token_client = TokenClient::new(e, &config.token);
+        // A rejected fill on a `from_crypto` order returns to the creator's
+        // escrowed remainder, so nothing leaves the contract.
         let recipient = if fiat_transfer_confirmed {
+            MarketStatsManager::record_trade(
+                e,
+                &order,
+                active_fill_amount,
+                OrderManager::fill_rate(e, &order),
+            )?;
             order.filled_amount = order
                 .filled_amount
                 .checked_add(active_fill_amount)
@@ -70,31 +103,119 @@ impl DisputeManager {
                 OrderStatus::AwaitingFiller
             };
             if order.from_crypto {
-                order.filler.clone().ok_or(ContractError::MissingFiller)?
+                Some(filler.clone())
             } else {
-                order.creator.clone()
+                Some(order.creator.clone())
             }
         } else {
             order.status = OrderStatus::AwaitingFiller;
             if order.from_crypto {
-                order.creator.clone()
+                None
             } else {
-                order.filler.clone().ok_or(ContractError::MissingFiller)?
+                Some(filler.clone())
             }
         };
 
-        token_client.transfer(
-            &e.current_contract_address(),
-            &recipient,
-            &active_fill_amount,
-        );
+        let mut fee = None;
+        let amount = match EscrowManager::get_fill_escrow(e, order_id) {
+            Some(fill_escrow) => {
+                let payee = recipient
+                    .clone()
+                    .unwrap_or_else(|| e.current_contract_address());
+                let (received, platform_fee) = EscrowManager::resolve(
+                    e,
+                    order_id,
+                    &fill_escrow,
+                    &config.token,
+                    &caller,
+                    &payee,
+                );
+                match &recipient {
+                    Some(recipient) if fiat_transfer_confirmed => {
+                        fee = FeeManager::charge_escrow_fee(e, &config, recipient, platform_fee)?;
+                        received
+                    }
+                    // Refunds are not charged the protocol fee.
+                    Some(recipient) => {
+                        if platform_fee > 0 {
+                            LedgerManager::receive(e, &config.token, platform_fee)?;
+                            LedgerManager::payout(e, &config.token, recipient, platform_fee)?;
+                        }
+                        received + platform_fee
+                    }
+                    None => {
+                        // The platform fee comes back with a returned fill,
+                        // so only the Trustless Work fee comes out of the
+                        // creator's deposit.
+                        LedgerManager::receive(e, &config.token, received)?;
+                        let fees = active_fill_amount
+                            .checked_sub(received)
+                            .ok_or(ContractError::Underflow)?;
+                        order.amount = order
+                            .amount
+                            .checked_sub(fees)
+                            .ok_or(ContractError::Underflow)?;
+                        order.remaining_amount = order
+                            .remaining_amount
+                            .checked_sub(fees)
+                            .ok_or(ContractError::Underflow)?;
+                        0
+                    }
+                }
+            }
+            None => match &recipient {
+                Some(recipient) if fiat_transfer_confirmed => {
+                    let (paid, charge) =
+                        FeeManager::pay_settlement(e, &config, recipient, active_fill_amount)?;
+                    fee = charge;
+                    paid
+                }
+                Some(recipient) => {
+                    LedgerManager::payout(e, &config.token, recipient, active_fill_amount)?;
+                    active_fill_amount
+                }
+                None => 0,
+            },
+        };
+        let outcome = if fiat_transfer_confirmed {
+            FillOutcome::DisputeWon
+        } else {
+            FillOutcome::DisputeLost
+        };
+        FillHistoryManager::record_settlement(e, order_id, outcome);
+        // The dispute itself was counted when it was opened; a fill the
+        // resolver confirms also counts as a settlement.
+        if fiat_transfer_confirmed {
+            CircuitBreakerManager::record(e, &order.fiat_currency, FillEvent::Settlement)?;
+        }
+        let ruling = ResolverManager::record_ruling(
+            e,
+            &order,
+            &filler,
+            &caller,
+            fiat_transfer_confirmed,
+            active_fill_amount,
+        )?;
+
         order.filler = None;
         order.active_fill_amount = None;
         order.fiat_transfer_deadline = None;
+        order.payment_timeout_secs = None;
+        AdManager::dispute_closed(e, &order);
+        AdManager::return_fill(e, &mut order)?;
         e.storage()
             .instance()
             .set(&DataKey::Order(order.order_id), &order);
 
-        Ok(order)
+        Ok((
+            order,
+            Settlement {
+                filler,
+                paid_to: recipient,
+                amount,
+                fee,
+            },
+            ruling,
+        ))
     }
 }
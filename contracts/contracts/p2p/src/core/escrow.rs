@@ -0,0 +1,304 @@
+use soroban_sdk::auth::{
+    ContractExecutable, CreateContractWithConstructorHostFnContext, InvokerContractAuthEntry,
+};
+use soroban_sdk::token::Client as TokenClient;
+use soroban_sdk::{
+    contractclient, map, vec, Address, BytesN, Env, IntoVal, Map, String, Symbol, Val, Vec,
+};
+
+use crate::core::admin::AdminManager;
+use crate::core::fees::FeeManager;
+use crate::core::ledger::LedgerManager;
+use crate::core::validators::admin::ensure_admin;
+use crate::error::ContractError;
+use crate::storage::escrow::{Escrow, Flags, Milestone, Roles, Trustline};
+use crate::storage::types::{Config, DataKey, EscrowSettings, FillEscrow, Order};
+
+/// The escrow contract entry points the P2P contract drives. Only the
+/// generated `EscrowClient` is used.
+#[allow(dead_code)]
+#[contractclient(name = "EscrowClient")]
+pub trait EscrowInterface {
+    fn tw_new_single_release_escrow(
+        env: Env,
+        deployer: Address,
+        wasm_hash: BytesN<32>,
+        salt: BytesN<32>,
+        init_fn: Symbol,
+        init_args: Vec<Val>,
+        constructor_args: Vec<Val>,
+    ) -> (Address, Val);
+    fn change_milestone_status(
+        env: Env,
+        milestone_index: i128,
+        new_status: String,
+        new_evidence: Option<String>,
+        service_provider: Address,
+    );
+    fn approve_milestone(env: Env, milestone_index: i128, approver: Address);
+    fn release_funds(env: Env, release_signer: Address, trustless_work_address: Address);
+    fn dispute_escrow(env: Env, signer: Address);
+    fn resolve_dispute(
+        env: Env,
+        dispute_resolver: Address,
+        trustless_work_address: Address,
+        distributions: Map<Address, i128>,
+    );
+}
+
+/// Every fill escrow has a single milestone: the fiat payment.
+const FIAT_MILESTONE: i128 = 0;
+
+/// Settles fills through one Trustless Work escrow per fill instead of the
+/// contract's shared balance. The parties hold the escrow roles for their
+/// side of the trade: the fiat payer is the service provider, the party
+/// confirming the payment is the approver and the crypto buyer is the
+/// receiver. The configured dispute resolver keeps the final say, and this
+/// contract releases funds and opens disputes on the parties' behalf. The
+/// protocol fee is the escrow's platform fee, paid to this contract as the
+/// platform.
+pub struct EscrowManager;
+
+impl EscrowManager {
+    /// Enables escrow settlement for new fills, or disables it when
+    /// `settings` is `None`. Fills already in an escrow settle through it.
+    pub fn set_settings(
+        e: &Env,
+        caller: Address,
+        settings: Option<EscrowSettings>,
+    ) -> Result<(), ContractError> {
+        caller.require_auth();
+        let config = AdminManager::get_config(e)?;
+        ensure_admin(&config, &caller)?;
+
+        match settings {
+            Some(settings) => e
+                .storage()
+                .instance()
+                .set(&DataKey::EscrowSettings, &settings),
+            None => e.storage().instance().remove(&DataKey::EscrowSettings),
+        }
+
+        Ok(())
+    }
+
+    pub fn get_settings(e: &Env) -> Option<EscrowSettings> {
+        e.storage().instance().get(&DataKey::EscrowSettings)
+    }
+
+    pub fn get_fill_escrow(e: &Env, order_id: u64) -> Option<FillEscrow> {
+        e.storage().instance().get(&DataKey::FillEscrow(order_id))
+    }
+
+    /// Deploys an escrow for the order's active fill and funds it, from the
+    /// contract when it already `held` the fill, otherwise from the filler.
+    pub fn open(
+        e: &Env,
+        config: &Config,
+        settings: &EscrowSettings,
+        order: &Order,
+        held: bool,
+    ) -> Result<FillEscrow, ContractError> {
+        let filler = order.filler.clone().ok_or(ContractError::MissingFiller)?;
+        let fill_amount = order
+            .active_fill_amount
+            .ok_or(ContractError::MissingActiveFill)?;
+        let (service_provider, receiver) = if order.from_crypto {
+            (filler.clone(), filler.clone())
+        } else {
+            (order.creator.clone(), order.creator.clone())
+        };
+
+        // This contract approves the milestone once the confirming party or
+        // one of its operators confirms, so operators never need the
+        // confirming party's own key.
+        let this = e.current_contract_address();
+        let escrow = Escrow {
+            engagement_id: Self::engagement_id(e, order.order_id),
+            title: String::from_str(e, "P2P fill"),
+            roles: Roles {
+                approver: this.clone(),
+                service_provider,
+                platform_address: this.clone(),
+                release_signer: this.clone(),
+                dispute_resolver: config.dispute_resolver.clone(),
+                receiver,
+            },
+            description: String::from_str(e, "Crypto held until the fiat payment is confirmed"),
+            amount: fill_amount,
+            platform_fee: FeeManager::get_fee_config(e).map_or(0, |fee_config| fee_config.fee_bps),
+            milestones: vec![
+                e,
+                Milestone {
+                    description: String::from_str(e, "Fiat payment"),
+                    status: String::from_str(e, ""),
+                    evidence: String::from_str(e, ""),
+                    approved: false,
+                },
+            ],
+            flags: Flags {
+                disputed: false,
+                released: false,
+                resolved: false,
+            },
+            trustline: Trustline {
+                address: order.token.clone(),
+            },
+            receiver_memo: 0,
+        };
+
+        let salt = Self::next_salt(e)?;
+        let init_args: Vec<Val> = vec![e, escrow.into_val(e)];
+        let constructor_args: Vec<Val> = Vec::new(e);
+        // The factory deploys on this contract's behalf.
+        e.authorize_as_current_contract(vec![
+            e,
+            InvokerContractAuthEntry::CreateContractWithCtorHostFn(
+                CreateContractWithConstructorHostFnContext {
+                    executable: ContractExecutable::Wasm(settings.wasm_hash.clone()),
+                    salt: salt.clone(),
+                    constructor_args: constructor_args.clone(),
+                },
+            ),
+        ]);
+        let (address, _) = EscrowClient::new(e, &settings.factory).tw_new_single_release_escrow(
+            &this,
+            &settings.wasm_hash,
+            &salt,
+            &Symbol::new(e, "initialize_escrow"),
+            &init_args,
+            &constructor_args,
+        );
+
+        if held {
+            LedgerManager::payout(e, &order.token, &address, fill_amount)?;
+        } else {
+            TokenClient::new(e, &order.token).transfer(&filler, &address, &fill_amount);
+        }
+
+        let fill_escrow = FillEscrow {
+            escrow: address,
+            trustless_work: settings.trustless_work.clone(),
+        };
+        e.storage()
+            .instance()
+            .set(&DataKey::FillEscrow(order.order_id), &fill_escrow);
+
+        Ok(fill_escrow)
+    }
+
+    /// Records the fiat payment on the escrow's milestone.
+    pub fn mark_paid(e: &Env, fill_escrow: &FillEscrow, payer: &Address) {
+        EscrowClient::new(e, &fill_escrow.escrow).change_milestone_status(
+            &FIAT_MILESTONE,
+            &String::from_str(e, "Paid"),
+            &None,
+            payer,
+        );
+    }
+
+    /// Approves the fiat milestone once the fill has been confirmed.
+    pub fn approve(e: &Env, fill_escrow: &FillEscrow) {
+        EscrowClient::new(e, &fill_escrow.escrow)
+            .approve_milestone(&FIAT_MILESTONE, &e.current_contract_address());
+    }
+
+    /// Releases an approved fill to the escrow's receiver. Returns what the
+    /// receiver got after fees and the platform fee this contract got.
+    pub fn release(
+        e: &Env,
+        order_id: u64,
+        fill_escrow: &FillEscrow,
+        token: &Address,
+        receiver: &Address,
+    ) -> (i128, i128) {
+        let this = e.current_contract_address();
+        let token_client = TokenClient::new(e, token);
+        let before = token_client.balance(receiver);
+        let platform_before = token_client.balance(&this);
+        EscrowClient::new(e, &fill_escrow.escrow).release_funds(&this, &fill_escrow.trustless_work);
+        e.storage()
+            .instance()
+            .remove(&DataKey::FillEscrow(order_id));
+
+        (
+            token_client.balance(receiver) - before,
+            token_client.balance(&this) - platform_before,
+        )
+    }
+
+    /// Flags the escrow as disputed so only the resolver can move its funds.
+    pub fn dispute(e: &Env, fill_escrow: &FillEscrow) {
+        EscrowClient::new(e, &fill_escrow.escrow).dispute_escrow(&e.current_contract_address());
+    }
+
+    /// Pays the whole escrow balance to `recipient` on the resolver's
+    /// ruling. Returns what the recipient got after fees and the platform
+    /// fee this contract got, which is part of the first when this contract
+    /// is the recipient.
+    pub fn resolve(
+        e: &Env,
+        order_id: u64,
+        fill_escrow: &FillEscrow,
+        token: &Address,
+        resolver: &Address,
+        recipient: &Address,
+    ) -> (i128, i128) {
+        let this = e.current_contract_address();
+        let token_client = TokenClient::new(e, token);
+        let held = token_client.balance(&fill_escrow.escrow);
+
+        let before = token_client.balance(recipient);
+        let platform_before = token_client.balance(&this);
+        EscrowClient::new(e, &fill_escrow.escrow).resolve_dispute(
+            resolver,
+            &fill_escrow.trustless_work,
+            &map![e, (recipient.clone(), held)],
+        );
+        e.storage()
+            .instance()
+            .remove(&DataKey::FillEscrow(order_id));
+
+        let received = token_client.balance(recipient) - before;
+        if *recipient == this {
+            return (received, 0);
+        }
+        (received, token_client.balance(&this) - platform_before)
+    }
+
+    fn next_salt(e: &Env) -> Result<BytesN<32>, ContractError> {
+        let count: u64 = e
+            .storage()
+            .instance()
+            .get(&DataKey::EscrowCount)
+            .unwrap_or(0);
+        let next = count.checked_add(1).ok_or(ContractError::Overflow)?;
+        e.storage().instance().set(&DataKey::EscrowCount, &next);
+
+        let mut salt = [0u8; 32];
+        salt[24..].copy_from_slice(&count.to_be_bytes());
+        Ok(BytesN::from_array(e, &salt))
+    }
+
+    fn engagement_id(e: &Env, order_id: u64) -> String {
+        const PREFIX: &[u8] = b"p2p-";
+        let mut digits = [0u8; 20];
+        let mut len = 0;
+        let mut rest = order_id;
+        loop {
+            digits[len] = b'0' + (rest % 10) as u8;
+            len += 1;
+            rest /= 10;
+            if rest == 0 {
+                break;
+            }
+        }
+
+        let mut id = [0u8; 24];
+        id[..PREFIX.len()].copy_from_slice(PREFIX);
+        for i in 0..len {
+            id[PREFIX.len() + i] = digits[len - 1 - i];
+        }
+        String::from_bytes(e, &id[..PREFIX.len() + len])
+    }
+}
@@ -0,0 +1,140 @@
+use soroban_sdk::{Address, Env};
+
+use crate::core::admin::AdminManager;
+use crate::core::insurance::InsuranceManager;
+use crate::core::ledger::LedgerManager;
+use crate::core::referral::ReferralManager;
+use crate::core::validators::admin::{ensure_admin, validate_fee_config};
+use crate::error::ContractError;
+use crate::storage::types::{Config, DataKey, FeeConfig};
+
+/// Fee taken from a settled fill, the part of it owed to a referrer and the
+/// part that went to the insurance pool.
+pub struct FeeCharge {
+    pub payer: Address,
+    pub token: Address,
+    pub fee: i128,
+    pub referrer: Option<Address>,
+    pub referral_amount: i128,
+    pub insurance_amount: i128,
+}
+
+/// Charges the protocol fee when a fill settles. No fee is charged until the
+/// admin configures one. Fills settled through an escrow pay it as the
+/// escrow's platform fee, which is split here once it arrives.
+pub struct FeeManager;
+
+impl FeeManager {
+    pub fn set_fee_config(
+        e: &Env,
+        caller: Address,
+        fee_config: FeeConfig,
+    ) -> Result<(), ContractError> {
+        caller.require_auth();
+        let config = AdminManager::get_config(e)?;
+        ensure_admin(&config, &caller)?;
+        validate_fee_config(fee_config.fee_bps, fee_config.referral_share_bps)?;
+
+        e.storage().instance().set(&DataKey::FeeConfig, &fee_config);
+
+        Ok(())
+    }
+
+    pub fn get_fee_config(e: &Env) -> Option<FeeConfig> {
+        e.storage().instance().get(&DataKey::FeeConfig)
+    }
+
+    /// Pays a settled fill to `recipient` less the protocol fee. The fee goes
+    /// to the fee recipient, except the referral share, which stays in the
+    /// contract until the recipient's referrer claims it, and the insurance
+    /// pool's cut of the rest. Returns the amount paid and the fee charged,
+    /// if any.
+    pub fn pay_settlement(
+        e: &Env,
+        config: &Config,
+        recipient: &Address,
+        amount: i128,
+    ) -> Result<(i128, Option<FeeCharge>), ContractError> {
+        let Some(fee_config) = Self::get_fee_config(e) else {
+            LedgerManager::payout(e, &config.token, recipient, amount)?;
+            return Ok((amount, None));
+        };
+
+        let fee = Self::bps_of(amount, fee_config.fee_bps)?;
+        let paid = amount - fee;
+        LedgerManager::payout(e, &config.token, recipient, paid)?;
+        let charge = Self::split_fee(e, config, &fee_config, recipient, fee)?;
+
+        Ok((paid, Some(charge)))
+    }
+
+    /// Splits the platform fee an escrow paid this contract on `payer`'s
+    /// fill. A fee left over after the admin removed the fee config goes
+    /// back to the payer.
+    pub fn charge_escrow_fee(
+        e: &Env,
+        config: &Config,
+        payer: &Address,
+        fee: i128,
+    ) -> Result<Option<FeeCharge>, ContractError> {
+        if fee <= 0 {
+            return Ok(None);
+        }
+
+        LedgerManager::receive(e, &config.token, fee)?;
+        let Some(fee_config) = Self::get_fee_config(e) else {
+            LedgerManager::payout(e, &config.token, payer, fee)?;
+            return Ok(None);
+        };
+
+        Ok(Some(Self::split_fee(e, config, &fee_config, payer, fee)?))
+    }
+
+    /// Pays out a fee the contract holds: the referral share stays until the
+    /// payer's referrer claims it, the insurance pool takes its cut of the
+    /// rest and the fee recipient gets the remainder.
+    fn split_fee(
+        e: &Env,
+        config: &Config,
+        fee_config: &FeeConfig,
+        payer: &Address,
+        fee: i128,
+    ) -> Result<FeeCharge, ContractError> {
+        let referrer = ReferralManager::get_referrer(e, payer.clone());
+        let referral_amount = match &referrer {
+            Some(_) => Self::bps_of(fee, fee_config.referral_share_bps)?,
+            None => 0,
+        };
+        let insurance_amount = InsuranceManager::fee_share(e, fee - referral_amount)?;
+        let protocol_amount = fee - referral_amount - insurance_amount;
+
+        if protocol_amount > 0 {
+            LedgerManager::payout(e, &config.token, &fee_config.fee_recipient, protocol_amount)?;
+        }
+        if insurance_amount > 0 {
+            InsuranceManager::fund(e, &config.token, insurance_amount)?;
+        }
+        if let Some(referrer) = &referrer {
+            if referral_amount > 0 {
+                ReferralManager::accrue(e, referrer, referral_amount)?;
+            }
+        }
+
+        Ok(FeeCharge {
+            payer: payer.clone(),
+            token: config.token.clone(),
+            fee,
+            referrer,
+            referral_amount,
+            insurance_amount,
+        })
+    }
+
+    fn bps_of(amount: i128, bps: u32) -> Result<i128, ContractError> {
+        amount
+            .checked_mul(bps as i128)
+            .ok_or(ContractError::Overflow)?
+            .checked_div(10_000)
+            .ok_or(ContractError::DivisionError)
+    }
+}
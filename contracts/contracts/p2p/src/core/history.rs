@@ -0,0 +1,79 @@
+use soroban_sdk::{Env, Vec};
+
+use crate::error::ContractError;
+use crate::storage::types::{DataKey, FillOutcome, FillRecord, Order};
+
+/// Keeps every fill of an order, in order. A record is appended when the
+/// order is taken and completed as the fill progresses; settled records
+/// never change. Histories live in persistent storage, one entry per order,
+/// so they outlast the order itself and its archived summary.
+pub struct FillHistoryManager;
+
+impl FillHistoryManager {
+    pub fn record_take(e: &Env, order: &Order, exchange_rate: i128) -> Result<(), ContractError> {
+        let mut fills = Self::get_order_fills(e, order.order_id);
+        fills.push_back(FillRecord {
+            filler: order.filler.clone().ok_or(ContractError::MissingFiller)?,
+            amount: order
+                .active_fill_amount
+                .ok_or(ContractError::MissingActiveFill)?,
+            exchange_rate,
+            taken_at: e.ledger().timestamp(),
+            submitted_at: None,
+            settled_at: None,
+            deadline_extended_secs: 0,
+            outcome: FillOutcome::Pending,
+        });
+        Self::store(e, order.order_id, &fills);
+
+        Ok(())
+    }
+
+    pub fn record_submission(e: &Env, order_id: u64) {
+        let now = e.ledger().timestamp();
+        Self::update_pending(e, order_id, |fill| fill.submitted_at = Some(now));
+    }
+
+    pub fn record_extension(e: &Env, order_id: u64, total_secs: u64) {
+        Self::update_pending(e, order_id, |fill| fill.deadline_extended_secs = total_secs);
+    }
+
+    pub fn record_settlement(e: &Env, order_id: u64, outcome: FillOutcome) {
+        let now = e.ledger().timestamp();
+        Self::update_pending(e, order_id, |fill| {
+            fill.settled_at = Some(now);
+            fill.outcome = outcome;
+        });
+    }
+
+    pub fn get_order_fills(e: &Env, order_id: u64) -> Vec<FillRecord> {
+        e.storage()
+            .persistent()
+            .get(&DataKey::FillHistory(order_id))
+            .unwrap_or(Vec::new(e))
+    }
+
+    /// Fills taken before histories were recorded have no pending record
+    /// and are left alone.
+    fn update_pending(e: &Env, order_id: u64, update: impl FnOnce(&mut FillRecord)) {
+        let mut fills = Self::get_order_fills(e, order_id);
+        let Some(index) = fills.len().checked_sub(1) else {
+            return;
+        };
+        let mut fill = fills.get_unchecked(index);
+        if fill.outcome != FillOutcome::Pending {
+            return;
+        }
+
+        update(&mut fill);
+        fills.set(index, fill);
+        Self::store(e, order_id, &fills);
+    }
+
+    fn store(e: &Env, order_id: u64, fills: &Vec<FillRecord>) {
+        let key = DataKey::FillHistory(order_id);
+        let max_ttl = e.storage().max_ttl();
+        e.storage().persistent().set(&key, fills);
+        e.storage().persistent().extend_ttl(&key, max_ttl, max_ttl);
+    }
+}
@@ -0,0 +1,240 @@
+use soroban_sdk::{Address, Env, Vec};
+
+use crate::core::admin::AdminManager;
+use crate::core::archive::ArchiveManager;
+use crate::core::history::FillHistoryManager;
+use crate::core::ledger::LedgerManager;
+use crate::core::validators::admin::ensure_admin;
+use crate::core::validators::insurance::{
+    ensure_approver, ensure_claim_pending, validate_claim_amount, validate_insurance_config,
+};
+use crate::error::ContractError;
+use crate::storage::types::{ClaimStatus, DataKey, InsuranceClaim, InsuranceConfig};
+
+/// Pending and paid claims an order can collect. Rejected claims do not
+/// count. Further claims fail with `TooManyClaims`.
+const MAX_CLAIMS_PER_ORDER: usize = 5;
+
+/// Insurance pool per token, funded by a cut of the protocol fee and by
+/// slashed resolver stake beyond what the wronged party lost. Pool tokens
+/// stay in the contract's liabilities, so they are never swept as surplus.
+/// Traders on an order claim against the pool and are paid once enough of
+/// the current approvers approve the claim.
+pub struct InsuranceManager;
+
+impl InsuranceManager {
+    pub fn set_insurance_config(
+        e: &Env,
+        caller: Address,
+        insurance_config: InsuranceConfig,
+    ) -> Result<(), ContractError> {
+        caller.require_auth();
+        let config = AdminManager::get_config(e)?;
+        ensure_admin(&config, &caller)?;
+        validate_insurance_config(&insurance_config)?;
+
+        e.storage()
+            .instance()
+            .set(&DataKey::InsuranceConfig, &insurance_config);
+
+        Ok(())
+    }
+
+    pub fn get_insurance_config(e: &Env) -> Option<InsuranceConfig> {
+        e.storage().instance().get(&DataKey::InsuranceConfig)
+    }
+
+    /// The pool's cut of `protocol_fee`, nothing until the pool is configured.
+    pub fn fee_share(e: &Env, protocol_fee: i128) -> Result<i128, ContractError> {
+        let Some(insurance_config) = Self::get_insurance_config(e) else {
+            return Ok(0);
+        };
+
+        protocol_fee
+            .checked_mul(insurance_config.fee_share_bps as i128)
+            .ok_or(ContractError::Overflow)?
+            .checked_div(10_000)
+            .ok_or(ContractError::DivisionError)
+    }
+
+    /// Credits tokens the contract already holds, such as a fee cut or
+    /// forfeited stake, to the token's pool. Returns the new pool balance.
+    pub fn fund(e: &Env, token: &Address, amount: i128) -> Result<i128, ContractError> {
+        let balance = Self::get_pool_balance(e, token.clone())
+            .checked_add(amount)
+            .ok_or(ContractError::Overflow)?;
+        e.storage()
+            .instance()
+            .set(&DataKey::InsurancePool(token.clone()), &balance);
+
+        Ok(balance)
+    }
+
+    /// Files a claim for a loss on the order. Only the order's creator and
+    /// its fillers can claim, and a claimant can have one pending claim per
+    /// order.
+    pub fn file_claim(
+        e: &Env,
+        caller: Address,
+        order_id: u64,
+        amount: i128,
+    ) -> Result<InsuranceClaim, ContractError> {
+        caller.require_auth();
+        let insurance_config =
+            Self::get_insurance_config(e).ok_or(ContractError::InsuranceNotConfigured)?;
+        validate_claim_amount(&insurance_config, amount)?;
+
+        let order = ArchiveManager::find_order(e, order_id)?;
+        let is_party = order.creator == caller
+            || FillHistoryManager::get_order_fills(e, order_id)
+                .iter()
+                .any(|fill| fill.filler == caller);
+        if !is_party {
+            return Err(ContractError::Unauthorized);
+        }
+
+        let claims = Self::get_order_claims(e, order_id);
+        for claim in claims.iter() {
+            if claim.claimant == caller && claim.status == ClaimStatus::Pending {
+                return Err(ContractError::ClaimAlreadyPending);
+            }
+        }
+        let counted = claims
+            .iter()
+            .filter(|claim| claim.status != ClaimStatus::Rejected)
+            .count();
+        if counted >= MAX_CLAIMS_PER_ORDER {
+            return Err(ContractError::TooManyClaims);
+        }
+        let mut claim_ids = Self::order_claim_ids(e, order_id);
+
+        let claim_id: u64 = e
+            .storage()
+            .instance()
+            .get(&DataKey::ClaimCount)
+            .unwrap_or(0);
+        let next = claim_id.checked_add(1).ok_or(ContractError::Overflow)?;
+        e.storage().instance().set(&DataKey::ClaimCount, &next);
+
+        let claim = InsuranceClaim {
+            claim_id,
+            order_id,
+            claimant: caller,
+            token: order.token,
+            amount,
+            approvals: Vec::new(e),
+            status: ClaimStatus::Pending,
+            filed_at: e.ledger().timestamp(),
+        };
+        Self::store_claim(e, &claim);
+        claim_ids.push_back(claim_id);
+        let key = DataKey::OrderClaims(order_id);
+        let max_ttl = e.storage().max_ttl();
+        e.storage().persistent().set(&key, &claim_ids);
+        e.storage().persistent().extend_ttl(&key, max_ttl, max_ttl);
+
+        Ok(claim)
+    }
+
+    /// Records the caller's approval and pays the claim from the pool once
+    /// the approval threshold is reached. The final approval fails if the
+    /// pool cannot cover the claim.
+    pub fn approve_claim(
+        e: &Env,
+        caller: Address,
+        claim_id: u64,
+    ) -> Result<InsuranceClaim, ContractError> {
+        caller.require_auth();
+        let insurance_config =
+            Self::get_insurance_config(e).ok_or(ContractError::InsuranceNotConfigured)?;
+        ensure_approver(&insurance_config, &caller)?;
+
+        let mut claim = Self::get_claim(e, claim_id)?;
+        ensure_claim_pending(&claim)?;
+        if claim.approvals.contains(&caller) {
+            return Err(ContractError::ClaimAlreadyApproved);
+        }
+        claim.approvals.push_back(caller);
+
+        // Approvals from approvers removed since do not count.
+        let approvals = claim
+            .approvals
+            .iter()
+            .filter(|approver| insurance_config.approvers.contains(approver))
+            .count() as u32;
+        if approvals >= insurance_config.threshold {
+            let balance = Self::get_pool_balance(e, claim.token.clone());
+            if balance < claim.amount {
+                return Err(ContractError::InsufficientInsurancePool);
+            }
+            e.storage().instance().set(
+                &DataKey::InsurancePool(claim.token.clone()),
+                &(balance - claim.amount),
+            );
+            LedgerManager::payout(e, &claim.token, &claim.claimant, claim.amount)?;
+            claim.status = ClaimStatus::Paid;
+        }
+        Self::store_claim(e, &claim);
+
+        Ok(claim)
+    }
+
+    /// Rejects a pending claim. Any single approver can reject.
+    pub fn reject_claim(
+        e: &Env,
+        caller: Address,
+        claim_id: u64,
+    ) -> Result<InsuranceClaim, ContractError> {
+        caller.require_auth();
+        let insurance_config =
+            Self::get_insurance_config(e).ok_or(ContractError::InsuranceNotConfigured)?;
+        ensure_approver(&insurance_config, &caller)?;
+
+        let mut claim = Self::get_claim(e, claim_id)?;
+        ensure_claim_pending(&claim)?;
+
+        claim.status = ClaimStatus::Rejected;
+        Self::store_claim(e, &claim);
+
+        Ok(claim)
+    }
+
+    pub fn get_pool_balance(e: &Env, token: Address) -> i128 {
+        e.storage()
+            .instance()
+            .get(&DataKey::InsurancePool(token))
+            .unwrap_or(0)
+    }
+
+    pub fn get_claim(e: &Env, claim_id: u64) -> Result<InsuranceClaim, ContractError> {
+        e.storage()
+            .persistent()
+            .get(&DataKey::Claim(claim_id))
+            .ok_or(ContractError::ClaimNotFound)
+    }
+
+    /// Every claim filed on the order, oldest first.
+    pub fn get_order_claims(e: &Env, order_id: u64) -> Vec<InsuranceClaim> {
+        let mut claims = Vec::new(e);
+        for claim_id in Self::order_claim_ids(e, order_id).iter() {
+            if let Ok(claim) = Self::get_claim(e, claim_id) {
+                claims.push_back(claim);
+            }
+        }
+        claims
+    }
+
+    fn order_claim_ids(e: &Env, order_id: u64) -> Vec<u64> {
+        e.storage()
+            .persistent()
+            .get(&DataKey::OrderClaims(order_id))
+            .unwrap_or(Vec::new(e))
+    }
+
+    fn store_claim(e: &Env, claim: &InsuranceClaim) {
+        let key = DataKey::Claim(claim.claim_id);
+        let max_ttl = e.storage().max_ttl();
+        e.storage().persistent().set(&key, claim);
+        e.storage().persistent().extend_ttl(&key, max_ttl, max_ttl);
+    }
+}
@@ -0,0 +1,130 @@
+use soroban_sdk::token::Client as TokenClient;
+use soroban_sdk::{Address, Env};
+
+use crate::core::admin::AdminManager;
+use crate::core::migration::{MigrationManager, CURRENT_SCHEMA_VERSION};
+use crate::core::validators::admin::ensure_admin;
+use crate::error::ContractError;
+use crate::storage::types::{DataKey, SolvencyReport};
+
+/// Moves tokens in and out of the contract while keeping a running total of
+/// what it owes depositors, per token. Every transfer of user funds must go
+/// through `deposit` or `payout` so the total never drifts from the orders.
+pub struct LedgerManager;
+
+impl LedgerManager {
+    pub fn deposit(
+        e: &Env,
+        token: &Address,
+        from: &Address,
+        amount: i128,
+    ) -> Result<(), ContractError> {
+        TokenClient::new(e, token).transfer(from, e.current_contract_address(), &amount);
+
+        let liabilities = Self::get_liabilities(e, token)
+            .checked_add(amount)
+            .ok_or(ContractError::Overflow)?;
+        Self::set_liabilities(e, token, liabilities);
+
+        Ok(())
+    }
+
+    /// Takes a deposit through the allowance `from` gave the contract, for
+    /// deposits an operator makes on the owner's behalf.
+    pub fn deposit_approved(
+        e: &Env,
+        token: &Address,
+        from: &Address,
+        amount: i128,
+    ) -> Result<(), ContractError> {
+        let contract = e.current_contract_address();
+        TokenClient::new(e, token).transfer_from(&contract, from, &contract, &amount);
+
+        let liabilities = Self::get_liabilities(e, token)
+            .checked_add(amount)
+            .ok_or(ContractError::Overflow)?;
+        Self::set_liabilities(e, token, liabilities);
+
+        Ok(())
+    }
+
+    pub fn payout(
+        e: &Env,
+        token: &Address,
+        to: &Address,
+        amount: i128,
+    ) -> Result<(), ContractError> {
+        TokenClient::new(e, token).transfer(&e.current_contract_address(), to, &amount);
+
+        let liabilities = Self::get_liabilities(e, token)
+            .checked_sub(amount)
+            .ok_or(ContractError::Underflow)?;
+        Self::set_liabilities(e, token, liabilities);
+
+        Ok(())
+    }
+
+    /// Records tokens that reached the contract without a `deposit`, such as
+    /// a fill returned by its settlement escrow.
+    pub fn receive(e: &Env, token: &Address, amount: i128) -> Result<(), ContractError> {
+        let liabilities = Self::get_liabilities(e, token)
+            .checked_add(amount)
+            .ok_or(ContractError::Overflow)?;
+        Self::set_liabilities(e, token, liabilities);
+
+        Ok(())
+    }
+
+    pub fn get_liabilities(e: &Env, token: &Address) -> i128 {
+        e.storage()
+            .instance()
+            .get(&DataKey::Liabilities(token.clone()))
+            .unwrap_or(0)
+    }
+
+    pub fn set_liabilities(e: &Env, token: &Address, liabilities: i128) {
+        e.storage()
+            .instance()
+            .set(&DataKey::Liabilities(token.clone()), &liabilities);
+    }
+
+    pub fn check_solvency(e: &Env, token: Address) -> SolvencyReport {
+        let liabilities = Self::get_liabilities(e, &token);
+        let balance = TokenClient::new(e, &token).balance(&e.current_contract_address());
+
+        SolvencyReport {
+            token,
+            liabilities,
+            balance,
+            surplus: balance - liabilities,
+            solvent: balance >= liabilities,
+        }
+    }
+
+    /// Sends tokens held above the recorded liabilities to `to`. Totals are
+    /// only trusted once the store has been migrated, since older layouts did
+    /// not record them.
+    pub fn sweep_surplus(
+        e: &Env,
+        caller: Address,
+        token: Address,
+        to: Address,
+    ) -> Result<i128, ContractError> {
+        caller.require_auth();
+        let config = AdminManager::get_config(e)?;
+        ensure_admin(&config, &caller)?;
+
+        if MigrationManager::get_schema_version(e) != CURRENT_SCHEMA_VERSION {
+            return Err(ContractError::MigrationPending);
+        }
+
+        let report = Self::check_solvency(e, token.clone());
+        if report.surplus <= 0 {
+            return Err(ContractError::NoSurplus);
+        }
+
+        TokenClient::new(e, &token).transfer(&e.current_contract_address(), &to, &report.surplus);
+
+        Ok(report.surplus)
+    }
+}
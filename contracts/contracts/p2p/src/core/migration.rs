@@ -0,0 +1,282 @@
+use soroban_sdk::{Address, BytesN, Env};
+
+use crate::core::admin::AdminManager;
+use crate::core::ledger::LedgerManager;
+use crate::core::registry::RegistryManager;
+use crate::core::validators::admin::ensure_admin;
+use crate::error::ContractError;
+use crate::storage::legacy::{ConfigV1, OrderV1, OrderV2};
+use crate::storage::types::{
+    Config, DataKey, MigrationProgress, Order, OrderStatus, OrderVisibility,
+};
+
+/// Storage layout written by this build. Bump it together with a new
+/// migration step whenever `Config` or `Order` change shape.
+pub const CURRENT_SCHEMA_VERSION: u32 = 5;
+
+/// Deployments created before versioning have no stored version.
+const UNVERSIONED_SCHEMA_VERSION: u32 = 1;
+
+/// Orders a single `migrate` call rewrites at most.
+const MAX_ORDERS_PER_MIGRATION: u32 = 50;
+
+pub struct MigrationManager;
+
+impl MigrationManager {
+    pub fn upgrade(
+        e: &Env,
+        caller: Address,
+        new_wasm_hash: BytesN<32>,
+    ) -> Result<(), ContractError> {
+        caller.require_auth();
+        let config = AdminManager::get_config(e)?;
+        ensure_admin(&config, &caller)?;
+
+        e.deployer().update_current_contract_wasm(new_wasm_hash);
+
+        Ok(())
+    }
+
+    /// Rewrites stored records into the current layout one version step at
+    /// a time, rewriting at most `limit` orders per call. An unfinished step
+    /// is resumed by the next call, and the stored version only moves once
+    /// a step has rewritten every order. Running it again once the store is
+    /// current is a no-op, so it is safe to retry.
+    pub fn migrate(e: &Env, caller: Address, limit: u32) -> Result<(u32, u32), ContractError> {
+        caller.require_auth();
+        let from_version = Self::get_schema_version(e);
+        if from_version > CURRENT_SCHEMA_VERSION {
+            return Err(ContractError::UnsupportedSchemaVersion);
+        }
+
+        if caller != Self::stored_admin(e, from_version)? {
+            return Err(ContractError::Unauthorized);
+        }
+
+        let order_count = AdminManager::get_order_count(e)?;
+        let mut budget = limit.min(MAX_ORDERS_PER_MIGRATION) as u64;
+        let mut progress = e
+            .storage()
+            .instance()
+            .get(&DataKey::MigrationProgress)
+            .unwrap_or(MigrationProgress {
+                version: from_version,
+                next_order_id: 0,
+                liabilities: 0,
+            });
+
+        while progress.version < CURRENT_SCHEMA_VERSION {
+            // Version 4 only touches the registries, so it has no orders
+            // to page through.
+            if progress.version != 3 {
+                let end = order_count.min(progress.next_order_id.saturating_add(budget));
+                for order_id in progress.next_order_id..end {
+                    match progress.version {
+                        1 => Self::migrate_order_v1_to_v2(e, order_id),
+                        2 => {
+                            progress.liabilities = progress
+                                .liabilities
+                                .checked_add(Self::held_amount_v2(e, order_id))
+                                .ok_or(ContractError::Overflow)?;
+                        }
+                        4 => Self::migrate_order_v4_to_v5(e, order_id)?,
+                        _ => return Err(ContractError::UnsupportedSchemaVersion),
+                    }
+                }
+                budget -= end - progress.next_order_id;
+                progress.next_order_id = end;
+            } else {
+                progress.next_order_id = order_count;
+            }
+
+            if progress.next_order_id < order_count {
+                e.storage()
+                    .instance()
+                    .set(&DataKey::MigrationProgress, &progress);
+                return Ok((from_version, progress.version));
+            }
+
+            match progress.version {
+                1 => Self::migrate_config_v1_to_v2(e)?,
+                2 => Self::finish_v2_to_v3(e, progress.liabilities)?,
+                3 => Self::migrate_v3_to_v4(e),
+                _ => {}
+            }
+            progress = MigrationProgress {
+                version: progress.version + 1,
+                next_order_id: 0,
+                liabilities: 0,
+            };
+            e.storage()
+                .instance()
+                .set(&DataKey::SchemaVersion, &progress.version);
+        }
+
+        e.storage().instance().remove(&DataKey::MigrationProgress);
+        Ok((from_version, progress.version))
+    }
+
+    pub fn get_schema_version(e: &Env) -> u32 {
+        e.storage()
+            .instance()
+            .get(&DataKey::SchemaVersion)
+            .unwrap_or(UNVERSIONED_SCHEMA_VERSION)
+    }
+
+    pub fn set_current_schema_version(e: &Env) {
+        e.storage()
+            .instance()
+            .set(&DataKey::SchemaVersion, &CURRENT_SCHEMA_VERSION);
+    }
+
+    fn stored_admin(e: &Env, version: u32) -> Result<Address, ContractError> {
+        if version == UNVERSIONED_SCHEMA_VERSION {
+            let legacy: ConfigV1 = e
+                .storage()
+                .instance()
+                .get(&DataKey::Config)
+                .ok_or(ContractError::ConfigNotInitialized)?;
+            return Ok(legacy.admin);
+        }
+
+        Ok(AdminManager::get_config(e)?.admin)
+    }
+
+    /// Version 2 added the compliance role, order freezing and private
+    /// orders. The compliance role defaults to the admin.
+    fn migrate_config_v1_to_v2(e: &Env) -> Result<(), ContractError> {
+        let legacy: ConfigV1 = e
+            .storage()
+            .instance()
+            .get(&DataKey::Config)
+            .ok_or(ContractError::ConfigNotInitialized)?;
+
+        let config = Config {
+            admin: legacy.admin.clone(),
+            dispute_resolver: legacy.dispute_resolver,
+            pauser: legacy.pauser,
+            compliance: legacy.admin,
+            token: legacy.token,
+            max_duration_secs: legacy.max_duration_secs,
+            filler_payment_timeout_secs: legacy.filler_payment_timeout_secs,
+            paused: legacy.paused,
+        };
+        e.storage().instance().set(&DataKey::Config, &config);
+
+        Ok(())
+    }
+
+    fn migrate_order_v1_to_v2(e: &Env, order_id: u64) {
+        let key = DataKey::Order(order_id);
+        let legacy: Option<OrderV1> = e.storage().instance().get(&key);
+        if let Some(legacy) = legacy {
+            let order = OrderV2 {
+                order_id: legacy.order_id,
+                creator: legacy.creator,
+                filler: legacy.filler,
+                token: legacy.token,
+                amount: legacy.amount,
+                remaining_amount: legacy.remaining_amount,
+                filled_amount: legacy.filled_amount,
+                active_fill_amount: legacy.active_fill_amount,
+                exchange_rate: legacy.exchange_rate,
+                from_crypto: legacy.from_crypto,
+                fiat_currency: legacy.fiat_currency,
+                payment_method: legacy.payment_method,
+                status: legacy.status,
+                created_at: legacy.created_at,
+                deadline: legacy.deadline,
+                fiat_transfer_deadline: legacy.fiat_transfer_deadline,
+                frozen: false,
+                visibility: OrderVisibility::Public,
+            };
+            e.storage().instance().set(&key, &order);
+        }
+    }
+
+    /// Version 3 records the tokens owed to depositors. The totals are
+    /// rebuilt from every order that still holds funds.
+    fn finish_v2_to_v3(e: &Env, liabilities: i128) -> Result<(), ContractError> {
+        let config = AdminManager::get_config(e)?;
+        LedgerManager::set_liabilities(e, &config.token, liabilities);
+
+        Ok(())
+    }
+
+    fn held_amount_v2(e: &Env, order_id: u64) -> i128 {
+        let order: Option<OrderV2> = e.storage().instance().get(&DataKey::Order(order_id));
+        order.map_or(0, |order| {
+            Self::held_amount(
+                &order.status,
+                order.from_crypto,
+                order.remaining_amount,
+                order.active_fill_amount,
+            )
+        })
+    }
+
+    /// Version 4 validates markets against the currency and payment method
+    /// registries, seeded with the markets accepted before them.
+    fn migrate_v3_to_v4(e: &Env) {
+        RegistryManager::seed_defaults(e);
+    }
+
+    /// Version 5 adds settlement timeouts to orders. Active fills were
+    /// given the global timeout, so that is what they record.
+    fn migrate_order_v4_to_v5(e: &Env, order_id: u64) -> Result<(), ContractError> {
+        let key = DataKey::Order(order_id);
+        let legacy: Option<OrderV2> = e.storage().instance().get(&key);
+        if let Some(legacy) = legacy {
+            let config = AdminManager::get_config(e)?;
+            let payment_timeout_secs = legacy
+                .fiat_transfer_deadline
+                .map(|_| config.filler_payment_timeout_secs);
+            let order = Order {
+                order_id: legacy.order_id,
+                creator: legacy.creator,
+                filler: legacy.filler,
+                token: legacy.token,
+                amount: legacy.amount,
+                remaining_amount: legacy.remaining_amount,
+                filled_amount: legacy.filled_amount,
+                active_fill_amount: legacy.active_fill_amount,
+                exchange_rate: legacy.exchange_rate,
+                from_crypto: legacy.from_crypto,
+                fiat_currency: legacy.fiat_currency,
+                payment_method: legacy.payment_method,
+                status: legacy.status,
+                created_at: legacy.created_at,
+                deadline: legacy.deadline,
+                fiat_transfer_deadline: legacy.fiat_transfer_deadline,
+                frozen: legacy.frozen,
+                visibility: legacy.visibility,
+                requested_timeout_secs: None,
+                payment_timeout_secs,
+            };
+            e.storage().instance().set(&key, &order);
+        }
+
+        Ok(())
+    }
+
+    fn held_amount(
+        status: &OrderStatus,
+        from_crypto: bool,
+        remaining_amount: i128,
+        active_fill_amount: Option<i128>,
+    ) -> i128 {
+        match status {
+            OrderStatus::AwaitingFiller
+            | OrderStatus::AwaitingPayment
+            | OrderStatus::AwaitingConfirmation
+            | OrderStatus::Disputed => {
+                if from_crypto {
+                    remaining_amount
+                } else {
+                    active_fill_amount.unwrap_or(0)
+                }
+            }
+            _ => 0,
+        }
+    }
+}
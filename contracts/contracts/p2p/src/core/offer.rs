@@ -0,0 +1,276 @@
+use soroban_sdk::{Address, Env, Vec};
+
+use crate::core::admin::AdminManager;
+use crate::core::archive::ArchiveManager;
+use crate::core::ledger::LedgerManager;
+use crate::core::order::OrderManager;
+use crate::core::validators::admin::{ensure_not_paused, PausableOperation};
+use crate::core::validators::compliance::{ensure_not_blocked, ensure_not_frozen};
+use crate::core::validators::offer::{ensure_offer_not_expired, ensure_offer_open, validate_offer};
+use crate::core::validators::order::{
+    ensure_creator, ensure_not_creator, ensure_not_expired, ensure_status, ensure_taker_allowed,
+    validate_fill_amount,
+};
+use crate::error::ContractError;
+use crate::storage::types::{DataKey, Offer, OfferStatus, Order, OrderStatus};
+
+/// Open offers an order can collect. A full order refunds its expired
+/// offers to make room; otherwise further offers fail with `TooManyOffers`
+/// until the creator accepts or rejects one.
+const MAX_OPEN_OFFERS: u32 = 20;
+
+/// Open offers a single taker can hold on one order. Offers on orders that
+/// sell crypto carry no deposit, so this keeps one taker from filling every
+/// slot for free.
+const MAX_OPEN_OFFERS_PER_TAKER: u32 = 3;
+
+/// Counter-offers from takers who want a different rate or amount than an
+/// order was posted with. Takers selling crypto deposit it with the offer.
+/// Accepting an offer starts the fill at the offered rate; rejected offers
+/// and offers that can no longer be accepted are refunded.
+pub struct OfferManager;
+
+impl OfferManager {
+    pub fn make_offer(
+        e: &Env,
+        caller: Address,
+        order_id: u64,
+        amount: i128,
+        exchange_rate: i128,
+        valid_for_secs: u64,
+    ) -> Result<(Offer, Vec<Offer>), ContractError> {
+        caller.require_auth();
+        let config = AdminManager::get_config(e)?;
+        ensure_not_paused(e, &config, PausableOperation::Takes)?;
+
+        let now = e.ledger().timestamp();
+        let order = OrderManager::get_order(e, order_id)?;
+        ensure_status(&order, OrderStatus::AwaitingFiller)?;
+        ensure_not_frozen(&order)?;
+        ensure_not_expired(&order, now)?;
+        ensure_not_blocked(e, &caller)?;
+        ensure_not_creator(&order, &caller)?;
+        ensure_taker_allowed(e, &order, &caller, &None)?;
+        validate_offer(amount, exchange_rate, valid_for_secs, &config)?;
+        validate_fill_amount(&order, amount)?;
+
+        let mut expired = Vec::new(e);
+        if Self::order_offer_ids(e, order_id).len() >= MAX_OPEN_OFFERS {
+            expired = Self::refund_expired_offers(e, &config.token, order_id, now)?;
+        }
+        let mut offer_ids = Self::order_offer_ids(e, order_id);
+        if offer_ids.len() >= MAX_OPEN_OFFERS {
+            return Err(ContractError::TooManyOffers);
+        }
+        let taker_offers = Self::get_order_offers(e, order_id)
+            .iter()
+            .filter(|offer| offer.taker == caller)
+            .count();
+        if taker_offers >= MAX_OPEN_OFFERS_PER_TAKER as usize {
+            return Err(ContractError::TooManyOffersFromTaker);
+        }
+
+        let deposited = if order.from_crypto { 0 } else { amount };
+        if deposited > 0 {
+            LedgerManager::deposit(e, &config.token, &caller, deposited)?;
+        }
+
+        let offer_id: u64 = e
+            .storage()
+            .instance()
+            .get(&DataKey::OfferCount)
+            .unwrap_or(0);
+        let next = offer_id.checked_add(1).ok_or(ContractError::Overflow)?;
+        e.storage().instance().set(&DataKey::OfferCount, &next);
+
+        let offer = Offer {
+            offer_id,
+            order_id,
+            taker: caller,
+            amount,
+            exchange_rate,
+            deposited,
+            created_at: now,
+            expires_at: now + valid_for_secs,
+            status: OfferStatus::Open,
+        };
+        Self::store_offer(e, &offer);
+        offer_ids.push_back(offer_id);
+        e.storage()
+            .instance()
+            .set(&DataKey::OrderOffers(order_id), &offer_ids);
+
+        Ok((offer, expired))
+    }
+
+    /// Starts the offered fill at the offered rate, funded by the taker's
+    /// deposit where there is one.
+    pub fn accept_offer(
+        e: &Env,
+        caller: Address,
+        offer_id: u64,
+    ) -> Result<(Offer, Order), ContractError> {
+        caller.require_auth();
+        let config = AdminManager::get_config(e)?;
+        ensure_not_paused(e, &config, PausableOperation::Takes)?;
+
+        let mut offer = Self::get_offer(e, offer_id)?;
+        ensure_offer_open(&offer)?;
+        ensure_offer_not_expired(&offer, e.ledger().timestamp())?;
+        ensure_creator(&OrderManager::get_order(e, offer.order_id)?, &caller)?;
+
+        let order = OrderManager::fill_at_rate(
+            e,
+            &config,
+            offer.taker.clone(),
+            offer.order_id,
+            offer.amount,
+            offer.exchange_rate,
+            offer.deposited > 0,
+        )?;
+
+        offer.status = OfferStatus::Accepted;
+        Self::close_offer(e, &offer);
+
+        Ok((offer, order))
+    }
+
+    pub fn reject_offer(e: &Env, caller: Address, offer_id: u64) -> Result<Offer, ContractError> {
+        caller.require_auth();
+        let config = AdminManager::get_config(e)?;
+
+        let mut offer = Self::get_offer(e, offer_id)?;
+        ensure_offer_open(&offer)?;
+        ensure_creator(&ArchiveManager::find_order(e, offer.order_id)?, &caller)?;
+
+        Self::refund(e, &config.token, &offer)?;
+        offer.status = OfferStatus::Rejected;
+        Self::close_offer(e, &offer);
+
+        Ok(offer)
+    }
+
+    /// Refunds an offer that can no longer be accepted, because it expired
+    /// or its order stopped taking fills. Callable by anyone.
+    pub fn refund_offer(e: &Env, offer_id: u64) -> Result<Offer, ContractError> {
+        let config = AdminManager::get_config(e)?;
+
+        let mut offer = Self::get_offer(e, offer_id)?;
+        ensure_offer_open(&offer)?;
+
+        let now = e.ledger().timestamp();
+        let order_open = match OrderManager::get_order(e, offer.order_id) {
+            Ok(order) => {
+                order.status == OrderStatus::AwaitingFiller && !order.frozen && order.deadline > now
+            }
+            Err(_) => false,
+        };
+        if order_open && offer.expires_at > now {
+            return Err(ContractError::OfferStillOpen);
+        }
+
+        Self::refund(e, &config.token, &offer)?;
+        offer.status = OfferStatus::Refunded;
+        Self::close_offer(e, &offer);
+
+        Ok(offer)
+    }
+
+    /// Refunds the offers still open on a finished order and drops its
+    /// offer list. Returns the refunded offers.
+    pub fn refund_order_offers(
+        e: &Env,
+        token: &Address,
+        order_id: u64,
+    ) -> Result<Vec<Offer>, ContractError> {
+        let mut refunded = Vec::new(e);
+        for mut offer in Self::get_order_offers(e, order_id).iter() {
+            Self::refund(e, token, &offer)?;
+            offer.status = OfferStatus::Refunded;
+            Self::store_offer(e, &offer);
+            refunded.push_back(offer);
+        }
+        e.storage()
+            .instance()
+            .remove(&DataKey::OrderOffers(order_id));
+
+        Ok(refunded)
+    }
+
+    pub fn get_offer(e: &Env, offer_id: u64) -> Result<Offer, ContractError> {
+        e.storage()
+            .persistent()
+            .get(&DataKey::Offer(offer_id))
+            .ok_or(ContractError::OfferNotFound)
+    }
+
+    /// The order's open offers, oldest first.
+    pub fn get_order_offers(e: &Env, order_id: u64) -> Vec<Offer> {
+        let mut offers = Vec::new(e);
+        for offer_id in Self::order_offer_ids(e, order_id).iter() {
+            if let Ok(offer) = Self::get_offer(e, offer_id) {
+                offers.push_back(offer);
+            }
+        }
+        offers
+    }
+
+    /// Refunds the order's expired offers and returns them.
+    fn refund_expired_offers(
+        e: &Env,
+        token: &Address,
+        order_id: u64,
+        now: u64,
+    ) -> Result<Vec<Offer>, ContractError> {
+        let mut refunded = Vec::new(e);
+        for mut offer in Self::get_order_offers(e, order_id).iter() {
+            if offer.expires_at > now {
+                continue;
+            }
+            Self::refund(e, token, &offer)?;
+            offer.status = OfferStatus::Refunded;
+            Self::close_offer(e, &offer);
+            refunded.push_back(offer);
+        }
+
+        Ok(refunded)
+    }
+
+    fn refund(e: &Env, token: &Address, offer: &Offer) -> Result<(), ContractError> {
+        if offer.deposited > 0 {
+            LedgerManager::payout(e, token, &offer.taker, offer.deposited)?;
+        }
+
+        Ok(())
+    }
+
+    /// Stores a settled offer and drops it from its order's open offers.
+    fn close_offer(e: &Env, offer: &Offer) {
+        Self::store_offer(e, offer);
+
+        let mut offer_ids = Self::order_offer_ids(e, offer.order_id);
+        if let Some(index) = offer_ids.first_index_of(offer.offer_id) {
+            offer_ids.remove(index);
+        }
+        let key = DataKey::OrderOffers(offer.order_id);
+        if offer_ids.is_empty() {
+            e.storage().instance().remove(&key);
+        } else {
+            e.storage().instance().set(&key, &offer_ids);
+        }
+    }
+
+    fn order_offer_ids(e: &Env, order_id: u64) -> Vec<u64> {
+        e.storage()
+            .instance()
+            .get(&DataKey::OrderOffers(order_id))
+            .unwrap_or(Vec::new(e))
+    }
+
+    fn store_offer(e: &Env, offer: &Offer) {
+        let key = DataKey::Offer(offer.offer_id);
+        let max_ttl = e.storage().max_ttl();
+        e.storage().persistent().set(&key, offer);
+        e.storage().persistent().extend_ttl(&key, max_ttl, max_ttl);
+    }
+}
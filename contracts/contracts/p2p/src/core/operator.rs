@@ -0,0 +1,134 @@
+use soroban_sdk::{Address, Env, IntoVal, Val, Vec};
+
+use crate::core::validators::operator::{
+    ensure_operator_scope, ensure_within_volume_cap, validate_operator_grant,
+};
+use crate::error::ContractError;
+use crate::storage::types::{DataKey, OperatorGrant, OperatorKey, OperatorScope, OperatorUsage};
+
+/// Operators an owner can have at once. Granting more fails with
+/// `TooManyOperators`.
+const MAX_OPERATORS_PER_OWNER: u32 = 10;
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Delegation from traders to operator keys, so automated trading does not
+/// need the key that owns the funds. Operators act on the owner's orders
+/// within the scopes they were granted; withdrawals, claims and anything
+/// else that pays out stay with the owner.
+pub struct OperatorManager;
+
+impl OperatorManager {
+    pub fn set_operator(
+        e: &Env,
+        owner: Address,
+        operator: Address,
+        grant: OperatorGrant,
+    ) -> Result<(), ContractError> {
+        owner.require_auth();
+        validate_operator_grant(&owner, &operator, &grant, e.ledger().timestamp())?;
+
+        let mut operators = Self::get_operators(e, owner.clone());
+        if !operators.contains(&operator) {
+            if operators.len() >= MAX_OPERATORS_PER_OWNER {
+                return Err(ContractError::TooManyOperators);
+            }
+            operators.push_back(operator.clone());
+            Self::store(e, OperatorKey::Operators(owner.clone()), &operators);
+        }
+
+        Self::store(e, OperatorKey::Grant(owner, operator), &grant);
+
+        Ok(())
+    }
+
+    pub fn revoke_operator(
+        e: &Env,
+        owner: Address,
+        operator: Address,
+    ) -> Result<(), ContractError> {
+        owner.require_auth();
+
+        let mut operators = Self::get_operators(e, owner.clone());
+        let index = operators
+            .first_index_of(&operator)
+            .ok_or(ContractError::OperatorNotFound)?;
+        operators.remove(index);
+        Self::store(e, OperatorKey::Operators(owner.clone()), &operators);
+        e.storage()
+            .persistent()
+            .remove(&DataKey::Operator(OperatorKey::Grant(
+                owner.clone(),
+                operator.clone(),
+            )));
+        e.storage()
+            .persistent()
+            .remove(&DataKey::Operator(OperatorKey::Usage(owner, operator)));
+
+        Ok(())
+    }
+
+    /// Accepts `caller` acting for `owner`: the owner itself, or an operator
+    /// granted `scope`. Operator calls add `volume` to the day's usage.
+    /// Callers have already required the caller's auth.
+    pub fn authorize(
+        e: &Env,
+        owner: &Address,
+        caller: &Address,
+        scope: OperatorScope,
+        volume: i128,
+    ) -> Result<(), ContractError> {
+        if caller == owner {
+            return Ok(());
+        }
+
+        let grant = Self::get_operator(e, owner.clone(), caller.clone())
+            .ok_or(ContractError::Unauthorized)?;
+        let now = e.ledger().timestamp();
+        ensure_operator_scope(&grant, &scope, now)?;
+
+        if volume > 0 {
+            let day = now / SECONDS_PER_DAY;
+            let mut usage = Self::get_usage(e, owner.clone(), caller.clone());
+            if usage.day != day {
+                usage = OperatorUsage { day, volume: 0 };
+            }
+            usage.volume = usage
+                .volume
+                .checked_add(volume)
+                .ok_or(ContractError::Overflow)?;
+            ensure_within_volume_cap(&grant, &usage)?;
+            Self::store(e, OperatorKey::Usage(owner.clone(), caller.clone()), &usage);
+        }
+
+        Ok(())
+    }
+
+    pub fn get_operator(e: &Env, owner: Address, operator: Address) -> Option<OperatorGrant> {
+        e.storage()
+            .persistent()
+            .get(&DataKey::Operator(OperatorKey::Grant(owner, operator)))
+    }
+
+    pub fn get_operators(e: &Env, owner: Address) -> Vec<Address> {
+        e.storage()
+            .persistent()
+            .get(&DataKey::Operator(OperatorKey::Operators(owner)))
+            .unwrap_or(Vec::new(e))
+    }
+
+    /// Volume the operator used on its last active day.
+    pub fn get_usage(e: &Env, owner: Address, operator: Address) -> OperatorUsage {
+        e.storage()
+            .persistent()
+            .get(&DataKey::Operator(OperatorKey::Usage(owner, operator)))
+            .unwrap_or_default()
+    }
+
+    fn store<V: IntoVal<Env, Val>>(e: &Env, key: OperatorKey, value: &V) {
+        let key = DataKey::Operator(key);
+        let max_ttl = e.storage().max_ttl();
+        e.storage().persistent().set(&key, value);
+        e.storage().persistent().extend_ttl(&key, max_ttl, max_ttl);
+    }
+}
@@ -1,40 +1,239 @@
-use soroban_sdk::token::Client as TokenClient;
-use soroban_sdk::{Address, Env};
+use soroban_sdk::{vec, Address, Bytes, BytesN, Env, Map, Vec};
 
+use crate::core::ad::AdManager;
 use crate::core::admin::AdminManager;
-use crate::core::validators::admin::ensure_not_paused;
+use crate::core::archive::ArchiveManager;
+use crate::core::breaker::{CircuitBreakerManager, FillEvent};
+use crate::core::escrow::EscrowManager;
+use crate::core::fees::{FeeCharge, FeeManager};
+use crate::core::history::FillHistoryManager;
+use crate::core::ledger::LedgerManager;
+use crate::core::operator::OperatorManager;
+use crate::core::stats::MarketStatsManager;
+use crate::core::validators::admin::{
+    ensure_emergency_exit_open, ensure_not_paused, PausableOperation,
+};
+use crate::core::validators::compliance::{ensure_not_blocked, ensure_not_frozen};
 use crate::core::validators::order::{
     ensure_active_fill_amount, ensure_creator, ensure_fiat_timeout_expired, ensure_filler,
-    ensure_not_creator, ensure_not_expired, ensure_status, validate_create_order,
-    validate_fill_amount,
+    ensure_hold_expired, ensure_market, ensure_not_creator, ensure_not_expired, ensure_status,
+    ensure_taker_allowed, validate_create_order, validate_deadline_extension, validate_fill_amount,
+    validate_requested_timeout, validate_sweep, validate_visibility, MAX_BATCH_SIZE,
 };
+use crate::core::validators::registry::ensure_market_supported;
 use crate::error::ContractError;
-use crate::storage::types::{DataKey, FiatCurrency, Order, OrderStatus, PaymentMethod};
+use crate::storage::types::{
+    Config, DataKey, FiatCurrency, FillOutcome, MerchantAd, OperatorScope, Order, OrderFill,
+    OrderParams, OrderStatus, OrderVisibility, PaymentMethod,
+};
+
+const MAX_ORDERS_PER_PAGE: u32 = 50;
+
+/// Funds moved when an active fill is settled. `paid_to` is `None` when the
+/// funds stay in the contract, e.g. a creator's deposit after a timeout.
+/// Escrowed fills report what the recipient received after escrow fees, and
+/// others what was left after the protocol fee in `fee`.
+pub struct Settlement {
+    pub filler: Address,
+    pub paid_to: Option<Address>,
+    pub amount: i128,
+    pub fee: Option<FeeCharge>,
+}
 
 pub struct OrderManager;
 
 impl OrderManager {
+    /// Returns the order and whether it was created. A `client_ref` the
+    /// creator already used returns that order instead, so retried
+    /// submissions do not open duplicates. `caller` is the creator or one of
+    /// its operators, whose deposits are drawn from the creator's allowance
+    /// to the contract.
     pub fn create_order(
         e: &Env,
         caller: Address,
+        creator: Address,
+        params: OrderParams,
+    ) -> Result<(Order, bool), ContractError> {
+        caller.require_auth();
+        if let Some(order) = Self::find_by_ref(e, &creator, &params.client_ref) {
+            return Ok((order, false));
+        }
+
+        let config = AdminManager::get_config(e)?;
+        ensure_not_paused(e, &config, PausableOperation::NewOrders)?;
+        let order = Self::open_order(e, &config, &caller, &creator, params)?;
+
+        if order.from_crypto {
+            if caller == creator {
+                LedgerManager::deposit(e, &config.token, &creator, order.amount)?;
+            } else {
+                LedgerManager::deposit_approved(e, &config.token, &creator, order.amount)?;
+            }
+        }
+
+        Ok((order, true))
+    }
+
+    /// Creates the caller's orders with one deposit for all `from_crypto`
+    /// orders in the batch. Returns each item's order id, which is `None`
+    /// for items that failed when the batch is not `all_or_nothing`, and the
+    /// orders created.
+    pub fn create_orders(
+        e: &Env,
+        caller: Address,
+        params: Vec<OrderParams>,
+        all_or_nothing: bool,
+    ) -> Result<(Vec<Option<u64>>, Vec<Order>), ContractError> {
+        caller.require_auth();
+        let config = AdminManager::get_config(e)?;
+        ensure_not_paused(e, &config, PausableOperation::NewOrders)?;
+        if params.len() > MAX_BATCH_SIZE {
+            return Err(ContractError::BatchTooLarge);
+        }
+
+        let mut order_ids = Vec::new(e);
+        let mut created = Vec::new(e);
+        let mut deposit: i128 = 0;
+        for item in params.iter() {
+            if let Some(order) = Self::find_by_ref(e, &caller, &item.client_ref) {
+                order_ids.push_back(Some(order.order_id));
+                continue;
+            }
+
+            match Self::open_order(e, &config, &caller, &caller, item) {
+                Ok(order) => {
+                    if order.from_crypto {
+                        deposit = deposit
+                            .checked_add(order.amount)
+                            .ok_or(ContractError::Overflow)?;
+                    }
+                    order_ids.push_back(Some(order.order_id));
+                    created.push_back(order);
+                }
+                Err(err) if all_or_nothing => return Err(err),
+                Err(_) => order_ids.push_back(None),
+            }
+        }
+
+        if deposit > 0 {
+            LedgerManager::deposit(e, &config.token, &caller, deposit)?;
+        }
+
+        Ok((order_ids, created))
+    }
+
+    fn find_by_ref(e: &Env, creator: &Address, client_ref: &Option<BytesN<32>>) -> Option<Order> {
+        let client_ref = client_ref.clone()?;
+        Self::get_order_by_ref(e, creator.clone(), client_ref).ok()
+    }
+
+    /// Validates and stores a new order. Callers check the pause flag and
+    /// take the creator's deposit.
+    fn open_order(
+        e: &Env,
+        config: &Config,
+        caller: &Address,
+        creator: &Address,
+        params: OrderParams,
+    ) -> Result<Order, ContractError> {
+        ensure_not_blocked(e, caller)?;
+        ensure_not_blocked(e, creator)?;
+        validate_create_order(
+            params.amount,
+            params.exchange_rate,
+            params.duration_secs,
+            config,
+        )?;
+        validate_visibility(&params.visibility, creator)?;
+        ensure_market_supported(e, &params.fiat_currency, &params.payment_method)?;
+        validate_requested_timeout(
+            &params.requested_timeout_secs,
+            &AdminManager::get_timeout_bounds(e),
+        )?;
+
+        OperatorManager::authorize(
+            e,
+            creator,
+            caller,
+            OperatorScope::CreateOrders,
+            params.amount,
+        )?;
+
+        let deadline = e.ledger().timestamp() + params.duration_secs;
+        let order = Self::insert_order(
+            e,
+            config,
+            creator.clone(),
+            params.fiat_currency,
+            params.payment_method,
+            params.from_crypto,
+            params.amount,
+            params.exchange_rate,
+            deadline,
+            params.requested_timeout_secs,
+            params.visibility,
+        )?;
+        if let Some(client_ref) = params.client_ref {
+            // Kept outside the instance so retries still resolve once the
+            // order is archived.
+            let key = DataKey::OrderRef(creator.clone(), client_ref);
+            let max_ttl = e.storage().max_ttl();
+            e.storage().persistent().set(&key, &order.order_id);
+            e.storage().persistent().extend_ttl(&key, max_ttl, max_ttl);
+        }
+
+        Ok(order)
+    }
+
+    /// Opens the order for one fill of a merchant ad and starts the fill.
+    /// Only the taker can take it, and a `from_crypto` ad funds it from the
+    /// inventory it already holds.
+    pub fn open_ad_fill(
+        e: &Env,
+        config: &Config,
+        ad: &MerchantAd,
+        taker: Address,
+        payment_method: PaymentMethod,
+        amount: i128,
+    ) -> Result<Order, ContractError> {
+        let timeout_secs =
+            AdminManager::get_payment_timeout(e, &payment_method, &ad.fiat_currency)?;
+        let order = Self::insert_order(
+            e,
+            config,
+            ad.merchant.clone(),
+            ad.fiat_currency.clone(),
+            payment_method,
+            ad.from_crypto,
+            amount,
+            ad.exchange_rate,
+            e.ledger().timestamp() + timeout_secs,
+            None,
+            OrderVisibility::Allowlist(vec![e, taker.clone()]),
+        )?;
+
+        Self::fill_order(e, config, taker, order.order_id, amount, None, None, false)
+    }
+
+    /// Stores a new order awaiting a filler. Callers validate the order and
+    /// take the creator's deposit.
+    fn insert_order(
+        e: &Env,
+        config: &Config,
+        creator: Address,
         fiat_currency: FiatCurrency,
         payment_method: PaymentMethod,
         from_crypto: bool,
         amount: i128,
         exchange_rate: i128,
-        duration_secs: u64,
+        deadline: u64,
+        requested_timeout_secs: Option<u64>,
+        visibility: OrderVisibility,
     ) -> Result<Order, ContractError> {
-        caller.require_auth();
-        let config = AdminManager::get_config(e)?;
-        ensure_not_paused(&config)?;
-        validate_create_order(amount, exchange_rate, duration_secs, &config)?;
-
-        let now = e.ledger().timestamp();
         let next_order_id = Self::next_order_id(e)?;
-        let deadline = now + duration_secs;
-        let mut order = Order {
+        let order = Order {
             order_id: next_order_id,
-            creator: caller.clone(),
+            creator,
             filler: None,
             token: config.token.clone(),
             amount,
@@ -45,18 +244,16 @@ impl OrderManager {
             from_crypto,
             fiat_currency,
             payment_method,
-            status: OrderStatus::Created,
-            created_at: now,
+            status: OrderStatus::AwaitingFiller,
+            created_at: e.ledger().timestamp(),
             deadline,
             fiat_transfer_deadline: None,
+            frozen: false,
+            visibility,
+            requested_timeout_secs,
+            payment_timeout_secs: None,
         };
 
-        if from_crypto {
-            let token_client = TokenClient::new(e, &config.token);
-            token_client.transfer(&caller, &e.current_contract_address(), &amount);
-        }
-
-        order.status = OrderStatus::AwaitingFiller;
         Self::store_order(e, &order);
         e.storage()
             .instance()
@@ -65,33 +262,131 @@ impl OrderManager {
         Ok(order)
     }
 
-    pub fn cancel_order(e: &Env, caller: Address, order_id: u64) -> Result<Order, ContractError> {
+    /// Returns the cancelled order and the amount refunded to the creator.
+    pub fn cancel_order(
+        e: &Env,
+        caller: Address,
+        order_id: u64,
+    ) -> Result<(Order, i128), ContractError> {
         caller.require_auth();
         let config = AdminManager::get_config(e)?;
-        ensure_not_paused(&config)?;
 
-        let mut order = Self::get_order(e, order_id)?;
-        ensure_status(&order, OrderStatus::AwaitingFiller)?;
-        ensure_creator(&order, &caller)?;
+        let order = Self::close_order(e, &caller, order_id)?;
+        let refund_amount = Self::cancel_refund(&order);
+        if refund_amount > 0 {
+            LedgerManager::payout(e, &config.token, &order.creator, refund_amount)?;
+        }
 
-        order.status = OrderStatus::Cancelled;
+        Ok((order, refund_amount))
+    }
 
+    /// Cancels the orders with one refund per creator. Orders that cannot be
+    /// cancelled revert the batch when it is `all_or_nothing` and are
+    /// skipped otherwise. Returns the cancelled orders.
+    pub fn cancel_orders(
+        e: &Env,
+        caller: Address,
+        order_ids: Vec<u64>,
+        all_or_nothing: bool,
+    ) -> Result<Vec<Order>, ContractError> {
+        caller.require_auth();
+        if order_ids.len() > MAX_BATCH_SIZE {
+            return Err(ContractError::BatchTooLarge);
+        }
+
+        let mut cancelled = Vec::new(e);
+        for order_id in order_ids.iter() {
+            match Self::close_order(e, &caller, order_id) {
+                Ok(order) => cancelled.push_back(order),
+                Err(err) if all_or_nothing => return Err(err),
+                Err(_) => {}
+            }
+        }
+
+        Self::refund_cancelled(e, &cancelled)?;
+        Ok(cancelled)
+    }
+
+    /// Cancels the caller's open orders among the next `limit` order ids
+    /// from `cursor`, with a single refund. Returns the cancelled orders and
+    /// the cursor to continue from, `None` once every order was scanned.
+    pub fn cancel_all_my_orders(
+        e: &Env,
+        caller: Address,
+        cursor: u64,
+        limit: u32,
+    ) -> Result<(Vec<Order>, Option<u64>), ContractError> {
+        caller.require_auth();
+        let order_count = AdminManager::get_order_count(e)?;
+        let end = cursor
+            .saturating_add(limit.min(MAX_ORDERS_PER_PAGE) as u64)
+            .min(order_count);
+
+        let mut cancelled = Vec::new(e);
+        for order_id in cursor..end {
+            let Ok(order) = Self::get_order(e, order_id) else {
+                continue;
+            };
+            if order.creator == caller
+                && order.status == OrderStatus::AwaitingFiller
+                && !order.frozen
+            {
+                cancelled.push_back(Self::close_order(e, &caller, order_id)?);
+            }
+        }
+
+        Self::refund_cancelled(e, &cancelled)?;
+        let next_cursor = if end < order_count { Some(end) } else { None };
+        Ok((cancelled, next_cursor))
+    }
+
+    /// What cancelling the order returns to its creator.
+    pub fn cancel_refund(order: &Order) -> i128 {
         if order.from_crypto {
-            let token_client = TokenClient::new(e, &config.token);
-            token_client.transfer(
-                &e.current_contract_address(),
-                &order.creator,
-                &order.remaining_amount,
-            );
+            order.remaining_amount
+        } else {
+            0
         }
+    }
 
+    /// Marks an open order cancelled. Callers refund the creator.
+    fn close_order(e: &Env, caller: &Address, order_id: u64) -> Result<Order, ContractError> {
+        let mut order = Self::get_order(e, order_id)?;
+        ensure_status(&order, OrderStatus::AwaitingFiller)?;
+        ensure_not_frozen(&order)?;
+        OperatorManager::authorize(e, &order.creator, caller, OperatorScope::CancelOrders, 0)?;
+
+        order.status = OrderStatus::Cancelled;
         Self::store_order(e, &order);
         Ok(order)
     }
 
+    /// Pays each creator's refunds for cancelled orders in one transfer.
+    fn refund_cancelled(e: &Env, cancelled: &Vec<Order>) -> Result<(), ContractError> {
+        let mut refunds: Map<Address, i128> = Map::new(e);
+        for order in cancelled.iter() {
+            let refund_amount = Self::cancel_refund(&order);
+            if refund_amount > 0 {
+                let total = refunds
+                    .get(order.creator.clone())
+                    .unwrap_or(0)
+                    .checked_add(refund_amount)
+                    .ok_or(ContractError::Overflow)?;
+                refunds.set(order.creator.clone(), total);
+            }
+        }
+
+        let config = AdminManager::get_config(e)?;
+        for (creator, amount) in refunds.iter() {
+            LedgerManager::payout(e, &config.token, &creator, amount)?;
+        }
+
+        Ok(())
+    }
+
     pub fn take_order(e: &Env, caller: Address, order_id: u64) -> Result<Order, ContractError> {
         let order = Self::get_order(e, order_id)?;
-        Self::take_order_with_amount(e, caller, order_id, order.remaining_amount)
+        Self::take_order_with_amount(e, caller, order_id, order.remaining_amount, None)
     }
 
     pub fn take_order_with_amount(
@@ -99,32 +394,182 @@ impl OrderManager {
         caller: Address,
         order_id: u64,
         fill_amount: i128,
+        secret: Option<Bytes>,
     ) -> Result<Order, ContractError> {
         caller.require_auth();
         let config = AdminManager::get_config(e)?;
-        ensure_not_paused(&config)?;
+        ensure_not_paused(e, &config, PausableOperation::Takes)?;
+
+        Self::fill_order(
+            e,
+            &config,
+            caller,
+            order_id,
+            fill_amount,
+            secret,
+            None,
+            false,
+        )
+    }
 
+    /// Starts a fill at a rate the creator agreed to. `prefunded` fills are
+    /// funded from a deposit the contract already holds for the taker.
+    pub fn fill_at_rate(
+        e: &Env,
+        config: &Config,
+        taker: Address,
+        order_id: u64,
+        fill_amount: i128,
+        exchange_rate: i128,
+        prefunded: bool,
+    ) -> Result<Order, ContractError> {
+        Self::fill_order(
+            e,
+            config,
+            taker,
+            order_id,
+            fill_amount,
+            None,
+            Some(exchange_rate),
+            prefunded,
+        )
+    }
+
+    /// Starts a fill for an already authorized taker, at the order's rate
+    /// unless `exchange_rate` is given.
+    fn fill_order(
+        e: &Env,
+        config: &Config,
+        caller: Address,
+        order_id: u64,
+        fill_amount: i128,
+        secret: Option<Bytes>,
+        exchange_rate: Option<i128>,
+        prefunded: bool,
+    ) -> Result<Order, ContractError> {
         let mut order = Self::get_order(e, order_id)?;
         ensure_status(&order, OrderStatus::AwaitingFiller)?;
+        ensure_not_frozen(&order)?;
+        CircuitBreakerManager::ensure_takes_open(e, &order.fiat_currency)?;
+        ensure_not_blocked(e, &caller)?;
         ensure_not_creator(&order, &caller)?;
+        ensure_taker_allowed(e, &order, &caller, &secret)?;
         ensure_not_expired(&order, e.ledger().timestamp())?;
         validate_fill_amount(&order, fill_amount)?;
 
-        if !order.from_crypto {
-            let token_client = TokenClient::new(e, &config.token);
-            token_client.transfer(&caller, &e.current_contract_address(), &fill_amount);
+        // A revealed secret is public once the transaction lands, so the order
+        // is bound to the first taker who revealed it.
+        if let OrderVisibility::SecretHash(_) = order.visibility {
+            order.visibility = OrderVisibility::Allowlist(vec![e, caller.clone()]);
+        }
+
+        let held = order.from_crypto || prefunded;
+        let escrow_settings = EscrowManager::get_settings(e);
+        if !held && escrow_settings.is_none() {
+            LedgerManager::deposit(e, &config.token, &caller, fill_amount)?;
         }
 
         order.filler = Some(caller);
         order.active_fill_amount = Some(fill_amount);
         order.status = OrderStatus::AwaitingPayment;
-        order.fiat_transfer_deadline =
-            Some(e.ledger().timestamp() + config.filler_payment_timeout_secs);
+        let timeout_secs = match order.requested_timeout_secs {
+            Some(timeout_secs) => timeout_secs,
+            None => {
+                AdminManager::get_payment_timeout(e, &order.payment_method, &order.fiat_currency)?
+            }
+        };
+        order.payment_timeout_secs = Some(timeout_secs);
+        order.fiat_transfer_deadline = Some(e.ledger().timestamp() + timeout_secs);
+
+        if let Some(settings) = escrow_settings {
+            EscrowManager::open(e, config, &settings, &order, held)?;
+        }
+        let key = DataKey::FillRate(order_id);
+        match exchange_rate {
+            Some(exchange_rate) => e.storage().instance().set(&key, &exchange_rate),
+            None => e.storage().instance().remove(&key),
+        }
+        e.storage()
+            .instance()
+            .remove(&DataKey::FillExtension(order_id));
+        FillHistoryManager::record_take(e, &order, Self::fill_rate(e, &order))?;
 
         Self::store_order(e, &order);
         Ok(order)
     }
 
+    /// Fills the candidate orders best price first until `total_amount` is
+    /// reached or the next order is worse than `worst_rate`. Takers of
+    /// `from_crypto` orders buy crypto, so lower rates are better; otherwise
+    /// higher rates are better. Any failing leg reverts the whole sweep.
+    pub fn take_best_orders(
+        e: &Env,
+        caller: Address,
+        fiat_currency: FiatCurrency,
+        payment_method: PaymentMethod,
+        from_crypto: bool,
+        total_amount: i128,
+        worst_rate: i128,
+        order_ids: Vec<u64>,
+    ) -> Result<Vec<OrderFill>, ContractError> {
+        caller.require_auth();
+        let config = AdminManager::get_config(e)?;
+        ensure_not_paused(e, &config, PausableOperation::Takes)?;
+        validate_sweep(total_amount, worst_rate, &order_ids)?;
+
+        let mut candidates: Vec<Order> = Vec::new(e);
+        for order_id in order_ids.iter() {
+            let order = Self::get_order(e, order_id)?;
+            ensure_market(&order, &fiat_currency, &payment_method, from_crypto)?;
+            candidates.push_back(order);
+        }
+        let candidates = Self::sort_by_price(e, candidates, from_crypto);
+
+        let mut fills = Vec::new(e);
+        let mut remaining = total_amount;
+        for order in candidates.iter() {
+            if remaining == 0 {
+                break;
+            }
+
+            let within_rate = if from_crypto {
+                order.exchange_rate <= worst_rate
+            } else {
+                order.exchange_rate >= worst_rate
+            };
+            if !within_rate {
+                break;
+            }
+
+            let fill_amount = remaining.min(order.remaining_amount);
+            Self::fill_order(
+                e,
+                &config,
+                caller.clone(),
+                order.order_id,
+                fill_amount,
+                None,
+                None,
+                false,
+            )?;
+            remaining = remaining
+                .checked_sub(fill_amount)
+                .ok_or(ContractError::Underflow)?;
+
+            fills.push_back(OrderFill {
+                order_id: order.order_id,
+                fill_amount,
+                exchange_rate: order.exchange_rate,
+            });
+        }
+
+        if fills.is_empty() {
+            return Err(ContractError::NoFillableOrders);
+        }
+
+        Ok(fills)
+    }
+
     pub fn submit_fiat_payment(
         e: &Env,
         caller: Address,
@@ -132,10 +577,11 @@ impl OrderManager {
     ) -> Result<Order, ContractError> {
         caller.require_auth();
         let config = AdminManager::get_config(e)?;
-        ensure_not_paused(&config)?;
+        ensure_not_paused(e, &config, PausableOperation::Settlements)?;
 
         let mut order = Self::get_order(e, order_id)?;
         ensure_status(&order, OrderStatus::AwaitingPayment)?;
+        ensure_not_frozen(&order)?;
 
         if order.from_crypto {
             ensure_filler(&order, &caller)?;
@@ -143,6 +589,11 @@ impl OrderManager {
             ensure_creator(&order, &caller)?;
         }
 
+        if let Some(fill_escrow) = EscrowManager::get_fill_escrow(e, order_id) {
+            EscrowManager::mark_paid(e, &fill_escrow, &caller);
+        }
+        FillHistoryManager::record_submission(e, order_id);
+
         order.status = OrderStatus::AwaitingConfirmation;
         Self::store_order(e, &order);
 
@@ -153,68 +604,226 @@ impl OrderManager {
         e: &Env,
         caller: Address,
         order_id: u64,
-    ) -> Result<(Order, i128), ContractError> {
+    ) -> Result<(Order, Settlement), ContractError> {
         caller.require_auth();
         let config = AdminManager::get_config(e)?;
-        ensure_not_paused(&config)?;
 
         let mut order = Self::get_order(e, order_id)?;
         ensure_status(&order, OrderStatus::AwaitingPayment)?;
+        ensure_not_frozen(&order)?;
         ensure_fiat_timeout_expired(&order, e.ledger().timestamp())?;
         let active_fill_amount = ensure_active_fill_amount(&order)?;
+        let filler = order.filler.clone().ok_or(ContractError::MissingFiller)?;
+
+        // Only the resolver can move funds out of an escrow, so an expired
+        // escrowed fill is escalated to a dispute instead of refunded.
+        if let Some(fill_escrow) = EscrowManager::get_fill_escrow(e, order_id) {
+            if order.from_crypto {
+                ensure_creator(&order, &caller)?;
+            } else {
+                ensure_filler(&order, &caller)?;
+            }
+            EscrowManager::dispute(e, &fill_escrow);
+
+            order.status = OrderStatus::Disputed;
+            AdManager::dispute_opened(e, &order);
+            CircuitBreakerManager::record(e, &order.fiat_currency, FillEvent::Dispute)?;
+            Self::store_order(e, &order);
+            return Ok((
+                order,
+                Settlement {
+                    filler,
+                    paid_to: None,
+                    amount: 0,
+                    fee: None,
+                },
+            ));
+        }
 
-        if order.from_crypto {
+        let settlement = if order.from_crypto {
             ensure_creator(&order, &caller)?;
+            Settlement {
+                filler,
+                paid_to: None,
+                amount: 0,
+                fee: None,
+            }
         } else {
             ensure_filler(&order, &caller)?;
 
-            let filler = order.filler.clone().ok_or(ContractError::MissingFiller)?;
-            let token_client = TokenClient::new(e, &config.token);
-            token_client.transfer(&e.current_contract_address(), &filler, &active_fill_amount);
-        }
+            LedgerManager::payout(e, &config.token, &filler, active_fill_amount)?;
+            Settlement {
+                filler: filler.clone(),
+                paid_to: Some(filler),
+                amount: active_fill_amount,
+                fee: None,
+            }
+        };
+
+        FillHistoryManager::record_settlement(e, order_id, FillOutcome::TimedOut);
+        CircuitBreakerManager::record(e, &order.fiat_currency, FillEvent::Timeout)?;
 
         order.status = OrderStatus::AwaitingFiller;
         order.filler = None;
         order.active_fill_amount = None;
         order.fiat_transfer_deadline = None;
+        order.payment_timeout_secs = None;
+        AdManager::return_fill(e, &mut order)?;
         Self::store_order(e, &order);
 
-        let refunded_amount = if order.from_crypto {
-            0
+        Ok((order, settlement))
+    }
+
+    /// Moves the fiat deadline of a fill awaiting payment to
+    /// `new_deadline`. Only the party who could call the timeout can grant
+    /// it, and the fill's extensions are capped in total. Returns the order
+    /// and the previous deadline.
+    pub fn extend_fiat_deadline(
+        e: &Env,
+        caller: Address,
+        order_id: u64,
+        new_deadline: u64,
+    ) -> Result<(Order, u64), ContractError> {
+        caller.require_auth();
+
+        let mut order = Self::get_order(e, order_id)?;
+        ensure_status(&order, OrderStatus::AwaitingPayment)?;
+        ensure_not_frozen(&order)?;
+        if order.from_crypto {
+            ensure_creator(&order, &caller)?;
         } else {
-            active_fill_amount
-        };
+            ensure_filler(&order, &caller)?;
+        }
 
-        Ok((order, refunded_amount))
+        let current_deadline = order
+            .fiat_transfer_deadline
+            .ok_or(ContractError::InvalidOrderStatus)?;
+        let key = DataKey::FillExtension(order_id);
+        let extended_secs: u64 = e.storage().instance().get(&key).unwrap_or(0);
+        let total_secs = extended_secs
+            .checked_add(new_deadline.saturating_sub(current_deadline))
+            .ok_or(ContractError::Overflow)?;
+        validate_deadline_extension(
+            current_deadline,
+            new_deadline,
+            e.ledger().timestamp(),
+            total_secs,
+            AdminManager::get_max_fiat_extension(e),
+        )?;
+
+        e.storage().instance().set(&key, &total_secs);
+        FillHistoryManager::record_extension(e, order_id, total_secs);
+
+        order.fiat_transfer_deadline = Some(new_deadline);
+        Self::store_order(e, &order);
+
+        Ok((order, current_deadline))
     }
 
+    /// Confirms the fiat payment and releases the fill, or, when the
+    /// payment method has a settlement hold, moves the order to `Settling`
+    /// until `finalize_settlement`. Held fills return no settlement.
     pub fn confirm_fiat_payment(
         e: &Env,
         caller: Address,
         order_id: u64,
-    ) -> Result<Order, ContractError> {
+    ) -> Result<(Order, Option<Settlement>), ContractError> {
         caller.require_auth();
         let config = AdminManager::get_config(e)?;
-        ensure_not_paused(&config)?;
+        ensure_not_paused(e, &config, PausableOperation::Settlements)?;
 
         let mut order = Self::get_order(e, order_id)?;
         ensure_status(&order, OrderStatus::AwaitingConfirmation)?;
+        ensure_not_frozen(&order)?;
         let active_fill_amount = ensure_active_fill_amount(&order)?;
 
-        let recipient = if order.from_crypto {
-            ensure_creator(&order, &caller)?;
+        // The fiat receiver confirms, itself or through an operator.
+        let confirmer = if order.from_crypto {
+            order.creator.clone()
+        } else {
             order.filler.clone().ok_or(ContractError::MissingFiller)?
+        };
+        OperatorManager::authorize(
+            e,
+            &confirmer,
+            &caller,
+            OperatorScope::ConfirmPayments,
+            active_fill_amount,
+        )?;
+
+        if let Some(fill_escrow) = EscrowManager::get_fill_escrow(e, order_id) {
+            EscrowManager::approve(e, &fill_escrow);
+        }
+
+        if let Some(hold_secs) = AdminManager::get_settlement_hold(e, &order.payment_method) {
+            e.storage().instance().set(
+                &DataKey::SettlementRelease(order_id),
+                &(e.ledger().timestamp() + hold_secs),
+            );
+            order.status = OrderStatus::Settling;
+            Self::store_order(e, &order);
+            return Ok((order, None));
+        }
+
+        let settlement = Self::settle(e, &config, &mut order)?;
+        Ok((order, Some(settlement)))
+    }
+
+    /// Releases a held fill once its settlement hold is over. Callable by
+    /// anyone.
+    pub fn finalize_settlement(
+        e: &Env,
+        order_id: u64,
+    ) -> Result<(Order, Settlement), ContractError> {
+        let config = AdminManager::get_config(e)?;
+        // Emergency withdrawals skip held fills, so they can still be
+        // released to the buyer during an emergency.
+        if AdminManager::get_emergency(e).is_none() {
+            ensure_not_paused(e, &config, PausableOperation::Settlements)?;
+        }
+
+        let mut order = Self::get_order(e, order_id)?;
+        ensure_status(&order, OrderStatus::Settling)?;
+        ensure_not_frozen(&order)?;
+        let release_at =
+            Self::get_settlement_release(e, order_id).ok_or(ContractError::InvalidOrderStatus)?;
+        ensure_hold_expired(release_at, e.ledger().timestamp())?;
+
+        let settlement = Self::settle(e, &config, &mut order)?;
+        Ok((order, settlement))
+    }
+
+    /// When a held fill can be finalized.
+    pub fn get_settlement_release(e: &Env, order_id: u64) -> Option<u64> {
+        e.storage()
+            .instance()
+            .get(&DataKey::SettlementRelease(order_id))
+    }
+
+    /// Pays a confirmed fill to the crypto buyer and books it on the order.
+    fn settle(e: &Env, config: &Config, order: &mut Order) -> Result<Settlement, ContractError> {
+        let order_id = order.order_id;
+        let active_fill_amount = ensure_active_fill_amount(order)?;
+        let filler = order.filler.clone().ok_or(ContractError::MissingFiller)?;
+        let recipient = if order.from_crypto {
+            filler.clone()
         } else {
-            ensure_filler(&order, &caller)?;
             order.creator.clone()
         };
 
-        let token_client = TokenClient::new(e, &config.token);
-        token_client.transfer(
-            &e.current_contract_address(),
-            &recipient,
-            &active_fill_amount,
-        );
+        let (amount, fee) = match EscrowManager::get_fill_escrow(e, order_id) {
+            Some(fill_escrow) => {
+                let (received, platform_fee) =
+                    EscrowManager::release(e, order_id, &fill_escrow, &config.token, &recipient);
+                let fee = FeeManager::charge_escrow_fee(e, config, &recipient, platform_fee)?;
+                (received, fee)
+            }
+            None => FeeManager::pay_settlement(e, config, &recipient, active_fill_amount)?,
+        };
+
+        FillHistoryManager::record_settlement(e, order_id, FillOutcome::Confirmed);
+        CircuitBreakerManager::record(e, &order.fiat_currency, FillEvent::Settlement)?;
+        MarketStatsManager::record_trade(e, order, active_fill_amount, Self::fill_rate(e, order))?;
 
         order.filled_amount = order
             .filled_amount
@@ -228,15 +837,89 @@ impl OrderManager {
         order.filler = None;
         order.active_fill_amount = None;
         order.fiat_transfer_deadline = None;
+        order.payment_timeout_secs = None;
 
         order.status = if order.remaining_amount == 0 {
             OrderStatus::Completed
         } else {
             OrderStatus::AwaitingFiller
         };
+        e.storage()
+            .instance()
+            .remove(&DataKey::SettlementRelease(order_id));
+        Self::store_order(e, order);
+
+        Ok(Settlement {
+            filler,
+            paid_to: Some(recipient),
+            amount,
+            fee,
+        })
+    }
+
+    /// Returns a depositor's funds once the emergency exit is open: the
+    /// creator's remaining deposit for `from_crypto` orders, otherwise the
+    /// filler's active fill. The order ends as `Refunded`. A fill held in a
+    /// settlement escrow has to be settled through the escrow first, and a
+    /// confirmed fill in its settlement hold through `finalize_settlement`.
+    pub fn emergency_withdraw(
+        e: &Env,
+        caller: Address,
+        order_id: u64,
+    ) -> Result<(Order, i128), ContractError> {
+        caller.require_auth();
+        let config = AdminManager::get_config(e)?;
+        ensure_emergency_exit_open(&AdminManager::get_emergency(e), e.ledger().timestamp())?;
+
+        let mut order = Self::get_order(e, order_id)?;
+        ensure_not_frozen(&order)?;
+        if EscrowManager::get_fill_escrow(e, order_id).is_some() {
+            return Err(ContractError::FillHeldInEscrow);
+        }
+        match order.status {
+            OrderStatus::AwaitingFiller
+            | OrderStatus::AwaitingPayment
+            | OrderStatus::AwaitingConfirmation
+            | OrderStatus::Disputed => {}
+            _ => return Err(ContractError::InvalidOrderStatus),
+        }
+
+        let amount = if order.from_crypto {
+            ensure_creator(&order, &caller)?;
+            order.remaining_amount
+        } else {
+            ensure_filler(&order, &caller)?;
+            ensure_active_fill_amount(&order)?
+        };
+        if amount <= 0 {
+            return Err(ContractError::NothingToWithdraw);
+        }
+
+        LedgerManager::payout(e, &config.token, &caller, amount)?;
+        if order.active_fill_amount.is_some() {
+            FillHistoryManager::record_settlement(e, order_id, FillOutcome::Refunded);
+        }
+        if order.status == OrderStatus::Disputed {
+            AdManager::dispute_closed(e, &order);
+        }
+
+        order.status = OrderStatus::Refunded;
+        order.filler = None;
+        order.active_fill_amount = None;
+        order.fiat_transfer_deadline = None;
+        order.payment_timeout_secs = None;
         Self::store_order(e, &order);
 
-        Ok(order)
+        Ok((order, amount))
+    }
+
+    /// Rate of the order's active fill: the one negotiated for it, if any,
+    /// otherwise the order's own rate.
+    pub fn fill_rate(e: &Env, order: &Order) -> i128 {
+        e.storage()
+            .instance()
+            .get(&DataKey::FillRate(order.order_id))
+            .unwrap_or(order.exchange_rate)
     }
 
     pub fn get_order(e: &Env, order_id: u64) -> Result<Order, ContractError> {
@@ -246,6 +929,65 @@ impl OrderManager {
             .ok_or(ContractError::OrderNotFound)
     }
 
+    pub fn get_order_by_ref(
+        e: &Env,
+        creator: Address,
+        client_ref: BytesN<32>,
+    ) -> Result<Order, ContractError> {
+        let order_id: u64 = e
+            .storage()
+            .persistent()
+            .get(&DataKey::OrderRef(creator, client_ref))
+            .ok_or(ContractError::OrderNotFound)?;
+        ArchiveManager::find_order(e, order_id)
+    }
+
+    /// Lists public orders that can currently be taken, scanning order ids
+    /// from `start`.
+    pub fn get_open_orders(e: &Env, start: u64, limit: u32) -> Result<Vec<Order>, ContractError> {
+        let order_count = AdminManager::get_order_count(e)?;
+        let limit = limit.min(MAX_ORDERS_PER_PAGE);
+        let now = e.ledger().timestamp();
+
+        let mut orders = Vec::new(e);
+        let mut order_id = start;
+        while order_id < order_count && orders.len() < limit {
+            if let Ok(order) = Self::get_order(e, order_id) {
+                if order.status == OrderStatus::AwaitingFiller
+                    && order.visibility == OrderVisibility::Public
+                    && !order.frozen
+                    && order.deadline > now
+                {
+                    orders.push_back(order);
+                }
+            }
+            order_id += 1;
+        }
+
+        Ok(orders)
+    }
+
+    fn sort_by_price(e: &Env, orders: Vec<Order>, from_crypto: bool) -> Vec<Order> {
+        let mut sorted: Vec<Order> = Vec::new(e);
+        for order in orders.iter() {
+            let mut index = sorted.len();
+            for (i, placed) in sorted.iter().enumerate() {
+                let better = if from_crypto {
+                    order.exchange_rate < placed.exchange_rate
+                } else {
+                    order.exchange_rate > placed.exchange_rate
+                };
+                if better {
+                    index = i as u32;
+                    break;
+                }
+            }
+            sorted.insert(index, order);
+        }
+
+        sorted
+    }
+
     fn next_order_id(e: &Env) -> Result<u64, ContractError> {
         let current = AdminManager::get_order_count(e)?;
         Ok(current)
@@ -0,0 +1,91 @@
+use soroban_sdk::{Address, Env};
+
+use crate::core::admin::AdminManager;
+use crate::core::ledger::LedgerManager;
+use crate::core::validators::compliance::ensure_not_blocked;
+use crate::core::validators::referral::validate_referrer;
+use crate::error::ContractError;
+use crate::storage::types::{DataKey, ReferralStats};
+
+/// Binds traders to the referrer who brought them. A binding is set once by
+/// the trader and never changes. Referrers accrue a share of the fees their
+/// traders pay and claim it whenever they like.
+pub struct ReferralManager;
+
+impl ReferralManager {
+    pub fn register_referrer(
+        e: &Env,
+        caller: Address,
+        referrer: Address,
+    ) -> Result<(), ContractError> {
+        caller.require_auth();
+        ensure_not_blocked(e, &caller)?;
+        validate_referrer(&caller, &referrer, &Self::get_referrer(e, caller.clone()))?;
+
+        let key = DataKey::Referrer(caller);
+        let max_ttl = e.storage().max_ttl();
+        e.storage().persistent().set(&key, &referrer);
+        e.storage().persistent().extend_ttl(&key, max_ttl, max_ttl);
+
+        let mut stats = Self::get_referral_stats(e, referrer.clone());
+        stats.referred_count = stats
+            .referred_count
+            .checked_add(1)
+            .ok_or(ContractError::Overflow)?;
+        Self::store_stats(e, &referrer, &stats);
+
+        Ok(())
+    }
+
+    pub fn get_referrer(e: &Env, trader: Address) -> Option<Address> {
+        e.storage().persistent().get(&DataKey::Referrer(trader))
+    }
+
+    /// Adds fees kept in the contract to the referrer's claimable balance.
+    pub fn accrue(e: &Env, referrer: &Address, amount: i128) -> Result<(), ContractError> {
+        let mut stats = Self::get_referral_stats(e, referrer.clone());
+        stats.total_earned = stats
+            .total_earned
+            .checked_add(amount)
+            .ok_or(ContractError::Overflow)?;
+        stats.claimable = stats
+            .claimable
+            .checked_add(amount)
+            .ok_or(ContractError::Overflow)?;
+        Self::store_stats(e, referrer, &stats);
+
+        Ok(())
+    }
+
+    pub fn claim_referral_rewards(e: &Env, caller: Address) -> Result<i128, ContractError> {
+        caller.require_auth();
+        let config = AdminManager::get_config(e)?;
+        ensure_not_blocked(e, &caller)?;
+
+        let mut stats = Self::get_referral_stats(e, caller.clone());
+        let amount = stats.claimable;
+        if amount <= 0 {
+            return Err(ContractError::NothingToWithdraw);
+        }
+
+        stats.claimable = 0;
+        Self::store_stats(e, &caller, &stats);
+        LedgerManager::payout(e, &config.token, &caller, amount)?;
+
+        Ok(amount)
+    }
+
+    pub fn get_referral_stats(e: &Env, referrer: Address) -> ReferralStats {
+        e.storage()
+            .persistent()
+            .get(&DataKey::ReferralStats(referrer))
+            .unwrap_or_default()
+    }
+
+    fn store_stats(e: &Env, referrer: &Address, stats: &ReferralStats) {
+        let key = DataKey::ReferralStats(referrer.clone());
+        let max_ttl = e.storage().max_ttl();
+        e.storage().persistent().set(&key, stats);
+        e.storage().persistent().extend_ttl(&key, max_ttl, max_ttl);
+    }
+}
@@ -0,0 +1,164 @@
+use soroban_sdk::{Address, Env, String, Vec};
+
+use crate::core::admin::AdminManager;
+use crate::core::validators::admin::ensure_admin;
+use crate::core::validators::registry::{validate_currency_info, validate_payment_method_info};
+use crate::error::ContractError;
+use crate::storage::types::{
+    CurrencyInfo, DataKey, FiatCurrency, PaymentMethod, PaymentMethodInfo,
+};
+
+/// Currencies and payment methods orders may be created in. Entries are
+/// keyed by the enum values orders store, so `Other(code)` values become
+/// usable once the admin registers them.
+pub struct RegistryManager;
+
+impl RegistryManager {
+    pub fn set_currency(
+        e: &Env,
+        caller: Address,
+        currency: FiatCurrency,
+        iso_code: String,
+        decimals: u32,
+        enabled: bool,
+    ) -> Result<CurrencyInfo, ContractError> {
+        caller.require_auth();
+        let config = AdminManager::get_config(e)?;
+        ensure_admin(&config, &caller)?;
+
+        let info = CurrencyInfo {
+            currency,
+            iso_code,
+            decimals,
+            enabled,
+        };
+        validate_currency_info(&info)?;
+        Self::store_currency(e, &info);
+
+        Ok(info)
+    }
+
+    pub fn set_payment_method(
+        e: &Env,
+        caller: Address,
+        method: PaymentMethod,
+        name: String,
+        currencies: Vec<FiatCurrency>,
+        enabled: bool,
+    ) -> Result<PaymentMethodInfo, ContractError> {
+        caller.require_auth();
+        let config = AdminManager::get_config(e)?;
+        ensure_admin(&config, &caller)?;
+
+        let info = PaymentMethodInfo {
+            method,
+            name,
+            currencies,
+            enabled,
+        };
+        validate_payment_method_info(e, &info)?;
+        Self::store_payment_method(e, &info);
+
+        Ok(info)
+    }
+
+    pub fn get_currencies(e: &Env) -> Vec<CurrencyInfo> {
+        let mut currencies = Vec::new(e);
+        for currency in Self::currency_keys(e).iter() {
+            if let Some(info) = e.storage().instance().get(&DataKey::Currency(currency)) {
+                currencies.push_back(info);
+            }
+        }
+
+        currencies
+    }
+
+    pub fn get_payment_methods(e: &Env) -> Vec<PaymentMethodInfo> {
+        let mut methods = Vec::new(e);
+        for method in Self::payment_method_keys(e).iter() {
+            if let Some(info) = e.storage().instance().get(&DataKey::PaymentMethod(method)) {
+                methods.push_back(info);
+            }
+        }
+
+        methods
+    }
+
+    /// Registers the built-in currencies and payment methods so a fresh or
+    /// migrated deployment accepts the same markets as before the registry.
+    pub fn seed_defaults(e: &Env) {
+        let defaults = [
+            (FiatCurrency::Usd, "USD"),
+            (FiatCurrency::Eur, "EUR"),
+            (FiatCurrency::Ars, "ARS"),
+            (FiatCurrency::Cop, "COP"),
+            (FiatCurrency::Gbp, "GBP"),
+        ];
+        let mut currencies = Vec::new(e);
+        for (currency, iso_code) in defaults {
+            currencies.push_back(currency.clone());
+            Self::store_currency(
+                e,
+                &CurrencyInfo {
+                    currency,
+                    iso_code: String::from_str(e, iso_code),
+                    decimals: 2,
+                    enabled: true,
+                },
+            );
+        }
+
+        let methods = [
+            (PaymentMethod::BankTransfer, "Bank transfer"),
+            (PaymentMethod::MobileWallet, "Mobile wallet"),
+            (PaymentMethod::Cash, "Cash"),
+        ];
+        for (method, name) in methods {
+            Self::store_payment_method(
+                e,
+                &PaymentMethodInfo {
+                    method,
+                    name: String::from_str(e, name),
+                    currencies: currencies.clone(),
+                    enabled: true,
+                },
+            );
+        }
+    }
+
+    fn store_currency(e: &Env, info: &CurrencyInfo) {
+        let mut keys = Self::currency_keys(e);
+        if !keys.contains(&info.currency) {
+            keys.push_back(info.currency.clone());
+            e.storage().instance().set(&DataKey::Currencies, &keys);
+        }
+        e.storage()
+            .instance()
+            .set(&DataKey::Currency(info.currency.clone()), info);
+    }
+
+    fn store_payment_method(e: &Env, info: &PaymentMethodInfo) {
+        let mut keys = Self::payment_method_keys(e);
+        if !keys.contains(&info.method) {
+            keys.push_back(info.method.clone());
+            e.storage().instance().set(&DataKey::PaymentMethods, &keys);
+        }
+        e.storage()
+            .instance()
+            .set(&DataKey::PaymentMethod(info.method.clone()), info);
+    }
+
+    fn currency_keys(e: &Env) -> Vec<FiatCurrency> {
+        e.storage()
+            .instance()
+            .get(&DataKey::Currencies)
+            .unwrap_or(Vec::new(e))
+    }
+
+    fn payment_method_keys(e: &Env) -> Vec<PaymentMethod> {
+        e.storage()
+            .instance()
+            .get(&DataKey::PaymentMethods)
+            .unwrap_or(Vec::new(e))
+    }
+}
@@ -0,0 +1,295 @@
+use soroban_sdk::{Address, Env};
+
+use crate::core::admin::AdminManager;
+use crate::core::insurance::InsuranceManager;
+use crate::core::ledger::LedgerManager;
+use crate::core::validators::admin::ensure_admin;
+use crate::core::validators::resolver::{
+    ensure_appeal_judge, ensure_ruling_status, ensure_within_appeal_window,
+    validate_resolver_stake_config,
+};
+use crate::error::ContractError;
+use crate::storage::types::{
+    DataKey, Order, ResolverStake, ResolverStakeConfig, Ruling, RulingStatus,
+};
+
+/// Economic accountability for the dispute resolver. Once the admin sets a
+/// stake config, the resolver can only rule while it has `min_stake` bonded,
+/// and every ruling is recorded so the losing party can appeal it. Overturned
+/// rulings slash the resolver's stake to the appellant. Other contracts that
+/// rely on the same resolver, such as the settlement escrows, can check
+/// `is_eligible` before accepting its rulings.
+pub struct ResolverManager;
+
+impl ResolverManager {
+    pub fn set_stake_config(
+        e: &Env,
+        caller: Address,
+        stake_config: ResolverStakeConfig,
+    ) -> Result<(), ContractError> {
+        caller.require_auth();
+        let config = AdminManager::get_config(e)?;
+        ensure_admin(&config, &caller)?;
+        validate_resolver_stake_config(&stake_config)?;
+
+        e.storage()
+            .instance()
+            .set(&DataKey::ResolverStakeConfig, &stake_config);
+
+        Ok(())
+    }
+
+    pub fn get_stake_config(e: &Env) -> Option<ResolverStakeConfig> {
+        e.storage().instance().get(&DataKey::ResolverStakeConfig)
+    }
+
+    pub fn stake(e: &Env, caller: Address, amount: i128) -> Result<ResolverStake, ContractError> {
+        caller.require_auth();
+        let config = AdminManager::get_config(e)?;
+        if amount <= 0 {
+            return Err(ContractError::InvalidAmount);
+        }
+
+        LedgerManager::deposit(e, &config.token, &caller, amount)?;
+        let mut stake = Self::get_stake(e, caller.clone());
+        stake.staked = stake
+            .staked
+            .checked_add(amount)
+            .ok_or(ContractError::Overflow)?;
+        Self::store_stake(e, &caller, &stake);
+
+        Ok(stake)
+    }
+
+    /// Starts unbonding `amount` of the caller's stake. Unbonding restarts
+    /// the period for everything already unbonding.
+    pub fn unbond(e: &Env, caller: Address, amount: i128) -> Result<ResolverStake, ContractError> {
+        caller.require_auth();
+        let stake_config =
+            Self::get_stake_config(e).ok_or(ContractError::ResolverStakingNotConfigured)?;
+
+        let mut stake = Self::get_stake(e, caller.clone());
+        if amount <= 0 {
+            return Err(ContractError::InvalidAmount);
+        }
+        if amount > stake.staked {
+            return Err(ContractError::InsufficientStake);
+        }
+
+        stake.staked -= amount;
+        stake.unbonding = stake
+            .unbonding
+            .checked_add(amount)
+            .ok_or(ContractError::Overflow)?;
+        stake.unbonds_at = e.ledger().timestamp() + stake_config.unbonding_secs;
+        Self::store_stake(e, &caller, &stake);
+
+        Ok(stake)
+    }
+
+    /// Pays out stake that has finished unbonding. Held back while an appeal
+    /// against one of the resolver's rulings is undecided or any of its
+    /// rulings can still be appealed, so a resolver cannot unbond, rule and
+    /// withdraw before the ruling is appealed.
+    pub fn withdraw(e: &Env, caller: Address) -> Result<i128, ContractError> {
+        caller.require_auth();
+        let config = AdminManager::get_config(e)?;
+
+        let mut stake = Self::get_stake(e, caller.clone());
+        if stake.unbonding == 0 {
+            return Err(ContractError::NothingToWithdraw);
+        }
+        let now = e.ledger().timestamp();
+        if now < stake.unbonds_at {
+            return Err(ContractError::StakeStillUnbonding);
+        }
+        if stake.open_appeals > 0 {
+            return Err(ContractError::AppealPending);
+        }
+        if now <= stake.locked_until {
+            return Err(ContractError::StakeLockedByRuling);
+        }
+
+        let amount = stake.unbonding;
+        LedgerManager::payout(e, &config.token, &caller, amount)?;
+        stake.unbonding = 0;
+        Self::store_stake(e, &caller, &stake);
+
+        Ok(amount)
+    }
+
+    /// Whether `resolver` may rule on disputes: it must be the configured
+    /// dispute resolver and, once staking is configured, have the minimum
+    /// stake bonded.
+    pub fn is_eligible(e: &Env, resolver: Address) -> bool {
+        let Ok(config) = AdminManager::get_config(e) else {
+            return false;
+        };
+        if resolver != config.dispute_resolver {
+            return false;
+        }
+
+        match Self::get_stake_config(e) {
+            Some(stake_config) => Self::get_stake(e, resolver).staked >= stake_config.min_stake,
+            None => true,
+        }
+    }
+
+    /// Records a ruling on the order's active fill so the losing party can
+    /// appeal it, and locks the resolver's stake until the appeal window
+    /// closes. Nothing is recorded while staking is not configured.
+    pub fn record_ruling(
+        e: &Env,
+        order: &Order,
+        filler: &Address,
+        resolver: &Address,
+        fiat_transfer_confirmed: bool,
+        amount: i128,
+    ) -> Result<Option<Ruling>, ContractError> {
+        let Some(stake_config) = Self::get_stake_config(e) else {
+            return Ok(None);
+        };
+
+        // The fiat payer loses a rejected fill and the fiat receiver a
+        // confirmed one.
+        let losing_party = if fiat_transfer_confirmed == order.from_crypto {
+            order.creator.clone()
+        } else {
+            filler.clone()
+        };
+
+        let ruling_id: u64 = e
+            .storage()
+            .instance()
+            .get(&DataKey::RulingCount)
+            .unwrap_or(0);
+        let next = ruling_id.checked_add(1).ok_or(ContractError::Overflow)?;
+        e.storage().instance().set(&DataKey::RulingCount, &next);
+
+        let now = e.ledger().timestamp();
+        let ruling = Ruling {
+            ruling_id,
+            order_id: order.order_id,
+            resolver: resolver.clone(),
+            fiat_transfer_confirmed,
+            losing_party,
+            amount,
+            ruled_at: now,
+            appeal_deadline: now + stake_config.appeal_window_secs,
+            status: RulingStatus::Open,
+        };
+        Self::store_ruling(e, &ruling);
+
+        let mut stake = Self::get_stake(e, resolver.clone());
+        stake.locked_until = stake.locked_until.max(ruling.appeal_deadline);
+        Self::store_stake(e, resolver, &stake);
+
+        Ok(Some(ruling))
+    }
+
+    pub fn appeal(e: &Env, caller: Address, ruling_id: u64) -> Result<Ruling, ContractError> {
+        caller.require_auth();
+
+        let mut ruling = Self::get_ruling(e, ruling_id)?;
+        if caller != ruling.losing_party {
+            return Err(ContractError::Unauthorized);
+        }
+        ensure_ruling_status(&ruling, RulingStatus::Open)?;
+        ensure_within_appeal_window(&ruling, e.ledger().timestamp())?;
+
+        let mut stake = Self::get_stake(e, ruling.resolver.clone());
+        stake.open_appeals = stake.open_appeals.saturating_add(1);
+        Self::store_stake(e, &ruling.resolver, &stake);
+
+        ruling.status = RulingStatus::Appealed;
+        Self::store_ruling(e, &ruling);
+
+        Ok(ruling)
+    }
+
+    /// Decides an appeal. Overturning the ruling slashes `slash_bps` of the
+    /// resolver's stake, bonded first, to the appellant. With an insurance
+    /// pool configured the appellant gets at most the ruled amount and the
+    /// rest of the slash is forfeited to the pool. Returns the ruling, the
+    /// amount slashed and the amount forfeited.
+    pub fn decide_appeal(
+        e: &Env,
+        caller: Address,
+        ruling_id: u64,
+        overturn: bool,
+    ) -> Result<(Ruling, i128, i128), ContractError> {
+        caller.require_auth();
+        let config = AdminManager::get_config(e)?;
+        let stake_config =
+            Self::get_stake_config(e).ok_or(ContractError::ResolverStakingNotConfigured)?;
+        ensure_appeal_judge(&config, &stake_config, &caller)?;
+
+        let mut ruling = Self::get_ruling(e, ruling_id)?;
+        ensure_ruling_status(&ruling, RulingStatus::Appealed)?;
+
+        let mut stake = Self::get_stake(e, ruling.resolver.clone());
+        stake.open_appeals = stake.open_appeals.saturating_sub(1);
+
+        let mut slashed = 0;
+        let mut forfeited = 0;
+        if overturn {
+            let total = stake
+                .staked
+                .checked_add(stake.unbonding)
+                .ok_or(ContractError::Overflow)?;
+            slashed = total
+                .checked_mul(stake_config.slash_bps as i128)
+                .ok_or(ContractError::Overflow)?
+                .checked_div(10_000)
+                .ok_or(ContractError::DivisionError)?;
+
+            let from_staked = slashed.min(stake.staked);
+            stake.staked -= from_staked;
+            stake.unbonding -= slashed - from_staked;
+            if InsuranceManager::get_insurance_config(e).is_some() {
+                forfeited = (slashed - ruling.amount).max(0);
+            }
+            let paid = slashed - forfeited;
+            if paid > 0 {
+                LedgerManager::payout(e, &config.token, &ruling.losing_party, paid)?;
+            }
+            if forfeited > 0 {
+                InsuranceManager::fund(e, &config.token, forfeited)?;
+            }
+            ruling.status = RulingStatus::Overturned;
+        } else {
+            ruling.status = RulingStatus::Upheld;
+        }
+        Self::store_stake(e, &ruling.resolver, &stake);
+        Self::store_ruling(e, &ruling);
+
+        Ok((ruling, slashed, forfeited))
+    }
+
+    pub fn get_stake(e: &Env, resolver: Address) -> ResolverStake {
+        e.storage()
+            .instance()
+            .get(&DataKey::ResolverStake(resolver))
+            .unwrap_or_default()
+    }
+
+    pub fn get_ruling(e: &Env, ruling_id: u64) -> Result<Ruling, ContractError> {
+        e.storage()
+            .persistent()
+            .get(&DataKey::Ruling(ruling_id))
+            .ok_or(ContractError::RulingNotFound)
+    }
+
+    fn store_stake(e: &Env, resolver: &Address, stake: &ResolverStake) {
+        e.storage()
+            .instance()
+            .set(&DataKey::ResolverStake(resolver.clone()), stake);
+    }
+
+    fn store_ruling(e: &Env, ruling: &Ruling) {
+        let key = DataKey::Ruling(ruling.ruling_id);
+        let max_ttl = e.storage().max_ttl();
+        e.storage().persistent().set(&key, ruling);
+        e.storage().persistent().extend_ttl(&key, max_ttl, max_ttl);
+    }
+}
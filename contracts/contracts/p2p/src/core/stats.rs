@@ -0,0 +1,97 @@
+use soroban_sdk::{Address, Env, Vec};
+
+use crate::error::ContractError;
+use crate::storage::types::{DataKey, FiatCurrency, MarketStats, Order, VolumeBucket};
+
+/// Width of a volume bucket.
+const BUCKET_SECS: u64 = 3_600;
+
+/// Buckets kept per market, so a day of hourly volume at most.
+const MAX_BUCKETS: u32 = 24;
+
+/// Keeps running aggregates per token and fiat currency, updated once per
+/// settled fill. Each market is a single persistent entry of bounded size.
+pub struct MarketStatsManager;
+
+impl MarketStatsManager {
+    /// Records a fill that settled in the buyer's favour at `exchange_rate`.
+    pub fn record_trade(
+        e: &Env,
+        order: &Order,
+        amount: i128,
+        exchange_rate: i128,
+    ) -> Result<(), ContractError> {
+        let now = e.ledger().timestamp();
+        let mut stats = Self::get_market_stats(e, order.token.clone(), order.fiat_currency.clone())
+            .unwrap_or(MarketStats {
+                token: order.token.clone(),
+                currency: order.fiat_currency.clone(),
+                volume: 0,
+                trade_count: 0,
+                last_rate: 0,
+                last_trade_at: 0,
+                buckets: Vec::new(e),
+            });
+
+        stats.volume = stats
+            .volume
+            .checked_add(amount)
+            .ok_or(ContractError::Overflow)?;
+        stats.trade_count = stats
+            .trade_count
+            .checked_add(1)
+            .ok_or(ContractError::Overflow)?;
+        stats.last_rate = exchange_rate;
+        stats.last_trade_at = now;
+
+        let notional = amount
+            .checked_mul(exchange_rate)
+            .ok_or(ContractError::Overflow)?;
+        let start = now - now % BUCKET_SECS;
+        let mut bucket = match stats.buckets.last() {
+            Some(last) if last.start == start => {
+                stats.buckets.pop_back();
+                last
+            }
+            _ => VolumeBucket {
+                start,
+                volume: 0,
+                notional: 0,
+                vwap_rate: 0,
+            },
+        };
+        bucket.volume = bucket
+            .volume
+            .checked_add(amount)
+            .ok_or(ContractError::Overflow)?;
+        bucket.notional = bucket
+            .notional
+            .checked_add(notional)
+            .ok_or(ContractError::Overflow)?;
+        bucket.vwap_rate = bucket
+            .notional
+            .checked_div(bucket.volume)
+            .ok_or(ContractError::DivisionError)?;
+        stats.buckets.push_back(bucket);
+        while stats.buckets.len() > MAX_BUCKETS {
+            stats.buckets.pop_front();
+        }
+
+        let key = DataKey::MarketStats(order.token.clone(), order.fiat_currency.clone());
+        let max_ttl = e.storage().max_ttl();
+        e.storage().persistent().set(&key, &stats);
+        e.storage().persistent().extend_ttl(&key, max_ttl, max_ttl);
+
+        Ok(())
+    }
+
+    pub fn get_market_stats(
+        e: &Env,
+        token: Address,
+        currency: FiatCurrency,
+    ) -> Option<MarketStats> {
+        e.storage()
+            .persistent()
+            .get(&DataKey::MarketStats(token, currency))
+    }
+}
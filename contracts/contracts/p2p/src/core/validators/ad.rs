@@ -0,0 +1,104 @@
+use soroban_sdk::{Address, Env};
+
+use crate::core::validators::registry::ensure_market_supported;
+use crate::error::ContractError;
+use crate::storage::types::{AdParams, AdStatus, MerchantAd, PaymentMethod};
+
+const SECS_PER_DAY: u64 = 86_400;
+const MAX_AD_PAYMENT_METHODS: u32 = 5;
+
+pub fn validate_ad_params(e: &Env, params: &AdParams) -> Result<(), ContractError> {
+    if params.inventory <= 0 {
+        return Err(ContractError::InvalidAmount);
+    }
+
+    if params.exchange_rate <= 0 {
+        return Err(ContractError::InvalidExchangeRate);
+    }
+
+    if params.min_fill <= 0 || params.min_fill > params.max_fill {
+        return Err(ContractError::InvalidFillAmount);
+    }
+
+    if params.active_from_secs as u64 >= SECS_PER_DAY
+        || params.active_to_secs as u64 >= SECS_PER_DAY
+    {
+        return Err(ContractError::InvalidDuration);
+    }
+
+    if params.payment_methods.is_empty() {
+        return Err(ContractError::PaymentMethodNotSupported);
+    }
+    if params.payment_methods.len() > MAX_AD_PAYMENT_METHODS {
+        return Err(ContractError::TooManyPaymentMethods);
+    }
+
+    for payment_method in params.payment_methods.iter() {
+        ensure_market_supported(e, &params.fiat_currency, &payment_method)?;
+    }
+
+    Ok(())
+}
+
+pub fn ensure_merchant(ad: &MerchantAd, caller: &Address) -> Result<(), ContractError> {
+    if *caller != ad.merchant {
+        return Err(ContractError::Unauthorized);
+    }
+
+    Ok(())
+}
+
+pub fn ensure_ad_open(ad: &MerchantAd) -> Result<(), ContractError> {
+    if ad.status == AdStatus::Closed {
+        return Err(ContractError::AdClosed);
+    }
+
+    Ok(())
+}
+
+pub fn ensure_ad_active(ad: &MerchantAd) -> Result<(), ContractError> {
+    if ad.status != AdStatus::Active {
+        return Err(ContractError::AdNotActive);
+    }
+
+    Ok(())
+}
+
+/// Active hours may wrap past midnight, e.g. 22:00 to 06:00.
+pub fn ensure_within_active_hours(ad: &MerchantAd, now: u64) -> Result<(), ContractError> {
+    if ad.active_from_secs == ad.active_to_secs {
+        return Ok(());
+    }
+
+    let time_of_day = (now % SECS_PER_DAY) as u32;
+    let open = if ad.active_from_secs < ad.active_to_secs {
+        time_of_day >= ad.active_from_secs && time_of_day < ad.active_to_secs
+    } else {
+        time_of_day >= ad.active_from_secs || time_of_day < ad.active_to_secs
+    };
+    if !open {
+        return Err(ContractError::AdOutsideActiveHours);
+    }
+
+    Ok(())
+}
+
+pub fn validate_ad_fill(
+    ad: &MerchantAd,
+    amount: i128,
+    payment_method: &PaymentMethod,
+) -> Result<(), ContractError> {
+    if amount < ad.min_fill || amount > ad.max_fill {
+        return Err(ContractError::InvalidFillAmount);
+    }
+
+    if amount > ad.inventory {
+        return Err(ContractError::FillAmountExceedsRemaining);
+    }
+
+    if !ad.payment_methods.contains(payment_method) {
+        return Err(ContractError::PaymentMethodNotSupported);
+    }
+
+    Ok(())
+}
@@ -1,7 +1,17 @@
-use soroban_sdk::Address;
+use soroban_sdk::{Address, Env};
 
 use crate::error::ContractError;
-use crate::storage::types::Config;
+use crate::storage::types::{Config, DataKey, EmergencyKey, EmergencyState, PauseFlags};
+
+/// Fees are capped at 10% of a fill.
+const MAX_FEE_BPS: u32 = 1_000;
+
+pub enum PausableOperation {
+    NewOrders,
+    Takes,
+    Settlements,
+    Disputes,
+}
 
 pub fn validate_initialize_inputs(
     max_duration_secs: u64,
@@ -14,6 +24,51 @@ pub fn validate_initialize_inputs(
     Ok(())
 }
 
+pub fn validate_timeout_bounds(min_secs: u64, max_secs: u64) -> Result<(), ContractError> {
+    if min_secs == 0 || min_secs > max_secs {
+        return Err(ContractError::InvalidTimeout);
+    }
+
+    Ok(())
+}
+
+pub fn validate_max_fiat_extension(max_secs: u64, config: &Config) -> Result<(), ContractError> {
+    if max_secs > config.max_duration_secs {
+        return Err(ContractError::InvalidTimeout);
+    }
+
+    Ok(())
+}
+
+pub fn validate_settlement_hold(
+    hold_secs: &Option<u64>,
+    config: &Config,
+) -> Result<(), ContractError> {
+    if let Some(hold_secs) = hold_secs {
+        if *hold_secs == 0 || *hold_secs > config.max_duration_secs {
+            return Err(ContractError::InvalidSettlementHold);
+        }
+    }
+
+    Ok(())
+}
+
+pub fn validate_fee_config(fee_bps: u32, referral_share_bps: u32) -> Result<(), ContractError> {
+    if fee_bps > MAX_FEE_BPS || referral_share_bps > 10_000 {
+        return Err(ContractError::InvalidFeeConfig);
+    }
+
+    Ok(())
+}
+
+pub fn ensure_admin(config: &Config, caller: &Address) -> Result<(), ContractError> {
+    if *caller != config.admin {
+        return Err(ContractError::Unauthorized);
+    }
+
+    Ok(())
+}
+
 pub fn ensure_pauser(config: &Config, caller: &Address) -> Result<(), ContractError> {
     if *caller != config.pauser {
         return Err(ContractError::Unauthorized);
@@ -30,10 +85,57 @@ pub fn ensure_dispute_resolver(config: &Config, caller: &Address) -> Result<(),
     Ok(())
 }
 
-pub fn ensure_not_paused(config: &Config) -> Result<(), ContractError> {
-    if config.paused {
+pub fn ensure_compliance(config: &Config, caller: &Address) -> Result<(), ContractError> {
+    if *caller != config.compliance {
+        return Err(ContractError::Unauthorized);
+    }
+
+    Ok(())
+}
+
+/// Rejects `operation` when the contract is globally paused, in emergency
+/// mode, or the operation's own pause flag is set.
+pub fn ensure_not_paused(
+    e: &Env,
+    config: &Config,
+    operation: PausableOperation,
+) -> Result<(), ContractError> {
+    if config.paused
+        || e.storage()
+            .instance()
+            .has(&DataKey::Emergency(EmergencyKey::State))
+    {
+        return Err(ContractError::Paused);
+    }
+
+    let flags: PauseFlags = e
+        .storage()
+        .instance()
+        .get(&DataKey::PauseFlags)
+        .unwrap_or_default();
+    let paused = match operation {
+        PausableOperation::NewOrders => flags.new_orders,
+        PausableOperation::Takes => flags.takes,
+        PausableOperation::Settlements => flags.settlements,
+        PausableOperation::Disputes => flags.disputes,
+    };
+    if paused {
         return Err(ContractError::Paused);
     }
 
     Ok(())
 }
+
+pub fn ensure_emergency_exit_open(
+    emergency: &Option<EmergencyState>,
+    now: u64,
+) -> Result<(), ContractError> {
+    let emergency = emergency
+        .as_ref()
+        .ok_or(ContractError::EmergencyNotActive)?;
+    if now < emergency.exit_at {
+        return Err(ContractError::EmergencyExitNotOpen);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,28 @@
+use crate::error::ContractError;
+use crate::storage::types::{BreakerConfig, CurrencyActivity};
+
+/// Slices a breaker window is counted in.
+pub const WINDOW_BUCKETS: u64 = 12;
+
+pub fn validate_breaker_config(breaker_config: &BreakerConfig) -> Result<(), ContractError> {
+    if breaker_config.window_secs < WINDOW_BUCKETS {
+        return Err(ContractError::InvalidBreakerConfig);
+    }
+
+    if breaker_config.max_dispute_bps == 0
+        || breaker_config.max_dispute_bps > 10_000
+        || breaker_config.min_samples == 0
+    {
+        return Err(ContractError::InvalidBreakerConfig);
+    }
+
+    Ok(())
+}
+
+pub fn ensure_not_tripped(activity: &Option<CurrencyActivity>) -> Result<(), ContractError> {
+    if matches!(activity, Some(activity) if activity.tripped_at.is_some()) {
+        return Err(ContractError::CircuitBreakerTripped);
+    }
+
+    Ok(())
+}
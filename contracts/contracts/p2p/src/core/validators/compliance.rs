@@ -0,0 +1,60 @@
+use soroban_sdk::{Address, Env};
+
+use crate::error::ContractError;
+use crate::storage::types::{DataKey, Order, OrderStatus};
+
+pub fn is_blocked(e: &Env, address: &Address) -> bool {
+    e.storage()
+        .persistent()
+        .get(&DataKey::Blocked(address.clone()))
+        .unwrap_or(false)
+}
+
+pub fn ensure_not_blocked(e: &Env, address: &Address) -> Result<(), ContractError> {
+    if is_blocked(e, address) {
+        return Err(ContractError::AddressBlocked);
+    }
+
+    Ok(())
+}
+
+pub fn ensure_not_frozen(order: &Order) -> Result<(), ContractError> {
+    if order.frozen {
+        return Err(ContractError::OrderFrozen);
+    }
+
+    Ok(())
+}
+
+pub fn ensure_freezable(e: &Env, order: &Order) -> Result<(), ContractError> {
+    if order.frozen {
+        return Err(ContractError::AlreadyFrozen);
+    }
+
+    match order.status {
+        OrderStatus::AwaitingFiller
+        | OrderStatus::AwaitingPayment
+        | OrderStatus::AwaitingConfirmation
+        | OrderStatus::Settling
+        | OrderStatus::Disputed => {}
+        _ => return Err(ContractError::InvalidOrderStatus),
+    }
+
+    let filler_blocked = match &order.filler {
+        Some(filler) => is_blocked(e, filler),
+        None => false,
+    };
+    if !is_blocked(e, &order.creator) && !filler_blocked {
+        return Err(ContractError::PartyNotBlocked);
+    }
+
+    Ok(())
+}
+
+pub fn ensure_frozen(order: &Order) -> Result<(), ContractError> {
+    if !order.frozen {
+        return Err(ContractError::NotFrozen);
+    }
+
+    Ok(())
+}
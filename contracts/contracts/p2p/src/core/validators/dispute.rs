@@ -1,12 +1,13 @@
 use crate::error::ContractError;
 use crate::storage::types::{Order, OrderStatus};
 
+/// Fills can be disputed while awaiting confirmation and, in case the fiat
+/// payment is reversed, during a settlement hold.
 pub fn ensure_disputable(order: &Order) -> Result<(), ContractError> {
-    if order.status != OrderStatus::AwaitingConfirmation {
-        return Err(ContractError::InvalidOrderStatus);
+    match order.status {
+        OrderStatus::AwaitingConfirmation | OrderStatus::Settling => Ok(()),
+        _ => Err(ContractError::InvalidOrderStatus),
     }
-
-    Ok(())
 }
 
 pub fn ensure_disputed(order: &Order) -> Result<(), ContractError> {
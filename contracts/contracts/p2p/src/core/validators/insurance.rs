@@ -0,0 +1,62 @@
+use soroban_sdk::Address;
+
+use crate::error::ContractError;
+use crate::storage::types::{ClaimStatus, InsuranceClaim, InsuranceConfig};
+
+/// Approvers a pool can have, which bounds the approvals kept per claim.
+const MAX_APPROVERS: u32 = 10;
+
+pub fn validate_insurance_config(config: &InsuranceConfig) -> Result<(), ContractError> {
+    if config.fee_share_bps > 10_000 {
+        return Err(ContractError::InvalidInsuranceConfig);
+    }
+
+    let approver_count = config.approvers.len();
+    if approver_count == 0
+        || approver_count > MAX_APPROVERS
+        || config.threshold == 0
+        || config.threshold > approver_count
+    {
+        return Err(ContractError::InvalidInsuranceConfig);
+    }
+
+    for (index, approver) in config.approvers.iter().enumerate() {
+        if config.approvers.first_index_of(&approver) != Some(index as u32) {
+            return Err(ContractError::InvalidInsuranceConfig);
+        }
+    }
+
+    if config.max_claim <= 0 {
+        return Err(ContractError::InvalidInsuranceConfig);
+    }
+
+    Ok(())
+}
+
+pub fn validate_claim_amount(config: &InsuranceConfig, amount: i128) -> Result<(), ContractError> {
+    if amount <= 0 {
+        return Err(ContractError::InvalidAmount);
+    }
+
+    if amount > config.max_claim {
+        return Err(ContractError::ClaimTooLarge);
+    }
+
+    Ok(())
+}
+
+pub fn ensure_approver(config: &InsuranceConfig, caller: &Address) -> Result<(), ContractError> {
+    if !config.approvers.contains(caller) {
+        return Err(ContractError::Unauthorized);
+    }
+
+    Ok(())
+}
+
+pub fn ensure_claim_pending(claim: &InsuranceClaim) -> Result<(), ContractError> {
+    if claim.status != ClaimStatus::Pending {
+        return Err(ContractError::ClaimNotPending);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,39 @@
+use crate::error::ContractError;
+use crate::storage::types::{Config, Offer, OfferStatus};
+
+pub fn validate_offer(
+    amount: i128,
+    exchange_rate: i128,
+    valid_for_secs: u64,
+    config: &Config,
+) -> Result<(), ContractError> {
+    if amount <= 0 {
+        return Err(ContractError::InvalidAmount);
+    }
+
+    if exchange_rate <= 0 {
+        return Err(ContractError::InvalidExchangeRate);
+    }
+
+    if valid_for_secs == 0 || valid_for_secs > config.max_duration_secs {
+        return Err(ContractError::InvalidDuration);
+    }
+
+    Ok(())
+}
+
+pub fn ensure_offer_open(offer: &Offer) -> Result<(), ContractError> {
+    if offer.status != OfferStatus::Open {
+        return Err(ContractError::OfferNotOpen);
+    }
+
+    Ok(())
+}
+
+pub fn ensure_offer_not_expired(offer: &Offer, now: u64) -> Result<(), ContractError> {
+    if offer.expires_at <= now {
+        return Err(ContractError::OfferExpired);
+    }
+
+    Ok(())
+}
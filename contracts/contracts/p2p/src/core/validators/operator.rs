@@ -0,0 +1,56 @@
+use soroban_sdk::Address;
+
+use crate::error::ContractError;
+use crate::storage::types::{OperatorGrant, OperatorScope, OperatorUsage};
+
+pub fn validate_operator_grant(
+    owner: &Address,
+    operator: &Address,
+    grant: &OperatorGrant,
+    now: u64,
+) -> Result<(), ContractError> {
+    if owner == operator {
+        return Err(ContractError::InvalidAddress);
+    }
+
+    if grant.scopes.is_empty() {
+        return Err(ContractError::InvalidOperatorGrant);
+    }
+
+    if matches!(grant.expires_at, Some(expires_at) if expires_at <= now) {
+        return Err(ContractError::InvalidOperatorGrant);
+    }
+
+    if matches!(grant.daily_volume_cap, Some(cap) if cap <= 0) {
+        return Err(ContractError::InvalidOperatorGrant);
+    }
+
+    Ok(())
+}
+
+pub fn ensure_operator_scope(
+    grant: &OperatorGrant,
+    scope: &OperatorScope,
+    now: u64,
+) -> Result<(), ContractError> {
+    if !grant.scopes.contains(scope) {
+        return Err(ContractError::Unauthorized);
+    }
+
+    if matches!(grant.expires_at, Some(expires_at) if expires_at <= now) {
+        return Err(ContractError::OperatorGrantExpired);
+    }
+
+    Ok(())
+}
+
+pub fn ensure_within_volume_cap(
+    grant: &OperatorGrant,
+    usage: &OperatorUsage,
+) -> Result<(), ContractError> {
+    if matches!(grant.daily_volume_cap, Some(cap) if usage.volume > cap) {
+        return Err(ContractError::OperatorVolumeCapExceeded);
+    }
+
+    Ok(())
+}
@@ -1,7 +1,13 @@
-use soroban_sdk::Address;
+use soroban_sdk::{Address, Bytes, Env, Vec};
 
 use crate::error::ContractError;
-use crate::storage::types::{Config, Order, OrderStatus};
+use crate::storage::types::{
+    Config, FiatCurrency, Order, OrderStatus, OrderVisibility, PaymentMethod, TimeoutBounds,
+};
+
+/// Orders a batch entry point handles in one call. Larger batches fail with
+/// `BatchTooLarge`.
+pub const MAX_BATCH_SIZE: u32 = 20;
 
 pub fn validate_create_order(
     amount: i128,
@@ -24,6 +30,36 @@ pub fn validate_create_order(
     Ok(())
 }
 
+/// Creators may only pick a settlement timeout once the admin has set bounds.
+pub fn validate_requested_timeout(
+    requested_timeout_secs: &Option<u64>,
+    bounds: &Option<TimeoutBounds>,
+) -> Result<(), ContractError> {
+    let Some(timeout_secs) = requested_timeout_secs else {
+        return Ok(());
+    };
+
+    match bounds {
+        Some(bounds) if *timeout_secs >= bounds.min_secs && *timeout_secs <= bounds.max_secs => {
+            Ok(())
+        }
+        _ => Err(ContractError::TimeoutOutOfBounds),
+    }
+}
+
+pub fn validate_visibility(
+    visibility: &OrderVisibility,
+    creator: &Address,
+) -> Result<(), ContractError> {
+    if let OrderVisibility::Allowlist(takers) = visibility {
+        if takers.is_empty() || takers.contains(creator) {
+            return Err(ContractError::InvalidVisibility);
+        }
+    }
+
+    Ok(())
+}
+
 pub fn ensure_status(order: &Order, expected: OrderStatus) -> Result<(), ContractError> {
     if order.status != expected {
         return Err(ContractError::InvalidOrderStatus);
@@ -32,6 +68,14 @@ pub fn ensure_status(order: &Order, expected: OrderStatus) -> Result<(), Contrac
     Ok(())
 }
 
+/// Only orders that can no longer change are archived.
+pub fn ensure_archivable(order: &Order) -> Result<(), ContractError> {
+    match order.status {
+        OrderStatus::Completed | OrderStatus::Cancelled | OrderStatus::Refunded => Ok(()),
+        _ => Err(ContractError::InvalidOrderStatus),
+    }
+}
+
 pub fn ensure_creator(order: &Order, caller: &Address) -> Result<(), ContractError> {
     if *caller != order.creator {
         return Err(ContractError::Unauthorized);
@@ -68,6 +112,39 @@ pub fn ensure_fiat_timeout_expired(order: &Order, now: u64) -> Result<(), Contra
     Ok(())
 }
 
+/// A new fiat deadline must move the current one later, lie in the future
+/// and keep the fill's total extension within `max_secs`.
+pub fn validate_deadline_extension(
+    current_deadline: u64,
+    new_deadline: u64,
+    now: u64,
+    total_secs: u64,
+    max_secs: u64,
+) -> Result<(), ContractError> {
+    if max_secs == 0 {
+        return Err(ContractError::FiatExtensionDisabled);
+    }
+
+    if new_deadline <= current_deadline || new_deadline <= now {
+        return Err(ContractError::InvalidTimeout);
+    }
+
+    if total_secs > max_secs {
+        return Err(ContractError::FiatExtensionExceedsLimit);
+    }
+
+    Ok(())
+}
+
+/// A held fill is released once its hold is over, like a timeout.
+pub fn ensure_hold_expired(release_at: u64, now: u64) -> Result<(), ContractError> {
+    if release_at > now {
+        return Err(ContractError::SettlementHoldActive);
+    }
+
+    Ok(())
+}
+
 pub fn ensure_filler(order: &Order, caller: &Address) -> Result<(), ContractError> {
     match &order.filler {
         Some(filler) if *filler == *caller => Ok(()),
@@ -94,3 +171,69 @@ pub fn ensure_active_fill_amount(order: &Order) -> Result<i128, ContractError> {
         _ => Err(ContractError::MissingActiveFill),
     }
 }
+
+pub fn ensure_taker_allowed(
+    e: &Env,
+    order: &Order,
+    caller: &Address,
+    secret: &Option<Bytes>,
+) -> Result<(), ContractError> {
+    match &order.visibility {
+        OrderVisibility::Public => Ok(()),
+        OrderVisibility::Allowlist(takers) => {
+            if !takers.contains(caller) {
+                return Err(ContractError::TakerNotAllowed);
+            }
+            Ok(())
+        }
+        OrderVisibility::SecretHash(hash) => {
+            let secret = secret.as_ref().ok_or(ContractError::InvalidSecret)?;
+            if e.crypto().sha256(secret).to_bytes() != *hash {
+                return Err(ContractError::InvalidSecret);
+            }
+            Ok(())
+        }
+    }
+}
+
+pub fn validate_sweep(
+    total_amount: i128,
+    worst_rate: i128,
+    order_ids: &Vec<u64>,
+) -> Result<(), ContractError> {
+    if total_amount <= 0 {
+        return Err(ContractError::InvalidAmount);
+    }
+
+    if worst_rate <= 0 {
+        return Err(ContractError::InvalidExchangeRate);
+    }
+
+    if order_ids.len() > MAX_BATCH_SIZE {
+        return Err(ContractError::BatchTooLarge);
+    }
+
+    for (i, order_id) in order_ids.iter().enumerate() {
+        if order_ids.iter().skip(i + 1).any(|other| other == order_id) {
+            return Err(ContractError::DuplicateOrderId);
+        }
+    }
+
+    Ok(())
+}
+
+pub fn ensure_market(
+    order: &Order,
+    fiat_currency: &FiatCurrency,
+    payment_method: &PaymentMethod,
+    from_crypto: bool,
+) -> Result<(), ContractError> {
+    if order.fiat_currency != *fiat_currency
+        || order.payment_method != *payment_method
+        || order.from_crypto != from_crypto
+    {
+        return Err(ContractError::OrderMarketMismatch);
+    }
+
+    Ok(())
+}
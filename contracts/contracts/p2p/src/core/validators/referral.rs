@@ -0,0 +1,19 @@
+use soroban_sdk::Address;
+
+use crate::error::ContractError;
+
+pub fn validate_referrer(
+    caller: &Address,
+    referrer: &Address,
+    current: &Option<Address>,
+) -> Result<(), ContractError> {
+    if current.is_some() {
+        return Err(ContractError::ReferrerAlreadySet);
+    }
+
+    if caller == referrer {
+        return Err(ContractError::InvalidReferrer);
+    }
+
+    Ok(())
+}
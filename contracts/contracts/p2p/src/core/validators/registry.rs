@@ -0,0 +1,60 @@
+use soroban_sdk::Env;
+
+use crate::error::ContractError;
+use crate::storage::types::{
+    CurrencyInfo, DataKey, FiatCurrency, PaymentMethod, PaymentMethodInfo,
+};
+
+const ISO_CODE_LEN: u32 = 3;
+const MAX_FIAT_DECIMALS: u32 = 6;
+const MAX_METHOD_NAME_LEN: u32 = 32;
+
+pub fn validate_currency_info(info: &CurrencyInfo) -> Result<(), ContractError> {
+    if info.iso_code.len() != ISO_CODE_LEN || info.decimals > MAX_FIAT_DECIMALS {
+        return Err(ContractError::InvalidRegistryEntry);
+    }
+
+    Ok(())
+}
+
+/// Every currency a method lists must already be registered.
+pub fn validate_payment_method_info(
+    e: &Env,
+    info: &PaymentMethodInfo,
+) -> Result<(), ContractError> {
+    if info.name.is_empty() || info.name.len() > MAX_METHOD_NAME_LEN || info.currencies.is_empty() {
+        return Err(ContractError::InvalidRegistryEntry);
+    }
+
+    for currency in info.currencies.iter() {
+        if !e.storage().instance().has(&DataKey::Currency(currency)) {
+            return Err(ContractError::CurrencyNotSupported);
+        }
+    }
+
+    Ok(())
+}
+
+pub fn ensure_market_supported(
+    e: &Env,
+    fiat_currency: &FiatCurrency,
+    payment_method: &PaymentMethod,
+) -> Result<(), ContractError> {
+    let currency: Option<CurrencyInfo> = e
+        .storage()
+        .instance()
+        .get(&DataKey::Currency(fiat_currency.clone()));
+    match currency {
+        Some(currency) if currency.enabled => {}
+        _ => return Err(ContractError::CurrencyNotSupported),
+    }
+
+    let method: Option<PaymentMethodInfo> = e
+        .storage()
+        .instance()
+        .get(&DataKey::PaymentMethod(payment_method.clone()));
+    match method {
+        Some(method) if method.enabled && method.currencies.contains(fiat_currency) => Ok(()),
+        _ => Err(ContractError::PaymentMethodNotSupported),
+    }
+}
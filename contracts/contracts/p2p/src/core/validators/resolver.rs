@@ -0,0 +1,49 @@
+use soroban_sdk::Address;
+
+use crate::error::ContractError;
+use crate::storage::types::{Config, ResolverStakeConfig, Ruling, RulingStatus};
+
+pub fn validate_resolver_stake_config(
+    stake_config: &ResolverStakeConfig,
+) -> Result<(), ContractError> {
+    if stake_config.min_stake <= 0 || stake_config.slash_bps > 10_000 {
+        return Err(ContractError::InvalidResolverStakeConfig);
+    }
+
+    // Stake must stay slashable for as long as a ruling can be appealed.
+    if stake_config.appeal_window_secs == 0
+        || stake_config.unbonding_secs < stake_config.appeal_window_secs
+    {
+        return Err(ContractError::InvalidResolverStakeConfig);
+    }
+
+    Ok(())
+}
+
+pub fn ensure_appeal_judge(
+    config: &Config,
+    stake_config: &ResolverStakeConfig,
+    caller: &Address,
+) -> Result<(), ContractError> {
+    if *caller != config.admin && *caller != stake_config.appeal_court {
+        return Err(ContractError::Unauthorized);
+    }
+
+    Ok(())
+}
+
+pub fn ensure_ruling_status(ruling: &Ruling, status: RulingStatus) -> Result<(), ContractError> {
+    if ruling.status != status {
+        return Err(ContractError::InvalidRulingStatus);
+    }
+
+    Ok(())
+}
+
+pub fn ensure_within_appeal_window(ruling: &Ruling, now: u64) -> Result<(), ContractError> {
+    if now > ruling.appeal_deadline {
+        return Err(ContractError::AppealWindowClosed);
+    }
+
+    Ok(())
+}
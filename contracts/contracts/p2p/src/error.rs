@@ -1,8 +1,11 @@
 use core::fmt;
 use soroban_sdk::contracterror;
 
+/// Every error the contract returns. A spec error enum holds at most 50
+/// cases, so this one is left out of the contract spec and its codes are
+/// published through the enums in [`spec`] instead.
 #[derive(Debug, Copy, Clone, PartialEq)]
-#[contracterror]
+#[contracterror(export = false)]
 pub enum ContractError {
     InvalidAmount = 1,
     InvalidExchangeRate = 2,
@@ -26,6 +29,80 @@ pub enum ContractError {
     InvalidFillAmount = 20,
     FillAmountExceedsRemaining = 21,
     MissingActiveFill = 22,
+    AddressBlocked = 23,
+    AlreadyBlocked = 24,
+    NotBlocked = 25,
+    OrderFrozen = 26,
+    AlreadyFrozen = 27,
+    NotFrozen = 28,
+    PartyNotBlocked = 29,
+    InvalidVisibility = 30,
+    TakerNotAllowed = 31,
+    InvalidSecret = 32,
+    OrderMarketMismatch = 33,
+    NoFillableOrders = 34,
+    UnsupportedSchemaVersion = 35,
+    EmergencyAlreadyActive = 36,
+    EmergencyNotActive = 37,
+    EmergencyExitNotOpen = 38,
+    NothingToWithdraw = 39,
+    NoSurplus = 40,
+    MigrationPending = 41,
+    CurrencyNotSupported = 42,
+    PaymentMethodNotSupported = 43,
+    InvalidRegistryEntry = 44,
+    TimeoutOutOfBounds = 45,
+    FillHeldInEscrow = 46,
+    InvalidRetention = 47,
+    InvalidFeeConfig = 48,
+    ReferrerAlreadySet = 49,
+    InvalidReferrer = 50,
+    AdNotFound = 51,
+    TooManyAds = 52,
+    AdClosed = 53,
+    AdNotActive = 54,
+    AdOutsideActiveHours = 55,
+    AdOutOfInventory = 56,
+    TooManyOpenDisputes = 57,
+    TooManyPaymentMethods = 58,
+    OfferNotFound = 59,
+    TooManyOffers = 60,
+    TooManyOffersFromTaker = 61,
+    OfferNotOpen = 62,
+    OfferExpired = 63,
+    OfferStillOpen = 64,
+    FiatExtensionDisabled = 65,
+    FiatExtensionExceedsLimit = 66,
+    InvalidSettlementHold = 67,
+    SettlementHoldActive = 68,
+    InsuranceNotConfigured = 69,
+    InvalidInsuranceConfig = 70,
+    ClaimNotFound = 71,
+    ClaimTooLarge = 72,
+    TooManyClaims = 73,
+    ClaimAlreadyPending = 74,
+    ClaimNotPending = 75,
+    ClaimAlreadyApproved = 76,
+    InsufficientInsurancePool = 77,
+    ResolverStakingNotConfigured = 78,
+    InvalidResolverStakeConfig = 79,
+    InsufficientStake = 80,
+    StakeStillUnbonding = 81,
+    AppealPending = 82,
+    StakeLockedByRuling = 83,
+    RulingNotFound = 84,
+    InvalidRulingStatus = 85,
+    AppealWindowClosed = 86,
+    TooManyOperators = 87,
+    InvalidOperatorGrant = 88,
+    OperatorNotFound = 89,
+    OperatorGrantExpired = 90,
+    OperatorVolumeCapExceeded = 91,
+    BatchTooLarge = 92,
+    InvalidBreakerConfig = 93,
+    CircuitBreakerTripped = 94,
+    BreakerNotTripped = 95,
+    DuplicateOrderId = 96,
 }
 
 impl fmt::Display for ContractError {
@@ -59,6 +136,244 @@ impl fmt::Display for ContractError {
                 write!(f, "Fill amount exceeds remaining amount")
             }
             ContractError::MissingActiveFill => write!(f, "Order active fill amount is missing"),
+            ContractError::AddressBlocked => write!(f, "Address is blocked"),
+            ContractError::AlreadyBlocked => write!(f, "Address is already blocked"),
+            ContractError::NotBlocked => write!(f, "Address is not blocked"),
+            ContractError::OrderFrozen => write!(f, "Order is frozen"),
+            ContractError::AlreadyFrozen => write!(f, "Order is already frozen"),
+            ContractError::NotFrozen => write!(f, "Order is not frozen"),
+            ContractError::PartyNotBlocked => {
+                write!(f, "Neither order party is blocked")
+            }
+            ContractError::InvalidVisibility => write!(f, "Invalid order visibility"),
+            ContractError::TakerNotAllowed => write!(f, "Taker is not allowed for this order"),
+            ContractError::InvalidSecret => write!(f, "Invalid order secret"),
+            ContractError::OrderMarketMismatch => {
+                write!(f, "Order does not match the requested market")
+            }
+            ContractError::NoFillableOrders => write!(f, "No order could be filled"),
+            ContractError::UnsupportedSchemaVersion => {
+                write!(f, "Stored schema version is not supported")
+            }
+            ContractError::EmergencyAlreadyActive => write!(f, "Emergency mode is already active"),
+            ContractError::EmergencyNotActive => write!(f, "Emergency mode is not active"),
+            ContractError::EmergencyExitNotOpen => write!(f, "Emergency exit is not open yet"),
+            ContractError::NothingToWithdraw => write!(f, "Nothing to withdraw"),
+            ContractError::NoSurplus => write!(f, "No surplus tokens to sweep"),
+            ContractError::MigrationPending => {
+                write!(f, "Storage must be migrated before this operation")
+            }
+            ContractError::CurrencyNotSupported => {
+                write!(f, "Fiat currency is not registered or is disabled")
+            }
+            ContractError::PaymentMethodNotSupported => {
+                write!(
+                    f,
+                    "Payment method is not registered, disabled or not offered in this currency"
+                )
+            }
+            ContractError::InvalidRegistryEntry => write!(f, "Invalid registry entry"),
+            ContractError::TimeoutOutOfBounds => {
+                write!(f, "Settlement timeout is outside the allowed bounds")
+            }
+            ContractError::FillHeldInEscrow => {
+                write!(f, "Active fill is held in a settlement escrow")
+            }
+            ContractError::InvalidRetention => {
+                write!(
+                    f,
+                    "Retention must be between one ledger and the maximum TTL"
+                )
+            }
+            ContractError::InvalidFeeConfig => write!(f, "Invalid fee configuration"),
+            ContractError::ReferrerAlreadySet => write!(f, "Referrer is already registered"),
+            ContractError::InvalidReferrer => write!(f, "Invalid referrer"),
+            ContractError::AdNotFound => write!(f, "Ad not found"),
+            ContractError::TooManyAds => write!(f, "Merchant has too many open ads"),
+            ContractError::AdClosed => write!(f, "Ad is closed"),
+            ContractError::AdNotActive => write!(f, "Ad is not active"),
+            ContractError::AdOutsideActiveHours => write!(f, "Ad is outside its active hours"),
+            ContractError::AdOutOfInventory => {
+                write!(f, "Ad inventory does not cover a minimum fill")
+            }
+            ContractError::TooManyOpenDisputes => write!(f, "Merchant has too many open disputes"),
+            ContractError::TooManyPaymentMethods => write!(f, "Too many payment methods"),
+            ContractError::OfferNotFound => write!(f, "Offer not found"),
+            ContractError::TooManyOffers => write!(f, "Order has too many open offers"),
+            ContractError::TooManyOffersFromTaker => {
+                write!(f, "Taker has too many open offers on this order")
+            }
+            ContractError::OfferNotOpen => write!(f, "Offer is not open"),
+            ContractError::OfferExpired => write!(f, "Offer has expired"),
+            ContractError::OfferStillOpen => write!(f, "Offer can still be accepted"),
+            ContractError::FiatExtensionDisabled => {
+                write!(f, "Fiat deadline extensions are disabled")
+            }
+            ContractError::FiatExtensionExceedsLimit => {
+                write!(f, "Fiat deadline extension exceeds the configured maximum")
+            }
+            ContractError::InvalidSettlementHold => write!(f, "Settlement hold is out of bounds"),
+            ContractError::SettlementHoldActive => write!(f, "Settlement hold has not ended"),
+            ContractError::InsuranceNotConfigured => write!(f, "Insurance pool is not configured"),
+            ContractError::InvalidInsuranceConfig => write!(f, "Invalid insurance config"),
+            ContractError::ClaimNotFound => write!(f, "Insurance claim not found"),
+            ContractError::ClaimTooLarge => write!(f, "Claim exceeds the per-claim cap"),
+            ContractError::TooManyClaims => write!(f, "Order has too many open or paid claims"),
+            ContractError::ClaimAlreadyPending => {
+                write!(f, "Claimant already has a pending claim on this order")
+            }
+            ContractError::ClaimNotPending => write!(f, "Insurance claim is not pending"),
+            ContractError::ClaimAlreadyApproved => {
+                write!(f, "Approver already approved this claim")
+            }
+            ContractError::InsufficientInsurancePool => {
+                write!(f, "Insurance pool cannot cover the claim")
+            }
+            ContractError::ResolverStakingNotConfigured => {
+                write!(f, "Resolver staking is not configured")
+            }
+            ContractError::InvalidResolverStakeConfig => write!(f, "Invalid resolver stake config"),
+            ContractError::InsufficientStake => write!(f, "Amount exceeds the bonded stake"),
+            ContractError::StakeStillUnbonding => write!(f, "Stake is still unbonding"),
+            ContractError::AppealPending => {
+                write!(f, "An appeal against the resolver is undecided")
+            }
+            ContractError::StakeLockedByRuling => {
+                write!(
+                    f,
+                    "Stake is locked until the resolver's rulings can no longer be appealed"
+                )
+            }
+            ContractError::RulingNotFound => write!(f, "Ruling not found"),
+            ContractError::InvalidRulingStatus => write!(f, "Invalid ruling status"),
+            ContractError::AppealWindowClosed => write!(f, "Appeal window has closed"),
+            ContractError::TooManyOperators => write!(f, "Owner has too many operators"),
+            ContractError::InvalidOperatorGrant => write!(f, "Invalid operator grant"),
+            ContractError::OperatorNotFound => write!(f, "Operator not found"),
+            ContractError::OperatorGrantExpired => write!(f, "Operator grant has expired"),
+            ContractError::OperatorVolumeCapExceeded => {
+                write!(f, "Operator daily volume cap exceeded")
+            }
+            ContractError::BatchTooLarge => write!(f, "Batch has too many items"),
+            ContractError::InvalidBreakerConfig => write!(f, "Invalid circuit breaker config"),
+            ContractError::CircuitBreakerTripped => {
+                write!(f, "Circuit breaker has suspended takes in this currency")
+            }
+            ContractError::BreakerNotTripped => write!(f, "Circuit breaker is not tripped"),
+            ContractError::DuplicateOrderId => write!(f, "Order id appears more than once"),
         }
     }
 }
+
+/// The contract spec's view of [`ContractError`], split in two since a spec
+/// error enum holds at most 50 cases. Both list the same codes under the
+/// same names as `ContractError`.
+pub mod spec {
+    use soroban_sdk::contracterror;
+
+    #[derive(Debug, Copy, Clone, PartialEq)]
+    #[contracterror]
+    pub enum ContractError {
+        InvalidAmount = 1,
+        InvalidExchangeRate = 2,
+        InvalidDuration = 3,
+        OrderNotFound = 4,
+        InvalidOrderStatus = 5,
+        Unauthorized = 6,
+        OrderExpired = 7,
+        FiatTransferHasNotExpired = 8,
+        AlreadyInitialized = 9,
+        ConfigNotInitialized = 10,
+        Paused = 11,
+        AlreadyPaused = 12,
+        AlreadyUnpaused = 13,
+        MissingFiller = 14,
+        Overflow = 15,
+        Underflow = 16,
+        DivisionError = 17,
+        InvalidTimeout = 18,
+        InvalidAddress = 19,
+        InvalidFillAmount = 20,
+        FillAmountExceedsRemaining = 21,
+        MissingActiveFill = 22,
+        AddressBlocked = 23,
+        AlreadyBlocked = 24,
+        NotBlocked = 25,
+        OrderFrozen = 26,
+        AlreadyFrozen = 27,
+        NotFrozen = 28,
+        PartyNotBlocked = 29,
+        InvalidVisibility = 30,
+        TakerNotAllowed = 31,
+        InvalidSecret = 32,
+        OrderMarketMismatch = 33,
+        NoFillableOrders = 34,
+        UnsupportedSchemaVersion = 35,
+        EmergencyAlreadyActive = 36,
+        EmergencyNotActive = 37,
+        EmergencyExitNotOpen = 38,
+        NothingToWithdraw = 39,
+        NoSurplus = 40,
+        MigrationPending = 41,
+        CurrencyNotSupported = 42,
+        PaymentMethodNotSupported = 43,
+        InvalidRegistryEntry = 44,
+        TimeoutOutOfBounds = 45,
+        FillHeldInEscrow = 46,
+        InvalidRetention = 47,
+        InvalidFeeConfig = 48,
+        ReferrerAlreadySet = 49,
+        InvalidReferrer = 50,
+    }
+
+    #[derive(Debug, Copy, Clone, PartialEq)]
+    #[contracterror]
+    pub enum ContractErrorExt {
+        AdNotFound = 51,
+        TooManyAds = 52,
+        AdClosed = 53,
+        AdNotActive = 54,
+        AdOutsideActiveHours = 55,
+        AdOutOfInventory = 56,
+        TooManyOpenDisputes = 57,
+        TooManyPaymentMethods = 58,
+        OfferNotFound = 59,
+        TooManyOffers = 60,
+        TooManyOffersFromTaker = 61,
+        OfferNotOpen = 62,
+        OfferExpired = 63,
+        OfferStillOpen = 64,
+        FiatExtensionDisabled = 65,
+        FiatExtensionExceedsLimit = 66,
+        InvalidSettlementHold = 67,
+        SettlementHoldActive = 68,
+        InsuranceNotConfigured = 69,
+        InvalidInsuranceConfig = 70,
+        ClaimNotFound = 71,
+        ClaimTooLarge = 72,
+        TooManyClaims = 73,
+        ClaimAlreadyPending = 74,
+        ClaimNotPending = 75,
+        ClaimAlreadyApproved = 76,
+        InsufficientInsurancePool = 77,
+        ResolverStakingNotConfigured = 78,
+        InvalidResolverStakeConfig = 79,
+        InsufficientStake = 80,
+        StakeStillUnbonding = 81,
+        AppealPending = 82,
+        StakeLockedByRuling = 83,
+        RulingNotFound = 84,
+        InvalidRulingStatus = 85,
+        AppealWindowClosed = 86,
+        TooManyOperators = 87,
+        InvalidOperatorGrant = 88,
+        OperatorNotFound = 89,
+        OperatorGrantExpired = 90,
+        OperatorVolumeCapExceeded = 91,
+        BatchTooLarge = 92,
+        InvalidBreakerConfig = 93,
+        CircuitBreakerTripped = 94,
+        BreakerNotTripped = 95,
+        DuplicateOrderId = 96,
+    }
+}
@@ -1,83 +1,748 @@
-use soroban_sdk::{contractevent, Address};
+use soroban_sdk::{contractevent, Address, BytesN, String, Vec};
+
+use crate::storage::types::{
+    AdStatus, ClaimStatus, FiatCurrency, OfferStatus, OperatorScope, OrderStatus, PaymentMethod,
+    RulingStatus, TimeoutScope,
+};
+
+/// Published as the `version` topic of every event. Bump it whenever an
+/// event's fields change so indexers can decode old and new payloads.
+pub const EVENT_VERSION: u32 = 2;
 
 #[contractevent(topics = ["p2p_initialized"], data_format = "vec")]
 #[derive(Clone)]
 pub struct Initialized {
+    #[topic]
+    pub version: u32,
     pub admin: Address,
     pub dispute_resolver: Address,
     pub pauser: Address,
+    pub compliance: Address,
     pub token: Address,
+    pub max_duration_secs: u64,
+    pub filler_payment_timeout_secs: u64,
 }
 
 #[contractevent(topics = ["p2p_paused"], data_format = "single-value")]
 #[derive(Clone)]
 pub struct PausedEvt {
+    #[topic]
+    pub version: u32,
     pub by: Address,
 }
 
 #[contractevent(topics = ["p2p_unpaused"], data_format = "single-value")]
 #[derive(Clone)]
 pub struct UnpausedEvt {
+    #[topic]
+    pub version: u32,
+    pub by: Address,
+}
+
+#[contractevent(topics = ["p2p_pause_flags_updated"], data_format = "vec")]
+#[derive(Clone)]
+pub struct PauseFlagsUpdated {
+    #[topic]
+    pub version: u32,
     pub by: Address,
+    pub new_orders: bool,
+    pub takes: bool,
+    pub settlements: bool,
+    pub disputes: bool,
+}
+
+#[contractevent(topics = ["p2p_emergency_triggered"], data_format = "vec")]
+#[derive(Clone)]
+pub struct EmergencyTriggered {
+    #[topic]
+    pub version: u32,
+    pub by: Address,
+    pub triggered_at: u64,
+    pub exit_at: u64,
+}
+
+#[contractevent(topics = ["p2p_emergency_cancelled"], data_format = "single-value")]
+#[derive(Clone)]
+pub struct EmergencyCancelled {
+    #[topic]
+    pub version: u32,
+    pub by: Address,
+}
+
+#[contractevent(topics = ["p2p_emergency_delay_updated"], data_format = "vec")]
+#[derive(Clone)]
+pub struct EmergencyDelayUpdated {
+    #[topic]
+    pub version: u32,
+    pub by: Address,
+    pub delay_secs: u64,
+}
+
+#[contractevent(topics = ["p2p_emergency_withdrawal"], data_format = "vec")]
+#[derive(Clone)]
+pub struct EmergencyWithdrawal {
+    #[topic]
+    pub version: u32,
+    pub order_id: u64,
+    pub recipient: Address,
+    pub token: Address,
+    pub amount: i128,
+    pub status: OrderStatus,
 }
 
 #[contractevent(topics = ["p2p_order_created"], data_format = "vec")]
 #[derive(Clone)]
 pub struct OrderCreated {
+    #[topic]
+    pub version: u32,
     pub order_id: u64,
     pub creator: Address,
+    pub token: Address,
     pub amount: i128,
+    pub deposited: i128,
+    pub exchange_rate: i128,
     pub from_crypto: bool,
+    pub fiat_currency: FiatCurrency,
+    pub payment_method: PaymentMethod,
+    pub deadline: u64,
+    pub status: OrderStatus,
+    pub client_ref: Option<BytesN<32>>,
+}
+
+#[contractevent(topics = ["p2p_order_archived"], data_format = "vec")]
+#[derive(Clone)]
+pub struct OrderArchived {
+    #[topic]
+    pub version: u32,
+    pub order_id: u64,
+    pub status: OrderStatus,
+    pub archived_at: u64,
+}
+
+#[contractevent(topics = ["p2p_archive_retention_updated"], data_format = "vec")]
+#[derive(Clone)]
+pub struct ArchiveRetentionUpdated {
+    #[topic]
+    pub version: u32,
+    pub by: Address,
+    pub ledgers: u32,
 }
 
 #[contractevent(topics = ["p2p_order_cancelled"], data_format = "vec")]
 #[derive(Clone)]
 pub struct OrderCancelled {
+    #[topic]
+    pub version: u32,
     pub order_id: u64,
     pub cancelled_by: Address,
+    pub token: Address,
+    pub refunded_to: Option<Address>,
+    pub refund_amount: i128,
+    pub status: OrderStatus,
 }
 
 #[contractevent(topics = ["p2p_order_taken"], data_format = "vec")]
 #[derive(Clone)]
 pub struct OrderTaken {
+    #[topic]
+    pub version: u32,
     pub order_id: u64,
+    pub creator: Address,
     pub filler: Address,
+    pub token: Address,
+    pub fill_amount: i128,
+    pub deposited: i128,
+    pub remaining_amount: i128,
+    pub fiat_transfer_deadline: u64,
+    pub status: OrderStatus,
 }
 
 #[contractevent(topics = ["p2p_fiat_payment_submitted"], data_format = "vec")]
 #[derive(Clone)]
 pub struct FiatPaymentSubmitted {
+    #[topic]
+    pub version: u32,
     pub order_id: u64,
     pub submitted_by: Address,
+    pub creator: Address,
+    pub filler: Address,
+    pub fill_amount: i128,
+    pub status: OrderStatus,
 }
 
 #[contractevent(topics = ["p2p_fiat_transfer_timeout"], data_format = "vec")]
 #[derive(Clone)]
 pub struct FiatTransferTimeout {
+    #[topic]
+    pub version: u32,
     pub order_id: u64,
     pub executed_by: Address,
+    pub creator: Address,
+    pub filler: Address,
+    pub token: Address,
     pub refunded_to: Option<Address>,
     pub refund_amount: i128,
+    pub remaining_amount: i128,
+    pub status: OrderStatus,
 }
 
 #[contractevent(topics = ["p2p_fiat_payment_confirmed"], data_format = "vec")]
 #[derive(Clone)]
 pub struct FiatPaymentConfirmed {
+    #[topic]
+    pub version: u32,
     pub order_id: u64,
     pub confirmed_by: Address,
+    pub creator: Address,
+    pub filler: Address,
+    pub token: Address,
+    pub released_to: Address,
+    pub amount_released: i128,
+    pub filled_amount: i128,
+    pub remaining_amount: i128,
+    pub status: OrderStatus,
 }
 
 #[contractevent(topics = ["p2p_fiat_payment_disputed"], data_format = "vec")]
 #[derive(Clone)]
 pub struct FiatPaymentDisputed {
+    #[topic]
+    pub version: u32,
     pub order_id: u64,
     pub disputed_by: Address,
+    pub creator: Address,
+    pub filler: Address,
+    pub fill_amount: i128,
+    pub status: OrderStatus,
 }
 
 #[contractevent(topics = ["p2p_dispute_resolved"], data_format = "vec")]
 #[derive(Clone)]
 pub struct DisputeResolved {
+    #[topic]
+    pub version: u32,
     pub order_id: u64,
     pub resolved_by: Address,
     pub fiat_transfer_confirmed: bool,
+    pub creator: Address,
+    pub filler: Address,
+    pub token: Address,
+    pub paid_to: Option<Address>,
+    pub amount: i128,
+    pub filled_amount: i128,
+    pub remaining_amount: i128,
+    pub status: OrderStatus,
+}
+
+#[contractevent(topics = ["p2p_surplus_swept"], data_format = "vec")]
+#[derive(Clone)]
+pub struct SurplusSwept {
+    #[topic]
+    pub version: u32,
+    pub token: Address,
+    pub to: Address,
+    pub amount: i128,
+    pub swept_by: Address,
+}
+
+#[contractevent(topics = ["p2p_currency_updated"], data_format = "vec")]
+#[derive(Clone)]
+pub struct CurrencyUpdated {
+    #[topic]
+    pub version: u32,
+    pub currency: FiatCurrency,
+    pub iso_code: String,
+    pub decimals: u32,
+    pub enabled: bool,
+}
+
+#[contractevent(topics = ["p2p_payment_method_updated"], data_format = "vec")]
+#[derive(Clone)]
+pub struct PaymentMethodUpdated {
+    #[topic]
+    pub version: u32,
+    pub method: PaymentMethod,
+    pub name: String,
+    pub currencies: Vec<FiatCurrency>,
+    pub enabled: bool,
+}
+
+#[contractevent(topics = ["p2p_timeout_override_updated"], data_format = "vec")]
+#[derive(Clone)]
+pub struct TimeoutOverrideUpdated {
+    #[topic]
+    pub version: u32,
+    pub scope: TimeoutScope,
+    pub timeout_secs: Option<u64>,
+}
+
+#[contractevent(topics = ["p2p_timeout_bounds_updated"], data_format = "vec")]
+#[derive(Clone)]
+pub struct TimeoutBoundsUpdated {
+    #[topic]
+    pub version: u32,
+    pub min_secs: u64,
+    pub max_secs: u64,
+}
+
+#[contractevent(topics = ["p2p_escrow_settings_updated"], data_format = "vec")]
+#[derive(Clone)]
+pub struct EscrowSettingsUpdated {
+    #[topic]
+    pub version: u32,
+    pub factory: Option<Address>,
+    pub wasm_hash: Option<BytesN<32>>,
+    pub trustless_work: Option<Address>,
+}
+
+#[contractevent(topics = ["p2p_fill_escrow_opened"], data_format = "vec")]
+#[derive(Clone)]
+pub struct FillEscrowOpened {
+    #[topic]
+    pub version: u32,
+    #[topic]
+    pub order_id: u64,
+    pub escrow: Address,
+    pub amount: i128,
+}
+
+#[contractevent(topics = ["p2p_address_blocked"], data_format = "vec")]
+#[derive(Clone)]
+pub struct AddressBlocked {
+    #[topic]
+    pub version: u32,
+    pub address: Address,
+    pub blocked_by: Address,
+}
+
+#[contractevent(topics = ["p2p_address_unblocked"], data_format = "vec")]
+#[derive(Clone)]
+pub struct AddressUnblocked {
+    #[topic]
+    pub version: u32,
+    pub address: Address,
+    pub unblocked_by: Address,
+}
+
+#[contractevent(topics = ["p2p_order_frozen"], data_format = "vec")]
+#[derive(Clone)]
+pub struct OrderFrozen {
+    #[topic]
+    pub version: u32,
+    pub order_id: u64,
+    pub frozen_by: Address,
+    pub status: OrderStatus,
+}
+
+#[contractevent(topics = ["p2p_order_unfrozen"], data_format = "vec")]
+#[derive(Clone)]
+pub struct OrderUnfrozen {
+    #[topic]
+    pub version: u32,
+    pub order_id: u64,
+    pub unfrozen_by: Address,
+    pub status: OrderStatus,
+}
+
+#[contractevent(topics = ["p2p_fee_config_updated"], data_format = "vec")]
+#[derive(Clone)]
+pub struct FeeConfigUpdated {
+    #[topic]
+    pub version: u32,
+    pub fee_bps: u32,
+    pub referral_share_bps: u32,
+    pub fee_recipient: Address,
+}
+
+#[contractevent(topics = ["p2p_fee_charged"], data_format = "vec")]
+#[derive(Clone)]
+pub struct FeeCharged {
+    #[topic]
+    pub version: u32,
+    #[topic]
+    pub order_id: u64,
+    pub payer: Address,
+    pub fee: i128,
+    pub referrer: Option<Address>,
+    pub referral_amount: i128,
+}
+
+#[contractevent(topics = ["p2p_referrer_registered"], data_format = "vec")]
+#[derive(Clone)]
+pub struct ReferrerRegistered {
+    #[topic]
+    pub version: u32,
+    pub trader: Address,
+    pub referrer: Address,
+}
+
+#[contractevent(topics = ["p2p_referral_rewards_claimed"], data_format = "vec")]
+#[derive(Clone)]
+pub struct ReferralRewardsClaimed {
+    #[topic]
+    pub version: u32,
+    pub referrer: Address,
+    pub amount: i128,
+}
+
+#[contractevent(topics = ["p2p_ad_created"], data_format = "vec")]
+#[derive(Clone)]
+pub struct AdCreated {
+    #[topic]
+    pub version: u32,
+    #[topic]
+    pub ad_id: u64,
+    pub merchant: Address,
+    pub from_crypto: bool,
+    pub fiat_currency: FiatCurrency,
+    pub payment_methods: Vec<PaymentMethod>,
+    pub exchange_rate: i128,
+    pub inventory: i128,
+    pub min_fill: i128,
+    pub max_fill: i128,
+    pub status: AdStatus,
+}
+
+/// Published with the `OrderTaken` event of the order opened for the fill.
+#[contractevent(topics = ["p2p_ad_filled"], data_format = "vec")]
+#[derive(Clone)]
+pub struct AdFilled {
+    #[topic]
+    pub version: u32,
+    #[topic]
+    pub ad_id: u64,
+    pub order_id: u64,
+    pub taker: Address,
+    pub amount: i128,
+    pub inventory: i128,
+    pub status: AdStatus,
+}
+
+#[contractevent(topics = ["p2p_ad_updated"], data_format = "vec")]
+#[derive(Clone)]
+pub struct AdUpdated {
+    #[topic]
+    pub version: u32,
+    #[topic]
+    pub ad_id: u64,
+    pub inventory: i128,
+    pub status: AdStatus,
+}
+
+#[contractevent(topics = ["p2p_ad_closed"], data_format = "vec")]
+#[derive(Clone)]
+pub struct AdClosed {
+    #[topic]
+    pub version: u32,
+    #[topic]
+    pub ad_id: u64,
+    pub merchant: Address,
+    pub refund_amount: i128,
+}
+
+#[contractevent(topics = ["p2p_offer_made"], data_format = "vec")]
+#[derive(Clone)]
+pub struct OfferMade {
+    #[topic]
+    pub version: u32,
+    #[topic]
+    pub order_id: u64,
+    pub offer_id: u64,
+    pub taker: Address,
+    pub amount: i128,
+    pub exchange_rate: i128,
+    pub deposited: i128,
+    pub expires_at: u64,
+}
+
+/// Published with the `OrderTaken` event of the fill the offer started.
+#[contractevent(topics = ["p2p_offer_accepted"], data_format = "vec")]
+#[derive(Clone)]
+pub struct OfferAccepted {
+    #[topic]
+    pub version: u32,
+    #[topic]
+    pub order_id: u64,
+    pub offer_id: u64,
+    pub taker: Address,
+    pub amount: i128,
+    pub exchange_rate: i128,
+}
+
+#[contractevent(topics = ["p2p_offer_closed"], data_format = "vec")]
+#[derive(Clone)]
+pub struct OfferClosed {
+    #[topic]
+    pub version: u32,
+    #[topic]
+    pub order_id: u64,
+    pub offer_id: u64,
+    pub taker: Address,
+    pub refund_amount: i128,
+    pub status: OfferStatus,
+}
+
+#[contractevent(topics = ["p2p_max_fiat_extension_updated"], data_format = "vec")]
+#[derive(Clone)]
+pub struct MaxFiatExtensionUpdated {
+    #[topic]
+    pub version: u32,
+    pub max_secs: u64,
+}
+
+#[contractevent(topics = ["p2p_fiat_deadline_extended"], data_format = "vec")]
+#[derive(Clone)]
+pub struct FiatDeadlineExtended {
+    #[topic]
+    pub version: u32,
+    #[topic]
+    pub order_id: u64,
+    pub extended_by: Address,
+    pub previous_deadline: u64,
+    pub new_deadline: u64,
+}
+
+#[contractevent(topics = ["p2p_settlement_hold_updated"], data_format = "vec")]
+#[derive(Clone)]
+pub struct SettlementHoldUpdated {
+    #[topic]
+    pub version: u32,
+    pub payment_method: PaymentMethod,
+    pub hold_secs: Option<u64>,
+}
+
+/// Published instead of `FiatPaymentConfirmed` when a confirmed fill is
+/// held before release.
+#[contractevent(topics = ["p2p_settlement_held"], data_format = "vec")]
+#[derive(Clone)]
+pub struct SettlementHeld {
+    #[topic]
+    pub version: u32,
+    #[topic]
+    pub order_id: u64,
+    pub confirmed_by: Address,
+    pub filler: Address,
+    pub amount: i128,
+    pub release_at: u64,
+}
+
+#[contractevent(topics = ["p2p_settlement_finalized"], data_format = "vec")]
+#[derive(Clone)]
+pub struct SettlementFinalized {
+    #[topic]
+    pub version: u32,
+    #[topic]
+    pub order_id: u64,
+    pub creator: Address,
+    pub filler: Address,
+    pub token: Address,
+    pub released_to: Address,
+    pub amount_released: i128,
+    pub filled_amount: i128,
+    pub remaining_amount: i128,
+    pub status: OrderStatus,
+}
+
+#[contractevent(topics = ["p2p_insurance_config_updated"], data_format = "vec")]
+#[derive(Clone)]
+pub struct InsuranceConfigUpdated {
+    #[topic]
+    pub version: u32,
+    pub fee_share_bps: u32,
+    pub approvers: Vec<Address>,
+    pub threshold: u32,
+    pub max_claim: i128,
+}
+
+#[contractevent(topics = ["p2p_insurance_funded"], data_format = "vec")]
+#[derive(Clone)]
+pub struct InsuranceFunded {
+    #[topic]
+    pub version: u32,
+    #[topic]
+    pub order_id: u64,
+    pub token: Address,
+    pub amount: i128,
+    pub pool_balance: i128,
+}
+
+#[contractevent(topics = ["p2p_insurance_claim_filed"], data_format = "vec")]
+#[derive(Clone)]
+pub struct InsuranceClaimFiled {
+    #[topic]
+    pub version: u32,
+    #[topic]
+    pub order_id: u64,
+    pub claim_id: u64,
+    pub claimant: Address,
+    pub amount: i128,
+}
+
+#[contractevent(topics = ["p2p_insurance_claim_approved"], data_format = "vec")]
+#[derive(Clone)]
+pub struct InsuranceClaimApproved {
+    #[topic]
+    pub version: u32,
+    #[topic]
+    pub order_id: u64,
+    pub claim_id: u64,
+    pub approver: Address,
+    pub approvals: u32,
+}
+
+#[contractevent(topics = ["p2p_insurance_claim_closed"], data_format = "vec")]
+#[derive(Clone)]
+pub struct InsuranceClaimClosed {
+    #[topic]
+    pub version: u32,
+    #[topic]
+    pub order_id: u64,
+    pub claim_id: u64,
+    pub claimant: Address,
+    pub amount_paid: i128,
+    pub status: ClaimStatus,
+    pub pool_balance: i128,
+}
+
+#[contractevent(topics = ["p2p_resolver_config_updated"], data_format = "vec")]
+#[derive(Clone)]
+pub struct ResolverStakeConfigUpdated {
+    #[topic]
+    pub version: u32,
+    pub min_stake: i128,
+    pub unbonding_secs: u64,
+    pub appeal_window_secs: u64,
+    pub slash_bps: u32,
+    pub appeal_court: Address,
+}
+
+#[contractevent(topics = ["p2p_resolver_stake_updated"], data_format = "vec")]
+#[derive(Clone)]
+pub struct ResolverStakeUpdated {
+    #[topic]
+    pub version: u32,
+    pub resolver: Address,
+    pub staked: i128,
+    pub unbonding: i128,
+    pub unbonds_at: u64,
+}
+
+#[contractevent(topics = ["p2p_resolver_stake_withdrawn"], data_format = "vec")]
+#[derive(Clone)]
+pub struct ResolverStakeWithdrawn {
+    #[topic]
+    pub version: u32,
+    pub resolver: Address,
+    pub amount: i128,
+}
+
+#[contractevent(topics = ["p2p_ruling_recorded"], data_format = "vec")]
+#[derive(Clone)]
+pub struct RulingRecorded {
+    #[topic]
+    pub version: u32,
+    #[topic]
+    pub order_id: u64,
+    pub ruling_id: u64,
+    pub resolver: Address,
+    pub losing_party: Address,
+    pub appeal_deadline: u64,
+}
+
+#[contractevent(topics = ["p2p_ruling_appealed"], data_format = "vec")]
+#[derive(Clone)]
+pub struct RulingAppealed {
+    #[topic]
+    pub version: u32,
+    #[topic]
+    pub order_id: u64,
+    pub ruling_id: u64,
+    pub appellant: Address,
+}
+
+#[contractevent(topics = ["p2p_appeal_decided"], data_format = "vec")]
+#[derive(Clone)]
+pub struct AppealDecided {
+    #[topic]
+    pub version: u32,
+    #[topic]
+    pub order_id: u64,
+    pub ruling_id: u64,
+    pub decided_by: Address,
+    pub status: RulingStatus,
+    pub resolver: Address,
+    pub slashed: i128,
+    pub paid_to: Address,
+}
+
+#[contractevent(topics = ["p2p_operator_updated"], data_format = "vec")]
+#[derive(Clone)]
+pub struct OperatorUpdated {
+    #[topic]
+    pub version: u32,
+    #[topic]
+    pub owner: Address,
+    pub operator: Address,
+    pub scopes: Vec<OperatorScope>,
+    pub expires_at: Option<u64>,
+    pub daily_volume_cap: Option<i128>,
+}
+
+#[contractevent(topics = ["p2p_operator_revoked"], data_format = "vec")]
+#[derive(Clone)]
+pub struct OperatorRevoked {
+    #[topic]
+    pub version: u32,
+    #[topic]
+    pub owner: Address,
+    pub operator: Address,
+}
+
+#[contractevent(topics = ["p2p_breaker_config_updated"], data_format = "vec")]
+#[derive(Clone)]
+pub struct CircuitBreakerConfigUpdated {
+    #[topic]
+    pub version: u32,
+    pub window_secs: u64,
+    pub max_dispute_bps: u32,
+    pub min_samples: u32,
+}
+
+#[contractevent(topics = ["p2p_breaker_tripped"], data_format = "vec")]
+#[derive(Clone)]
+pub struct CircuitBreakerTripped {
+    #[topic]
+    pub version: u32,
+    pub currency: FiatCurrency,
+    pub disputes: u32,
+    pub timeouts: u32,
+    pub settlements: u32,
+    pub tripped_at: u64,
+}
+
+#[contractevent(topics = ["p2p_breaker_reset"], data_format = "vec")]
+#[derive(Clone)]
+pub struct CircuitBreakerReset {
+    #[topic]
+    pub version: u32,
+    pub currency: FiatCurrency,
+    pub reset_by: Address,
+}
+
+#[contractevent(topics = ["p2p_upgraded"], data_format = "vec")]
+#[derive(Clone)]
+pub struct Upgraded {
+    #[topic]
+    pub version: u32,
+    pub new_wasm_hash: BytesN<32>,
+    pub upgraded_by: Address,
+}
+
+#[contractevent(topics = ["p2p_migrated"], data_format = "vec")]
+#[derive(Clone)]
+pub struct Migrated {
+    #[topic]
+    pub version: u32,
+    pub from_version: u32,
+    pub to_version: u32,
 }
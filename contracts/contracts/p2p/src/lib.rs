@@ -1,19 +1,61 @@
 #![no_std]
+#![allow(clippy::too_many_arguments)]
 
 mod contract;
 mod core {
+    pub mod ad;
     pub mod admin;
+    pub mod archive;
+    pub mod breaker;
+    pub mod compliance;
     pub mod dispute;
+    pub mod escrow;
+    pub mod fees;
+    pub mod history;
+    pub mod insurance;
+    pub mod ledger;
+    pub mod migration;
+    pub mod offer;
+    pub mod operator;
     pub mod order;
+    pub mod referral;
+    pub mod registry;
+    pub mod resolver;
+    pub mod stats;
 
+    pub use ad::*;
     pub use admin::*;
+    pub use archive::*;
+    pub use breaker::*;
+    pub use compliance::*;
     pub use dispute::*;
+    pub use escrow::*;
+    pub use fees::*;
+    pub use history::*;
+    pub use insurance::*;
+    pub use ledger::*;
+    pub use migration::*;
+    pub use offer::*;
+    pub use operator::*;
     pub use order::*;
+    pub use referral::*;
+    pub use registry::*;
+    pub use resolver::*;
+    pub use stats::*;
 
     pub mod validators {
+        pub mod ad;
         pub mod admin;
+        pub mod breaker;
+        pub mod compliance;
         pub mod dispute;
+        pub mod insurance;
+        pub mod offer;
+        pub mod operator;
         pub mod order;
+        pub mod referral;
+        pub mod registry;
+        pub mod resolver;
     }
 }
 mod error;
@@ -22,9 +64,13 @@ mod events {
 }
 
 mod storage {
+    pub mod escrow;
+    pub mod legacy;
     pub mod types;
 }
 mod tests {
+    #[cfg(test)]
+    mod state_machine;
     #[cfg(test)]
     mod test;
 }
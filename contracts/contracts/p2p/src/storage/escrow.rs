@@ -0,0 +1,53 @@
+//! Mirrors of the escrow contract's types, kept field for field so they
+//! encode identically when passed across the contract boundary.
+
+use soroban_sdk::{contracttype, Address, String, Vec};
+
+#[contracttype(export = false)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Escrow {
+    pub engagement_id: String,
+    pub title: String,
+    pub roles: Roles,
+    pub description: String,
+    pub amount: i128,
+    pub platform_fee: u32,
+    pub milestones: Vec<Milestone>,
+    pub flags: Flags,
+    pub trustline: Trustline,
+    pub receiver_memo: i128,
+}
+
+#[contracttype(export = false)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Milestone {
+    pub description: String,
+    pub status: String,
+    pub evidence: String,
+    pub approved: bool,
+}
+
+#[contracttype(export = false)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Roles {
+    pub approver: Address,
+    pub service_provider: Address,
+    pub platform_address: Address,
+    pub release_signer: Address,
+    pub dispute_resolver: Address,
+    pub receiver: Address,
+}
+
+#[contracttype(export = false)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Flags {
+    pub disputed: bool,
+    pub released: bool,
+    pub resolved: bool,
+}
+
+#[contracttype(export = false)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Trustline {
+    pub address: Address,
+}
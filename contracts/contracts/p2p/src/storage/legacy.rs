@@ -0,0 +1,62 @@
+use soroban_sdk::{contracttype, Address};
+
+use crate::storage::types::{FiatCurrency, OrderStatus, OrderVisibility, PaymentMethod};
+
+/// `Config` layout of schema version 1, before the compliance role.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConfigV1 {
+    pub admin: Address,
+    pub dispute_resolver: Address,
+    pub pauser: Address,
+    pub token: Address,
+    pub max_duration_secs: u64,
+    pub filler_payment_timeout_secs: u64,
+    pub paused: bool,
+}
+
+/// `Order` layout of schema version 1, before freezing and private orders.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OrderV1 {
+    pub order_id: u64,
+    pub creator: Address,
+    pub filler: Option<Address>,
+    pub token: Address,
+    pub amount: i128,
+    pub remaining_amount: i128,
+    pub filled_amount: i128,
+    pub active_fill_amount: Option<i128>,
+    pub exchange_rate: i128,
+    pub from_crypto: bool,
+    pub fiat_currency: FiatCurrency,
+    pub payment_method: PaymentMethod,
+    pub status: OrderStatus,
+    pub created_at: u64,
+    pub deadline: u64,
+    pub fiat_transfer_deadline: Option<u64>,
+}
+
+/// `Order` layout of schema versions 2 to 4, before settlement timeouts.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OrderV2 {
+    pub order_id: u64,
+    pub creator: Address,
+    pub filler: Option<Address>,
+    pub token: Address,
+    pub amount: i128,
+    pub remaining_amount: i128,
+    pub filled_amount: i128,
+    pub active_fill_amount: Option<i128>,
+    pub exchange_rate: i128,
+    pub from_crypto: bool,
+    pub fiat_currency: FiatCurrency,
+    pub payment_method: PaymentMethod,
+    pub status: OrderStatus,
+    pub created_at: u64,
+    pub deadline: u64,
+    pub fiat_transfer_deadline: Option<u64>,
+    pub frozen: bool,
+    pub visibility: OrderVisibility,
+}
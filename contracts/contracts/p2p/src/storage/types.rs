@@ -1,4 +1,4 @@
-use soroban_sdk::{contracttype, Address};
+use soroban_sdk::{contracttype, Address, BytesN, String, Vec};
 
 #[contracttype]
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -44,6 +44,55 @@ impl PaymentMethod {
     }
 }
 
+/// Registry entry for a fiat currency. `iso_code` is the ISO 4217 code and
+/// `decimals` the number of minor units frontends should display.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CurrencyInfo {
+    pub currency: FiatCurrency,
+    pub iso_code: String,
+    pub decimals: u32,
+    pub enabled: bool,
+}
+
+/// Registry entry for a payment method and the currencies it settles in.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PaymentMethodInfo {
+    pub method: PaymentMethod,
+    pub name: String,
+    pub currencies: Vec<FiatCurrency>,
+    pub enabled: bool,
+}
+
+/// Scope of an admin settlement timeout override. When several apply, the
+/// market override wins over the payment method, then the currency.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TimeoutScope {
+    Market(PaymentMethod, FiatCurrency),
+    PaymentMethod(PaymentMethod),
+    Currency(FiatCurrency),
+}
+
+/// Range a creator-selected settlement timeout must fall within.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimeoutBounds {
+    pub min_secs: u64,
+    pub max_secs: u64,
+}
+
+/// Who may take an order. Private orders are hidden from public listings.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OrderVisibility {
+    Public,
+    Allowlist(Vec<Address>),
+    /// sha256 of a secret the taker must reveal.
+    SecretHash(BytesN<32>),
+}
+
 #[contracttype]
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum OrderStatus {
@@ -55,6 +104,7 @@ pub enum OrderStatus {
     Disputed,
     Refunded,
     Cancelled,
+    Settling,
 }
 
 #[contracttype]
@@ -63,12 +113,31 @@ pub struct Config {
     pub admin: Address,
     pub dispute_resolver: Address,
     pub pauser: Address,
+    pub compliance: Address,
     pub token: Address,
     pub max_duration_secs: u64,
     pub filler_payment_timeout_secs: u64,
     pub paused: bool,
 }
 
+/// Per-operation pause switches. Cancellations, fiat timeouts and dispute
+/// rulings are never paused so users can always get their funds back.
+#[contracttype]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PauseFlags {
+    pub new_orders: bool,
+    pub takes: bool,
+    pub settlements: bool,
+    pub disputes: bool,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EmergencyState {
+    pub triggered_at: u64,
+    pub exit_at: u64,
+}
+
 #[contracttype]
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Order {
@@ -88,6 +157,421 @@ pub struct Order {
     pub created_at: u64,
     pub deadline: u64,
     pub fiat_transfer_deadline: Option<u64>,
+    pub frozen: bool,
+    pub visibility: OrderVisibility,
+    /// Settlement timeout chosen by the creator, within the admin bounds.
+    pub requested_timeout_secs: Option<u64>,
+    /// Timeout resolved for the active fill.
+    pub payment_timeout_secs: Option<u64>,
+}
+
+/// Protocol fee taken from the crypto released on each settled fill, in
+/// basis points. `referral_share_bps` of it goes to the fee payer's referrer.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FeeConfig {
+    pub fee_bps: u32,
+    pub referral_share_bps: u32,
+    pub fee_recipient: Address,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReferralStats {
+    pub referred_count: u32,
+    pub total_earned: i128,
+    pub claimable: i128,
+}
+
+/// How a fill ended. Dispute outcomes are from the fiat payer's side:
+/// `DisputeWon` when the resolver confirmed the fiat transfer.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FillOutcome {
+    Pending,
+    Confirmed,
+    TimedOut,
+    DisputeWon,
+    DisputeLost,
+    Refunded,
+}
+
+/// One fill of an order. Timestamps are `None` until the step happens.
+/// `deadline_extended_secs` is the total the fiat deadline was extended by.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FillRecord {
+    pub filler: Address,
+    pub amount: i128,
+    pub exchange_rate: i128,
+    pub taken_at: u64,
+    pub submitted_at: Option<u64>,
+    pub settled_at: Option<u64>,
+    pub deadline_extended_secs: u64,
+    pub outcome: FillOutcome,
+}
+
+/// Settled volume in one time bucket. `notional` is the sum of amount times
+/// rate, so `vwap_rate` is `notional / volume`.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VolumeBucket {
+    pub start: u64,
+    pub volume: i128,
+    pub notional: i128,
+    pub vwap_rate: i128,
+}
+
+/// Aggregates over settled fills in one token and fiat currency market.
+/// `buckets` holds the most recent buckets with trades, oldest first.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MarketStats {
+    pub token: Address,
+    pub currency: FiatCurrency,
+    pub volume: i128,
+    pub trade_count: u64,
+    pub last_rate: i128,
+    pub last_trade_at: u64,
+    pub buckets: Vec<VolumeBucket>,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AdStatus {
+    Active,
+    PausedByMerchant,
+    OutOfInventory,
+    TooManyDisputes,
+    Closed,
+}
+
+/// Terms of a standing merchant ad. Active hours are seconds into the UTC
+/// day; equal bounds keep the ad open all day.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AdParams {
+    pub from_crypto: bool,
+    pub fiat_currency: FiatCurrency,
+    pub payment_methods: Vec<PaymentMethod>,
+    pub exchange_rate: i128,
+    pub inventory: i128,
+    pub min_fill: i128,
+    pub max_fill: i128,
+    pub active_from_secs: u32,
+    pub active_to_secs: u32,
+    pub max_open_disputes: u32,
+}
+
+/// A standing ad that opens a single-fill order for each taker and draws
+/// the fill from its inventory. `from_crypto` ads hold their inventory in
+/// the contract.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerchantAd {
+    pub ad_id: u64,
+    pub merchant: Address,
+    pub token: Address,
+    pub from_crypto: bool,
+    pub fiat_currency: FiatCurrency,
+    pub payment_methods: Vec<PaymentMethod>,
+    pub exchange_rate: i128,
+    pub inventory: i128,
+    pub min_fill: i128,
+    pub max_fill: i128,
+    pub active_from_secs: u32,
+    pub active_to_secs: u32,
+    pub max_open_disputes: u32,
+    pub status: AdStatus,
+    pub created_at: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OfferStatus {
+    Open,
+    Accepted,
+    Rejected,
+    Refunded,
+}
+
+/// A taker's counter-offer on an open order. `deposited` is the crypto the
+/// taker escrowed for orders where the taker sells it, returned unless the
+/// offer is accepted.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Offer {
+    pub offer_id: u64,
+    pub order_id: u64,
+    pub taker: Address,
+    pub amount: i128,
+    pub exchange_rate: i128,
+    pub deposited: i128,
+    pub created_at: u64,
+    pub expires_at: u64,
+    pub status: OfferStatus,
+}
+
+/// Insurance pool settings. `fee_share_bps` of the protocol's part of each
+/// fee goes to the pool. A claim is paid once `threshold` of the `approvers`
+/// approve it, and no claim may ask for more than `max_claim`.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InsuranceConfig {
+    pub fee_share_bps: u32,
+    pub approvers: Vec<Address>,
+    pub threshold: u32,
+    pub max_claim: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ClaimStatus {
+    Pending,
+    Paid,
+    Rejected,
+}
+
+/// A trader's claim on the insurance pool for a loss on `order_id`.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InsuranceClaim {
+    pub claim_id: u64,
+    pub order_id: u64,
+    pub claimant: Address,
+    pub token: Address,
+    pub amount: i128,
+    pub approvals: Vec<Address>,
+    pub status: ClaimStatus,
+    pub filed_at: u64,
+}
+
+/// Stake required of the dispute resolver. Rulings can be appealed by the
+/// losing party for `appeal_window_secs`; an appeal the admin or the
+/// `appeal_court` upholds slashes `slash_bps` of the resolver's stake to
+/// that party. Unbonded stake is withdrawable after `unbonding_secs`.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResolverStakeConfig {
+    pub min_stake: i128,
+    pub unbonding_secs: u64,
+    pub appeal_window_secs: u64,
+    pub slash_bps: u32,
+    pub appeal_court: Address,
+}
+
+/// A resolver's bonded stake and the stake waiting out its unbonding
+/// period. Both can be slashed, and neither can be withdrawn before
+/// `locked_until`, the last appeal deadline of the resolver's rulings.
+#[contracttype]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ResolverStake {
+    pub staked: i128,
+    pub unbonding: i128,
+    pub unbonds_at: u64,
+    pub open_appeals: u32,
+    pub locked_until: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RulingStatus {
+    Open,
+    Appealed,
+    Upheld,
+    Overturned,
+}
+
+/// A dispute ruling and the party it went against, who may appeal it until
+/// `appeal_deadline`.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ruling {
+    pub ruling_id: u64,
+    pub order_id: u64,
+    pub resolver: Address,
+    pub fiat_transfer_confirmed: bool,
+    pub losing_party: Address,
+    pub amount: i128,
+    pub ruled_at: u64,
+    pub appeal_deadline: u64,
+    pub status: RulingStatus,
+}
+
+/// What an operator may do for the owner that granted it. None of the
+/// scopes moves funds anywhere but to the owner.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OperatorScope {
+    CreateOrders,
+    CancelOrders,
+    ConfirmPayments,
+}
+
+/// An owner's grant to an operator, such as a trading bot's key. Order
+/// amounts the operator creates and fills it confirms count toward
+/// `daily_volume_cap`, per UTC day.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OperatorGrant {
+    pub scopes: Vec<OperatorScope>,
+    pub expires_at: Option<u64>,
+    pub daily_volume_cap: Option<i128>,
+}
+
+/// Storage keys for operator grants, nested to keep `DataKey` within the
+/// contract spec's variant limit.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OperatorKey {
+    Grant(Address, Address),
+    Usage(Address, Address),
+    Operators(Address),
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OperatorUsage {
+    pub day: u64,
+    pub volume: i128,
+}
+
+/// Everything a creator chooses for a new order, for batch creation.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OrderParams {
+    pub fiat_currency: FiatCurrency,
+    pub payment_method: PaymentMethod,
+    pub from_crypto: bool,
+    pub amount: i128,
+    pub exchange_rate: i128,
+    pub duration_secs: u64,
+    pub requested_timeout_secs: Option<u64>,
+    pub client_ref: Option<BytesN<32>>,
+    pub visibility: OrderVisibility,
+}
+
+/// Trips the circuit breaker for a currency when disputes make up more than
+/// `max_dispute_bps` of the disputes, timeouts and settlements seen in the
+/// last `window_secs`, once there are at least `min_samples` of them.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BreakerConfig {
+    pub window_secs: u64,
+    pub max_dispute_bps: u32,
+    pub min_samples: u32,
+}
+
+/// Fill outcomes in one slice of a currency's breaker window.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutcomeBucket {
+    pub start: u64,
+    pub disputes: u32,
+    pub timeouts: u32,
+    pub settlements: u32,
+}
+
+/// A currency's recent outcomes, oldest first, and when its breaker tripped.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CurrencyActivity {
+    pub buckets: Vec<OutcomeBucket>,
+    pub tripped_at: Option<u64>,
+}
+
+/// Outcome counts within the current breaker window.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BreakerStatus {
+    pub currency: FiatCurrency,
+    pub disputes: u32,
+    pub timeouts: u32,
+    pub settlements: u32,
+    pub tripped_at: Option<u64>,
+}
+
+/// Storage keys for the circuit breaker, nested like `OperatorKey`.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BreakerKey {
+    Config,
+    Activity(FiatCurrency),
+}
+
+/// Storage keys for emergency mode, nested like `OperatorKey`.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EmergencyKey {
+    Delay,
+    State,
+}
+
+/// Where an unfinished `migrate` resumes: the step from `version` to the
+/// next version has rewritten every order before `next_order_id`, and
+/// `liabilities` carries the totals rebuilt so far.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MigrationProgress {
+    pub version: u32,
+    pub next_order_id: u64,
+    pub liabilities: i128,
+}
+
+/// What is kept of an order once it is archived.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OrderSummary {
+    pub order_id: u64,
+    pub creator: Address,
+    pub token: Address,
+    pub amount: i128,
+    pub filled_amount: i128,
+    pub exchange_rate: i128,
+    pub from_crypto: bool,
+    pub fiat_currency: FiatCurrency,
+    pub payment_method: PaymentMethod,
+    pub status: OrderStatus,
+    pub created_at: u64,
+    pub deadline: u64,
+    pub archived_at: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OrderFill {
+    pub order_id: u64,
+    pub fill_amount: i128,
+    pub exchange_rate: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SolvencyReport {
+    pub token: Address,
+    pub liabilities: i128,
+    pub balance: i128,
+    pub surplus: i128,
+    pub solvent: bool,
+}
+
+/// Where escrow-settled fills are deployed from. `factory` is an escrow
+/// contract whose `tw_new_single_release_escrow` deploys `wasm_hash`, and
+/// `trustless_work` receives the Trustless Work fee on every payout.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EscrowSettings {
+    pub factory: Address,
+    pub wasm_hash: BytesN<32>,
+    pub trustless_work: Address,
+}
+
+/// Escrow instance holding an order's active fill.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FillEscrow {
+    pub escrow: Address,
+    pub trustless_work: Address,
 }
 
 #[contracttype]
@@ -96,4 +580,51 @@ pub enum DataKey {
     Config,
     OrderCount,
     Order(u64),
+    Blocked(Address),
+    SchemaVersion,
+    MigrationProgress,
+    PauseFlags,
+    Emergency(EmergencyKey),
+    Liabilities(Address),
+    Currency(FiatCurrency),
+    Currencies,
+    PaymentMethod(PaymentMethod),
+    PaymentMethods,
+    TimeoutOverride(TimeoutScope),
+    TimeoutBounds,
+    EscrowSettings,
+    EscrowCount,
+    FillEscrow(u64),
+    OrderRef(Address, BytesN<32>),
+    ArchiveRetention,
+    OrderSummary(u64),
+    FillHistory(u64),
+    MarketStats(Address, FiatCurrency),
+    FeeConfig,
+    Referrer(Address),
+    ReferralStats(Address),
+    AdCount,
+    Ad(u64),
+    MerchantAds(Address),
+    AdOrder(u64),
+    MerchantOpenDisputes(Address),
+    FillRate(u64),
+    OfferCount,
+    Offer(u64),
+    OrderOffers(u64),
+    MaxFiatExtension,
+    FillExtension(u64),
+    SettlementHold(PaymentMethod),
+    SettlementRelease(u64),
+    InsuranceConfig,
+    InsurancePool(Address),
+    ClaimCount,
+    Claim(u64),
+    OrderClaims(u64),
+    ResolverStakeConfig,
+    ResolverStake(Address),
+    RulingCount,
+    Ruling(u64),
+    Operator(OperatorKey),
+    CircuitBreaker(BreakerKey),
 }
@@ -0,0 +1,454 @@
+#![cfg(test)]
+
+//! Randomized lifecycle runs. Each seed drives a deterministic sequence of
+//! actions across several traders and checks the accounting invariants after
+//! every step. A failure reports its seed and step; replay it with
+//! `P2P_SEED=<seed> cargo test -p p2p state_machine` and pin it by adding a
+//! test that calls `run_seed` with that seed.
+
+extern crate std;
+
+use std::format;
+use std::string::String;
+use std::vec::Vec as StdVec;
+
+use crate::contract::{P2PContract, P2PContractClient};
+use crate::storage::types::{
+    FiatCurrency, FillOutcome, Order, OrderStatus, OrderVisibility, PaymentMethod,
+};
+
+use soroban_sdk::testutils::{Address as _, Ledger};
+use soroban_sdk::{token, Address, Env};
+use token::Client as TokenClient;
+use token::StellarAssetClient as TokenAdminClient;
+
+const SEEDS: u64 = 8;
+const STEPS: u32 = 80;
+const TRADERS: usize = 5;
+const INITIAL_BALANCE: i128 = 1_000_000;
+
+/// xorshift64*, small and stable across platforms so seeds stay replayable.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    fn chance(&mut self, percent: u64) -> bool {
+        self.below(100) < percent
+    }
+}
+
+struct Harness<'a> {
+    env: Env,
+    client: P2PContractClient<'a>,
+    token: TokenClient<'a>,
+    pauser: Address,
+    dispute_resolver: Address,
+    traders: StdVec<Address>,
+    orders: StdVec<Order>,
+    paused: bool,
+}
+
+impl Harness<'_> {
+    fn new() -> Self {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let pauser = Address::generate(&env);
+        let dispute_resolver = Address::generate(&env);
+        let sac = env.register_stellar_asset_contract_v2(admin.clone());
+        let token = TokenClient::new(&env, &sac.address());
+        let token_admin = TokenAdminClient::new(&env, &sac.address());
+
+        let mut traders = StdVec::new();
+        for _ in 0..TRADERS {
+            let trader = Address::generate(&env);
+            token_admin.mint(&trader, &INITIAL_BALANCE);
+            traders.push(trader);
+        }
+
+        let client = P2PContractClient::new(&env, &env.register(P2PContract {}, ()));
+        client.initialize(
+            &admin,
+            &dispute_resolver,
+            &pauser,
+            &admin,
+            &token.address,
+            &2_592_000,
+            &1_800,
+        );
+
+        Harness {
+            env,
+            client,
+            token,
+            pauser,
+            dispute_resolver,
+            traders,
+            orders: StdVec::new(),
+            paused: false,
+        }
+    }
+
+    fn trader(&self, rng: &mut Rng) -> Address {
+        self.traders[rng.below(TRADERS as u64) as usize].clone()
+    }
+
+    /// Usually the party the action expects, sometimes a random trader so
+    /// the auth checks are exercised too.
+    fn party(&self, rng: &mut Rng, expected: Option<Address>) -> Address {
+        match expected {
+            Some(expected) if rng.chance(85) => expected,
+            _ => self.trader(rng),
+        }
+    }
+
+    /// Mostly an order in `status` so actions tend to be valid, otherwise
+    /// any order.
+    fn random_order(&self, rng: &mut Rng, status: OrderStatus) -> Option<Order> {
+        let matching: StdVec<&Order> = self.orders.iter().filter(|o| o.status == status).collect();
+        if !matching.is_empty() && rng.chance(85) {
+            return Some(matching[rng.below(matching.len() as u64) as usize].clone());
+        }
+        if self.orders.is_empty() {
+            return None;
+        }
+        Some(self.orders[rng.below(self.orders.len() as u64) as usize].clone())
+    }
+
+    fn advance(&self, secs: u64) {
+        self.env.ledger().with_mut(|ledger| {
+            ledger.timestamp += secs;
+        });
+    }
+
+    fn step(&mut self, rng: &mut Rng) -> String {
+        if self.paused && rng.chance(30) {
+            self.client.unpause(&self.pauser);
+            self.paused = false;
+            return String::from("unpause");
+        }
+
+        match rng.below(12) {
+            0 => {
+                let creator = self.trader(rng);
+                let from_crypto = rng.chance(50);
+                let amount = 1 + rng.below(1_000) as i128;
+                let rate = 900 + rng.below(200) as i128;
+                let duration = 600 + rng.below(5_000);
+                let ok = self
+                    .client
+                    .try_create_order(
+                        &creator,
+                        &FiatCurrency::Usd,
+                        &PaymentMethod::BankTransfer,
+                        &from_crypto,
+                        &amount,
+                        &rate,
+                        &duration,
+                        &None,
+                        &None,
+                        &OrderVisibility::Public,
+                    )
+                    .is_ok();
+                format!("create(from_crypto={from_crypto}, amount={amount}) ok={ok}")
+            }
+            1 | 2 => {
+                let Some(order) = self.random_order(rng, OrderStatus::AwaitingFiller) else {
+                    return String::from("take(no orders)");
+                };
+                let taker = self.trader(rng);
+                let fill = if order.remaining_amount <= 0 || rng.chance(50) {
+                    order.remaining_amount
+                } else {
+                    1 + rng.below(order.remaining_amount as u64) as i128
+                };
+                let ok = self
+                    .client
+                    .try_take_order_with_amount(&taker, &order.order_id, &fill)
+                    .is_ok();
+                format!("take({}, fill={fill}) ok={ok}", order.order_id)
+            }
+            3 | 4 => {
+                let Some(order) = self.random_order(rng, OrderStatus::AwaitingPayment) else {
+                    return String::from("submit(no orders)");
+                };
+                let expected = if order.from_crypto {
+                    order.filler.clone()
+                } else {
+                    Some(order.creator.clone())
+                };
+                let caller = self.party(rng, expected);
+                let ok = self
+                    .client
+                    .try_submit_fiat_payment(&caller, &order.order_id)
+                    .is_ok();
+                format!("submit({}) ok={ok}", order.order_id)
+            }
+            5 | 6 => {
+                let Some(order) = self.random_order(rng, OrderStatus::AwaitingConfirmation) else {
+                    return String::from("confirm(no orders)");
+                };
+                let expected = if order.from_crypto {
+                    Some(order.creator.clone())
+                } else {
+                    order.filler.clone()
+                };
+                let caller = self.party(rng, expected);
+                let ok = self
+                    .client
+                    .try_confirm_fiat_payment(&caller, &order.order_id)
+                    .is_ok();
+                format!("confirm({}) ok={ok}", order.order_id)
+            }
+            7 => {
+                if rng.chance(50) {
+                    self.advance(1_801);
+                }
+                let Some(order) = self.random_order(rng, OrderStatus::AwaitingPayment) else {
+                    return String::from("timeout(no orders)");
+                };
+                let expected = if order.from_crypto {
+                    Some(order.creator.clone())
+                } else {
+                    order.filler.clone()
+                };
+                let caller = self.party(rng, expected);
+                let ok = self
+                    .client
+                    .try_execute_fiat_transfer_timeout(&caller, &order.order_id)
+                    .is_ok();
+                format!("timeout({}) ok={ok}", order.order_id)
+            }
+            8 => {
+                let Some(order) = self.random_order(rng, OrderStatus::AwaitingConfirmation) else {
+                    return String::from("dispute(no orders)");
+                };
+                let expected = if order.from_crypto {
+                    order.filler.clone()
+                } else {
+                    Some(order.creator.clone())
+                };
+                let caller = self.party(rng, expected);
+                let ok = self
+                    .client
+                    .try_dispute_fiat_payment(&caller, &order.order_id)
+                    .is_ok();
+                format!("dispute({}) ok={ok}", order.order_id)
+            }
+            9 => {
+                let Some(order) = self.random_order(rng, OrderStatus::Disputed) else {
+                    return String::from("resolve(no orders)");
+                };
+                let caller = self.party(rng, Some(self.dispute_resolver.clone()));
+                let confirmed = rng.chance(50);
+                let ok = self
+                    .client
+                    .try_resolve_dispute(&caller, &order.order_id, &confirmed)
+                    .is_ok();
+                format!("resolve({}, confirmed={confirmed}) ok={ok}", order.order_id)
+            }
+            10 => {
+                if rng.chance(80) {
+                    let Some(order) = self.random_order(rng, OrderStatus::AwaitingFiller) else {
+                        return String::from("cancel(no orders)");
+                    };
+                    let caller = self.party(rng, Some(order.creator.clone()));
+                    let ok = self
+                        .client
+                        .try_cancel_order(&caller, &order.order_id)
+                        .is_ok();
+                    format!("cancel({}) ok={ok}", order.order_id)
+                } else {
+                    let ok = self.client.try_pause(&self.pauser).is_ok();
+                    self.paused = true;
+                    format!("pause ok={ok}")
+                }
+            }
+            _ => {
+                let secs = rng.below(600);
+                self.advance(secs);
+                format!("advance({secs})")
+            }
+        }
+    }
+
+    /// Tokens the contract must hold for an order: the creator's escrowed
+    /// remainder for `from_crypto` orders, otherwise the filler's active fill.
+    fn held_for(order: &Order) -> i128 {
+        match order.status {
+            OrderStatus::AwaitingFiller
+            | OrderStatus::AwaitingPayment
+            | OrderStatus::AwaitingConfirmation
+            | OrderStatus::Settling
+            | OrderStatus::Disputed => {
+                if order.from_crypto {
+                    order.remaining_amount
+                } else {
+                    order.active_fill_amount.unwrap_or(0)
+                }
+            }
+            _ => 0,
+        }
+    }
+
+    fn check_invariants(&mut self, context: &str) {
+        let contract_balance = self.token.balance(&self.client.address);
+        let traders_balance: i128 = self.traders.iter().map(|t| self.token.balance(t)).sum();
+        assert_eq!(
+            traders_balance + contract_balance,
+            INITIAL_BALANCE * TRADERS as i128,
+            "{context}: tokens not conserved"
+        );
+
+        let mut held = 0;
+        self.orders.clear();
+        for order_id in 0..self.client.get_order_count() {
+            let order = self.client.get_order(&order_id);
+            assert_eq!(
+                order.filled_amount + order.remaining_amount,
+                order.amount,
+                "{context}: order {order_id} amounts do not add up"
+            );
+            assert!(
+                order.remaining_amount >= 0 && order.filled_amount >= 0,
+                "{context}: order {order_id} has a negative amount"
+            );
+            if let Some(fill) = order.active_fill_amount {
+                assert!(
+                    fill > 0 && fill <= order.remaining_amount,
+                    "{context}: order {order_id} active fill out of range"
+                );
+            }
+            let fills = self.client.get_order_fills(&order_id);
+            let settled: i128 = fills
+                .iter()
+                .filter(|fill| {
+                    fill.outcome == FillOutcome::Confirmed
+                        || fill.outcome == FillOutcome::DisputeWon
+                })
+                .map(|fill| fill.amount)
+                .sum();
+            assert_eq!(
+                settled, order.filled_amount,
+                "{context}: order {order_id} fill history does not match filled amount"
+            );
+            let pending = fills
+                .iter()
+                .filter(|fill| fill.outcome == FillOutcome::Pending)
+                .count();
+            assert_eq!(
+                pending,
+                order.active_fill_amount.is_some() as usize,
+                "{context}: order {order_id} fill history out of step with the active fill"
+            );
+            if order.status == OrderStatus::Completed {
+                assert_eq!(
+                    order.remaining_amount, 0,
+                    "{context}: completed order {order_id} has a remainder"
+                );
+            }
+            held += Self::held_for(&order);
+            self.orders.push(order);
+        }
+
+        assert_eq!(
+            contract_balance, held,
+            "{context}: contract balance does not match open orders"
+        );
+        let report = self.client.check_solvency(&self.token.address);
+        assert_eq!(
+            report.liabilities, held,
+            "{context}: recorded liabilities drifted"
+        );
+        assert!(report.solvent, "{context}: contract is insolvent");
+    }
+}
+
+fn run_seed(seed: u64, steps: u32) {
+    // Printed so host panics, which cannot carry the context, still name it.
+    std::println!("state machine seed {seed}");
+    let mut harness = Harness::new();
+    let mut rng = Rng::new(seed);
+    let mut trace: StdVec<String> = StdVec::new();
+
+    for step in 0..steps {
+        trace.push(harness.step(&mut rng));
+        let recent = trace[trace.len().saturating_sub(5)..].join(", ");
+        harness.check_invariants(&format!("seed {seed} step {step} after [{recent}]"));
+    }
+
+    // Unwind everything still open; no funds may be left behind.
+    if harness.paused {
+        harness.client.unpause(&harness.pauser);
+    }
+    harness.advance(1_801);
+    for order_id in 0..harness.client.get_order_count() {
+        let order = harness.client.get_order(&order_id);
+        match order.status {
+            OrderStatus::AwaitingPayment => {
+                let caller = if order.from_crypto {
+                    order.creator.clone()
+                } else {
+                    order.filler.clone().unwrap()
+                };
+                harness
+                    .client
+                    .execute_fiat_transfer_timeout(&caller, &order_id);
+            }
+            OrderStatus::AwaitingConfirmation => {
+                let caller = if order.from_crypto {
+                    order.filler.clone().unwrap()
+                } else {
+                    order.creator.clone()
+                };
+                harness.client.dispute_fiat_payment(&caller, &order_id);
+                harness
+                    .client
+                    .resolve_dispute(&harness.dispute_resolver, &order_id, &false);
+            }
+            OrderStatus::Disputed => {
+                harness
+                    .client
+                    .resolve_dispute(&harness.dispute_resolver, &order_id, &false);
+            }
+            _ => {}
+        }
+        if harness.client.get_order(&order_id).status == OrderStatus::AwaitingFiller {
+            harness.client.cancel_order(&order.creator, &order_id);
+        }
+    }
+
+    harness.check_invariants(&format!("seed {seed} after unwind"));
+    assert_eq!(
+        harness.token.balance(&harness.client.address),
+        0,
+        "seed {seed}: funds stuck after unwind"
+    );
+}
+
+#[test]
+fn test_random_lifecycles_keep_invariants() {
+    if let Ok(seed) = std::env::var("P2P_SEED") {
+        run_seed(seed.parse().expect("P2P_SEED must be a number"), STEPS);
+        return;
+    }
+
+    for seed in 0..SEEDS {
+        run_seed(seed, STEPS);
+    }
+}
@@ -4,10 +4,29 @@ extern crate std;
 
 use crate::contract::P2PContract;
 use crate::contract::P2PContractClient;
-use crate::storage::types::{FiatCurrency, OrderStatus, PaymentMethod};
+use crate::core::CURRENT_SCHEMA_VERSION;
+use crate::error::{spec, ContractError};
+use crate::events::handler::{
+    ArchiveRetentionUpdated, CircuitBreakerTripped, DisputeResolved, EmergencyDelayUpdated,
+    FeeCharged, FiatDeadlineExtended, FiatPaymentConfirmed, FiatPaymentDisputed,
+    FiatPaymentSubmitted, FiatTransferTimeout, FillEscrowOpened, InsuranceClaimClosed,
+    InsuranceFunded, OrderCancelled, OrderCreated, OrderTaken, PausedEvt, RulingRecorded,
+    SettlementHeld, EVENT_VERSION,
+};
+use crate::storage::escrow::Escrow;
+use crate::storage::legacy::{ConfigV1, OrderV1, OrderV2};
+use crate::storage::types::{
+    AdParams, AdStatus, BreakerConfig, ClaimStatus, DataKey, EscrowSettings, FeeConfig,
+    FiatCurrency, FillOutcome, FillRecord, InsuranceConfig, OfferStatus, OperatorGrant,
+    OperatorScope, OrderParams, OrderStatus, OrderVisibility, PauseFlags, PaymentMethod,
+    ResolverStakeConfig, RulingStatus, TimeoutScope, VolumeBucket,
+};
 
-use soroban_sdk::testutils::{Address as _, Ledger};
-use soroban_sdk::{token, Address, Env};
+use soroban_sdk::testutils::{Address as _, Events, Ledger, MockAuth, MockAuthInvoke};
+use soroban_sdk::{
+    contract, contractimpl, token, vec, Address, Bytes, BytesN, Env, Event, IntoVal, String,
+    Symbol, TryFromVal, Val, Vec,
+};
 use token::Client as TokenClient;
 use token::StellarAssetClient as TokenAdminClient;
 
@@ -25,10 +44,55 @@ fn set_timestamp(e: &Env, timestamp: u64) {
     });
 }
 
+/// Asserts that the last event published by the P2P contract is exactly
+/// `expected`, topics and data included.
+fn assert_last_event(s: &Setup, expected: &impl Event) {
+    let published: std::vec::Vec<(Address, Vec<Val>, Val)> = s
+        .env
+        .events()
+        .all()
+        .iter()
+        .filter(|(contract, _, _)| *contract == s.client.address)
+        .collect();
+    let (contract, topics, data) = published.last().expect("no event published").clone();
+
+    assert_eq!(contract, s.client.address);
+    assert_eq!(topics, expected.topics(&s.env));
+    assert_eq!(
+        Vec::<Val>::from_array(&s.env, [data]),
+        Vec::<Val>::from_array(&s.env, [expected.data(&s.env)])
+    );
+}
+
+/// Asserts that the P2P contract published exactly `expected` at some point
+/// during the last invocation.
+fn assert_event_published(s: &Setup, expected: &impl Event) {
+    let expected_data = Vec::<Val>::from_array(&s.env, [expected.data(&s.env)]);
+    let published = s.env.events().all().iter().any(|(contract, topics, data)| {
+        contract == s.client.address
+            && topics == expected.topics(&s.env)
+            && Vec::<Val>::from_array(&s.env, [data]) == expected_data
+    });
+    assert!(published, "event not published");
+}
+
+/// Asserts that a `try_` call failed with `expected`.
+fn assert_contract_error<T, E: core::fmt::Debug>(
+    result: Result<T, Result<ContractError, E>>,
+    expected: ContractError,
+) {
+    match result {
+        Err(Ok(error)) => assert_eq!(error, expected),
+        Err(Err(error)) => panic!("expected {expected:?}, got {error:?}"),
+        Ok(_) => panic!("expected {expected:?}, call succeeded"),
+    }
+}
+
 struct Setup<'a> {
     env: Env,
     client: P2PContractClient<'a>,
     pauser: Address,
+    compliance: Address,
     dispute_resolver: Address,
     creator: Address,
     filler: Address,
@@ -41,6 +105,7 @@ fn setup<'a>() -> Setup<'a> {
 
     let admin = Address::generate(&env);
     let pauser = Address::generate(&env);
+    let compliance = Address::generate(&env);
     let dispute_resolver = Address::generate(&env);
     let creator = Address::generate(&env);
     let filler = Address::generate(&env);
@@ -55,6 +120,7 @@ fn setup<'a>() -> Setup<'a> {
         &admin,
         &dispute_resolver,
         &pauser,
+        &compliance,
         &token.address,
         &2_592_000,
         &1_800,
@@ -64,6 +130,7 @@ fn setup<'a>() -> Setup<'a> {
         env,
         client,
         pauser,
+        compliance,
         dispute_resolver,
         creator,
         filler,
@@ -84,6 +151,9 @@ fn test_create_order_from_crypto_holds_funds() {
         &100,
         &1200,
         &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
     );
 
     let order = s.client.get_order(&order_id);
@@ -98,6 +168,32 @@ fn test_create_order_from_crypto_holds_funds() {
     assert_eq!(contract_balance, 100);
 }
 
+#[test]
+fn test_spec_errors_match_contract_errors() {
+    let mut code = 1;
+    loop {
+        let error = soroban_sdk::Error::from_contract_error(code);
+        let Ok(flat) = ContractError::try_from(error) else {
+            break;
+        };
+        let name = match (
+            spec::ContractError::try_from(error),
+            spec::ContractErrorExt::try_from(error),
+        ) {
+            (Ok(published), Err(_)) => std::format!("{published:?}"),
+            (Err(_), Ok(published)) => std::format!("{published:?}"),
+            _ => panic!("code {code} must be published by exactly one spec enum"),
+        };
+        assert_eq!(std::format!("{flat:?}"), name);
+        code += 1;
+    }
+
+    let unused = soroban_sdk::Error::from_contract_error(code);
+    assert!(spec::ContractError::try_from(unused).is_err());
+    assert!(spec::ContractErrorExt::try_from(unused).is_err());
+    assert!(code > 50);
+}
+
 #[test]
 fn test_cancel_order_refunds_creator_for_from_crypto() {
     let s = setup();
@@ -111,6 +207,9 @@ fn test_cancel_order_refunds_creator_for_from_crypto() {
         &500,
         &1200,
         &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
     );
 
     s.client.cancel_order(&s.creator, &order_id);
@@ -135,6 +234,9 @@ fn test_take_order_from_fiat_requires_filler_deposit() {
         &700,
         &1250,
         &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
     );
 
     s.client.take_order(&s.filler, &order_id);
@@ -162,6 +264,9 @@ fn test_submit_confirm_from_crypto_releases_to_filler() {
         &400,
         &1000,
         &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
     );
 
     s.client.take_order(&s.filler, &order_id);
@@ -189,6 +294,9 @@ fn test_submit_confirm_from_fiat_releases_to_creator() {
         &450,
         &1000,
         &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
     );
 
     s.client.take_order(&s.filler, &order_id);
@@ -217,6 +325,9 @@ fn test_timeout_resets_order_to_awaiting_filler() {
         &300,
         &1000,
         &5_000,
+        &None,
+        &None,
+        &OrderVisibility::Public,
     );
 
     s.client.take_order(&s.filler, &order_id);
@@ -248,6 +359,9 @@ fn test_dispute_and_resolve_confirmed_completes() {
         &250,
         &1000,
         &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
     );
 
     s.client.take_order(&s.filler, &order_id);
@@ -276,6 +390,9 @@ fn test_dispute_and_resolve_not_confirmed_refunds_depositor() {
         &350,
         &1000,
         &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
     );
 
     s.client.take_order(&s.filler, &order_id);
@@ -284,11 +401,17 @@ fn test_dispute_and_resolve_not_confirmed_refunds_depositor() {
     s.client
         .resolve_dispute(&s.dispute_resolver, &order_id, &false);
 
+    // The rejected fill stays escrowed for the reopened order.
     let order = s.client.get_order(&order_id);
     assert_eq!(order.status, OrderStatus::AwaitingFiller);
     assert_eq!(order.remaining_amount, 350);
     assert_eq!(order.filled_amount, 0);
+    assert_eq!(s.token.balance(&s.client.address), 350);
+    assert!(s.client.check_solvency(&s.token.address).solvent);
+
+    s.client.cancel_order(&s.creator, &order_id);
     assert_eq!(s.token.balance(&s.creator), creator_before);
+    assert_eq!(s.token.balance(&s.client.address), 0);
 }
 
 #[test]
@@ -304,6 +427,9 @@ fn test_partial_fill_reduces_remaining_and_reopens_order() {
         &1_000,
         &1000,
         &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
     );
 
     s.client.take_order_with_amount(&s.filler, &order_id, &200);
@@ -333,6 +459,9 @@ fn test_pause_blocks_mutations() {
         &100,
         &1000,
         &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
     );
     assert!(res.is_err());
 
@@ -345,6 +474,9 @@ fn test_pause_blocks_mutations() {
         &100,
         &1000,
         &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
     );
 
     assert_eq!(order_id, 0);
@@ -362,6 +494,9 @@ fn test_only_dispute_resolver_can_resolve() {
         &300,
         &1000,
         &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
     );
     s.client.take_order(&s.filler, &order_id);
     s.client.submit_fiat_payment(&s.filler, &order_id);
@@ -378,6 +513,7 @@ fn test_initialize_rejects_duplicate_and_invalid_timeouts() {
 
     let admin = Address::generate(&env);
     let pauser = Address::generate(&env);
+    let compliance = Address::generate(&env);
     let dispute_resolver = Address::generate(&env);
     let (token, _) = create_token(&env, &admin);
 
@@ -387,6 +523,7 @@ fn test_initialize_rejects_duplicate_and_invalid_timeouts() {
         &admin,
         &dispute_resolver,
         &pauser,
+        &compliance,
         &token.address,
         &100,
         &10,
@@ -396,6 +533,7 @@ fn test_initialize_rejects_duplicate_and_invalid_timeouts() {
         &admin,
         &dispute_resolver,
         &pauser,
+        &compliance,
         &token.address,
         &100,
         &10,
@@ -403,8 +541,15 @@ fn test_initialize_rejects_duplicate_and_invalid_timeouts() {
     assert!(duplicate.is_err());
 
     let second = P2PContractClient::new(&env, &env.register(P2PContract {}, ()));
-    let invalid_timeout =
-        second.try_initialize(&admin, &dispute_resolver, &pauser, &token.address, &100, &0);
+    let invalid_timeout = second.try_initialize(
+        &admin,
+        &dispute_resolver,
+        &pauser,
+        &compliance,
+        &token.address,
+        &100,
+        &0,
+    );
     assert!(invalid_timeout.is_err());
 }
 
@@ -439,6 +584,9 @@ fn test_create_order_validation_failures() {
         &0,
         &1000,
         &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
     );
     assert!(invalid_amount.is_err());
 
@@ -450,6 +598,9 @@ fn test_create_order_validation_failures() {
         &100,
         &0,
         &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
     );
     assert!(invalid_exchange_rate.is_err());
 
@@ -461,6 +612,9 @@ fn test_create_order_validation_failures() {
         &100,
         &1000,
         &2_592_001,
+        &None,
+        &None,
+        &OrderVisibility::Public,
     );
     assert!(invalid_duration.is_err());
 }
@@ -477,6 +631,9 @@ fn test_cancel_order_negative_paths() {
         &100,
         &1000,
         &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
     );
 
     let unauthorized_cancel = s.client.try_cancel_order(&s.filler, &order_id);
@@ -502,6 +659,9 @@ fn test_take_order_negative_paths() {
         &100,
         &1000,
         &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
     );
 
     let self_take = s.client.try_take_order(&s.creator, &order_id);
@@ -516,6 +676,9 @@ fn test_take_order_negative_paths() {
         &100,
         &1000,
         &10,
+        &None,
+        &None,
+        &OrderVisibility::Public,
     );
     set_timestamp(&s.env, 111);
     let expired_take = s.client.try_take_order(&s.filler, &expiring_order);
@@ -537,6 +700,9 @@ fn test_submit_confirm_dispute_negative_actor_and_status_paths() {
         &200,
         &1000,
         &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
     );
     s.client.take_order(&s.filler, &from_crypto_order);
 
@@ -576,6 +742,9 @@ fn test_timeout_negative_paths() {
         &300,
         &1000,
         &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
     );
     s.client.take_order(&s.filler, &from_crypto_order);
 
@@ -599,3 +768,4374 @@ fn test_timeout_negative_paths() {
         .try_execute_fiat_transfer_timeout(&s.creator, &from_crypto_order);
     assert!(wrong_status.is_err());
 }
+
+#[test]
+fn test_blocked_address_cannot_create_or_take_orders() {
+    let s = setup();
+
+    let order_id = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &100,
+        &1000,
+        &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+
+    s.client.block_address(&s.compliance, &s.filler);
+    assert!(s.client.is_blocked(&s.filler));
+
+    let blocked_take = s.client.try_take_order(&s.filler, &order_id);
+    assert!(blocked_take.is_err());
+
+    let blocked_create = s.client.try_create_order(
+        &s.filler,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &100,
+        &1000,
+        &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    assert!(blocked_create.is_err());
+
+    s.client.unblock_address(&s.compliance, &s.filler);
+    assert!(!s.client.is_blocked(&s.filler));
+    s.client.take_order(&s.filler, &order_id);
+    assert_eq!(
+        s.client.get_order(&order_id).status,
+        OrderStatus::AwaitingPayment
+    );
+}
+
+#[test]
+fn test_block_auth_and_repeat_guards() {
+    let s = setup();
+    let random = Address::generate(&s.env);
+
+    let unauthorized_block = s.client.try_block_address(&s.pauser, &random);
+    assert!(unauthorized_block.is_err());
+
+    s.client.block_address(&s.compliance, &random);
+    let second_block = s.client.try_block_address(&s.compliance, &random);
+    assert!(second_block.is_err());
+
+    s.client.unblock_address(&s.compliance, &random);
+    let second_unblock = s.client.try_unblock_address(&s.compliance, &random);
+    assert!(second_unblock.is_err());
+}
+
+#[test]
+fn test_freeze_active_fill_holds_funds_until_ruling() {
+    let s = setup();
+
+    let filler_before = s.token.balance(&s.filler);
+    let order_id = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &false,
+        &500,
+        &1000,
+        &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    s.client.take_order_with_amount(&s.filler, &order_id, &200);
+    s.client.submit_fiat_payment(&s.creator, &order_id);
+
+    let not_blocked_freeze = s.client.try_freeze_order(&s.compliance, &order_id);
+    assert!(not_blocked_freeze.is_err());
+
+    s.client.block_address(&s.compliance, &s.filler);
+    s.client.freeze_order(&s.compliance, &order_id);
+
+    let order = s.client.get_order(&order_id);
+    assert!(order.frozen);
+    assert_eq!(order.status, OrderStatus::Disputed);
+
+    let confirm_frozen = s.client.try_confirm_fiat_payment(&s.filler, &order_id);
+    assert!(confirm_frozen.is_err());
+    assert_eq!(s.token.balance(&s.client.address), 200);
+
+    s.client
+        .resolve_dispute(&s.dispute_resolver, &order_id, &false);
+    let order = s.client.get_order(&order_id);
+    assert_eq!(order.status, OrderStatus::AwaitingFiller);
+    assert!(order.frozen);
+    assert_eq!(s.token.balance(&s.filler), filler_before);
+
+    let cancel_frozen = s.client.try_cancel_order(&s.creator, &order_id);
+    assert!(cancel_frozen.is_err());
+
+    s.client.unfreeze_order(&s.compliance, &order_id);
+    s.client.cancel_order(&s.creator, &order_id);
+    assert_eq!(s.client.get_order(&order_id).status, OrderStatus::Cancelled);
+    assert_eq!(s.token.balance(&s.client.address), 0);
+}
+
+#[test]
+fn test_allowlisted_order_only_takeable_by_listed_taker() {
+    let s = setup();
+    let outsider = Address::generate(&s.env);
+
+    let order_id = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &100,
+        &1000,
+        &600,
+        &None,
+        &None,
+        &OrderVisibility::Allowlist(vec![&s.env, s.filler.clone()]),
+    );
+
+    let outsider_take = s.client.try_take_order(&outsider, &order_id);
+    assert!(outsider_take.is_err());
+
+    s.client.take_order_with_amount(&s.filler, &order_id, &40);
+    let order = s.client.get_order(&order_id);
+    assert_eq!(order.filler, Some(s.filler.clone()));
+    assert_eq!(order.active_fill_amount, Some(40));
+
+    let empty_allowlist = s.client.try_create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &100,
+        &1000,
+        &600,
+        &None,
+        &None,
+        &OrderVisibility::Allowlist(Vec::new(&s.env)),
+    );
+    assert!(empty_allowlist.is_err());
+}
+
+#[test]
+fn test_secret_order_requires_preimage_and_binds_taker() {
+    let s = setup();
+    let outsider = Address::generate(&s.env);
+    let secret = Bytes::from_slice(&s.env, b"invoice-42");
+    let secret_hash = s.env.crypto().sha256(&secret).to_bytes();
+
+    let order_id = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &100,
+        &1000,
+        &600,
+        &None,
+        &None,
+        &OrderVisibility::SecretHash(secret_hash),
+    );
+
+    let without_secret = s
+        .client
+        .try_take_order_with_amount(&s.filler, &order_id, &50);
+    assert!(without_secret.is_err());
+
+    let wrong_secret = s.client.try_take_private_order(
+        &s.filler,
+        &order_id,
+        &50,
+        &Bytes::from_slice(&s.env, b"invoice-43"),
+    );
+    assert!(wrong_secret.is_err());
+
+    s.client
+        .take_private_order(&s.filler, &order_id, &50, &secret);
+    let order = s.client.get_order(&order_id);
+    assert_eq!(
+        order.visibility,
+        OrderVisibility::Allowlist(vec![&s.env, s.filler.clone()])
+    );
+
+    s.client.submit_fiat_payment(&s.filler, &order_id);
+    s.client.confirm_fiat_payment(&s.creator, &order_id);
+
+    let replayed_secret = s
+        .client
+        .try_take_private_order(&outsider, &order_id, &50, &secret);
+    assert!(replayed_secret.is_err());
+}
+
+#[test]
+fn test_open_orders_listing_excludes_private_orders() {
+    let s = setup();
+
+    let public_id = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &100,
+        &1000,
+        &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &100,
+        &1000,
+        &600,
+        &None,
+        &None,
+        &OrderVisibility::Allowlist(vec![&s.env, s.filler.clone()]),
+    );
+    let taken_id = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &100,
+        &1000,
+        &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    s.client.take_order(&s.filler, &taken_id);
+
+    let open_orders = s.client.get_open_orders(&0, &10);
+    assert_eq!(open_orders.len(), 1);
+    assert_eq!(open_orders.get(0).unwrap().order_id, public_id);
+}
+
+#[test]
+fn test_take_best_orders_fills_best_prices_within_worst_rate() {
+    let s = setup();
+
+    let mut order_ids = Vec::new(&s.env);
+    for rate in [1010, 1000, 1020] {
+        order_ids.push_back(s.client.create_order(
+            &s.creator,
+            &FiatCurrency::Usd,
+            &PaymentMethod::BankTransfer,
+            &true,
+            &100,
+            &rate,
+            &600,
+            &None,
+            &None,
+            &OrderVisibility::Public,
+        ));
+    }
+
+    let fills = s.client.take_best_orders(
+        &s.filler,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &250,
+        &1015,
+        &order_ids,
+    );
+
+    assert_eq!(fills.len(), 2);
+    let first = fills.get(0).unwrap();
+    assert_eq!(first.order_id, order_ids.get(1).unwrap());
+    assert_eq!(first.exchange_rate, 1000);
+    assert_eq!(first.fill_amount, 100);
+    let second = fills.get(1).unwrap();
+    assert_eq!(second.order_id, order_ids.get(0).unwrap());
+    assert_eq!(second.fill_amount, 100);
+
+    let skipped = s.client.get_order(&order_ids.get(2).unwrap());
+    assert_eq!(skipped.status, OrderStatus::AwaitingFiller);
+    assert_eq!(skipped.filler, None);
+}
+
+#[test]
+fn test_take_best_orders_caps_last_leg_at_total_amount() {
+    let s = setup();
+
+    let cheap = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &false,
+        &300,
+        &990,
+        &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    let best = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &false,
+        &100,
+        &1000,
+        &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+
+    let filler_before = s.token.balance(&s.filler);
+    let fills = s.client.take_best_orders(
+        &s.filler,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &false,
+        &150,
+        &900,
+        &vec![&s.env, cheap, best],
+    );
+
+    assert_eq!(fills.len(), 2);
+    assert_eq!(fills.get(0).unwrap().order_id, best);
+    assert_eq!(fills.get(1).unwrap().fill_amount, 50);
+    assert_eq!(s.client.get_order(&cheap).active_fill_amount, Some(50));
+    assert_eq!(s.token.balance(&s.filler), filler_before - 150);
+}
+
+#[test]
+fn test_take_best_orders_reverts_when_any_leg_fails() {
+    let s = setup();
+
+    let creator_order = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &false,
+        &100,
+        &1000,
+        &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    let own_order = s.client.create_order(
+        &s.filler,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &false,
+        &100,
+        &990,
+        &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+
+    let filler_before = s.token.balance(&s.filler);
+    let res = s.client.try_take_best_orders(
+        &s.filler,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &false,
+        &200,
+        &900,
+        &vec![&s.env, creator_order, own_order],
+    );
+    assert!(res.is_err());
+    assert_eq!(
+        s.client.get_order(&creator_order).status,
+        OrderStatus::AwaitingFiller
+    );
+    assert_eq!(s.token.balance(&s.filler), filler_before);
+
+    let wrong_market = s.client.try_take_best_orders(
+        &s.filler,
+        &FiatCurrency::Eur,
+        &PaymentMethod::BankTransfer,
+        &false,
+        &100,
+        &900,
+        &vec![&s.env, creator_order],
+    );
+    assert!(wrong_market.is_err());
+}
+
+#[test]
+fn test_take_best_orders_rejects_duplicate_and_oversized_candidate_lists() {
+    let s = setup();
+
+    let order_id = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &false,
+        &100,
+        &1000,
+        &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+
+    let duplicate = s.client.try_take_best_orders(
+        &s.filler,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &false,
+        &50,
+        &900,
+        &vec![&s.env, order_id, order_id],
+    );
+    assert_contract_error(duplicate, ContractError::DuplicateOrderId);
+
+    let mut order_ids = Vec::new(&s.env);
+    for id in 0..21u64 {
+        order_ids.push_back(id);
+    }
+    let oversized = s.client.try_take_best_orders(
+        &s.filler,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &false,
+        &50,
+        &900,
+        &order_ids,
+    );
+    assert_contract_error(oversized, ContractError::BatchTooLarge);
+    assert_eq!(
+        s.client.get_order(&order_id).status,
+        OrderStatus::AwaitingFiller
+    );
+}
+
+#[test]
+fn test_migrate_rewrites_v1_layout_idempotently() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let creator = Address::generate(&env);
+    let filler = Address::generate(&env);
+    let (token, token_admin) = create_token(&env, &admin);
+    token_admin.mint(&creator, &1_000);
+
+    let contract_id = env.register(P2PContract {}, ());
+    let client = P2PContractClient::new(&env, &contract_id);
+
+    // Seed the store exactly as a pre-versioning deployment left it.
+    env.as_contract(&contract_id, || {
+        let storage = env.storage().instance();
+        storage.set(
+            &DataKey::Config,
+            &ConfigV1 {
+                admin: admin.clone(),
+                dispute_resolver: admin.clone(),
+                pauser: admin.clone(),
+                token: token.address.clone(),
+                max_duration_secs: 2_592_000,
+                filler_payment_timeout_secs: 1_800,
+                paused: false,
+            },
+        );
+        storage.set(&DataKey::OrderCount, &1u64);
+        storage.set(
+            &DataKey::Order(0),
+            &OrderV1 {
+                order_id: 0,
+                creator: creator.clone(),
+                filler: None,
+                token: token.address.clone(),
+                amount: 500,
+                remaining_amount: 500,
+                filled_amount: 0,
+                active_fill_amount: None,
+                exchange_rate: 1000,
+                from_crypto: true,
+                fiat_currency: FiatCurrency::Ars,
+                payment_method: PaymentMethod::BankTransfer,
+                status: OrderStatus::AwaitingFiller,
+                created_at: 0,
+                deadline: 600,
+                fiat_transfer_deadline: None,
+            },
+        );
+    });
+    token.transfer(&creator, &contract_id, &500);
+    assert_eq!(client.get_schema_version(), 1);
+
+    let random = Address::generate(&env);
+    assert!(client.try_migrate(&random, &50).is_err());
+
+    assert_eq!(client.migrate(&admin, &50), CURRENT_SCHEMA_VERSION);
+    assert_eq!(client.get_schema_version(), CURRENT_SCHEMA_VERSION);
+
+    let config = client.get_config();
+    assert_eq!(config.compliance, admin);
+    assert_eq!(config.filler_payment_timeout_secs, 1_800);
+
+    let order = client.get_order(&0);
+    assert_eq!(order.amount, 500);
+    assert_eq!(order.fiat_currency, FiatCurrency::Ars);
+    assert!(!order.frozen);
+    assert_eq!(order.visibility, OrderVisibility::Public);
+    assert_eq!(order.requested_timeout_secs, None);
+    assert_eq!(order.payment_timeout_secs, None);
+
+    assert_eq!(client.get_currencies().len(), 5);
+    assert_eq!(client.get_payment_methods().len(), 3);
+
+    let report = client.check_solvency(&token.address);
+    assert_eq!(report.liabilities, 500);
+    assert_eq!(report.surplus, 0);
+
+    assert_eq!(client.migrate(&admin, &50), CURRENT_SCHEMA_VERSION);
+    assert_eq!(client.get_order(&0), order);
+
+    client.take_order(&filler, &0);
+    client.submit_fiat_payment(&filler, &0);
+    client.confirm_fiat_payment(&creator, &0);
+    assert_eq!(token.balance(&filler), 500);
+    assert_eq!(client.check_solvency(&token.address).liabilities, 0);
+}
+
+#[test]
+fn test_migrate_resumes_across_pages() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let creator = Address::generate(&env);
+    let (token, token_admin) = create_token(&env, &admin);
+    token_admin.mint(&creator, &600);
+
+    let contract_id = env.register(P2PContract {}, ());
+    let client = P2PContractClient::new(&env, &contract_id);
+
+    env.as_contract(&contract_id, || {
+        let storage = env.storage().instance();
+        storage.set(
+            &DataKey::Config,
+            &ConfigV1 {
+                admin: admin.clone(),
+                dispute_resolver: admin.clone(),
+                pauser: admin.clone(),
+                token: token.address.clone(),
+                max_duration_secs: 2_592_000,
+                filler_payment_timeout_secs: 1_800,
+                paused: false,
+            },
+        );
+        storage.set(&DataKey::OrderCount, &3u64);
+        for order_id in 0..3u64 {
+            storage.set(
+                &DataKey::Order(order_id),
+                &OrderV1 {
+                    order_id,
+                    creator: creator.clone(),
+                    filler: None,
+                    token: token.address.clone(),
+                    amount: 200,
+                    remaining_amount: 200,
+                    filled_amount: 0,
+                    active_fill_amount: None,
+                    exchange_rate: 1000,
+                    from_crypto: true,
+                    fiat_currency: FiatCurrency::Ars,
+                    payment_method: PaymentMethod::BankTransfer,
+                    status: OrderStatus::AwaitingFiller,
+                    created_at: 0,
+                    deadline: 600,
+                    fiat_transfer_deadline: None,
+                },
+            );
+        }
+    });
+    token.transfer(&creator, &contract_id, &600);
+
+    // Two of three orders: the first step is not finished yet.
+    assert_eq!(client.migrate(&admin, &2), 1);
+    assert_eq!(client.get_schema_version(), 1);
+
+    // Finishes the first step and starts totalling liabilities.
+    assert_eq!(client.migrate(&admin, &2), 2);
+    assert_eq!(client.get_schema_version(), 2);
+
+    // Totals the last two orders and runs the registry step, which has no
+    // orders to page through.
+    assert_eq!(client.migrate(&admin, &2), 4);
+    assert_eq!(client.get_schema_version(), 4);
+
+    assert_eq!(client.migrate(&admin, &2), 4);
+    assert_eq!(client.migrate(&admin, &2), CURRENT_SCHEMA_VERSION);
+    assert_eq!(client.get_schema_version(), CURRENT_SCHEMA_VERSION);
+
+    assert_eq!(client.get_config().compliance, admin);
+    assert_eq!(client.check_solvency(&token.address).liabilities, 600);
+    for order_id in 0..3u64 {
+        let order = client.get_order(&order_id);
+        assert_eq!(order.remaining_amount, 200);
+        assert_eq!(order.visibility, OrderVisibility::Public);
+    }
+    assert_eq!(client.migrate(&admin, &2), CURRENT_SCHEMA_VERSION);
+}
+
+#[test]
+fn test_upgrade_requires_admin() {
+    let s = setup();
+    let wasm_hash = BytesN::from_array(&s.env, &[7; 32]);
+
+    let unauthorized_upgrade = s.client.try_upgrade(&s.pauser, &wasm_hash);
+    assert!(unauthorized_upgrade.is_err());
+
+    assert_eq!(s.client.get_schema_version(), CURRENT_SCHEMA_VERSION);
+    assert_eq!(
+        s.client.migrate(&s.client.get_config().admin, &50),
+        CURRENT_SCHEMA_VERSION
+    );
+}
+
+#[test]
+fn test_pause_keeps_cancel_timeout_and_resolution_open() {
+    let s = setup();
+
+    set_timestamp(&s.env, 1_000);
+    let open_order = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &100,
+        &1000,
+        &5_000,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    let timed_out_order = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &false,
+        &100,
+        &1000,
+        &5_000,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    s.client.take_order(&s.filler, &timed_out_order);
+    let disputed_order = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &false,
+        &100,
+        &1000,
+        &5_000,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    s.client.take_order(&s.filler, &disputed_order);
+    s.client.submit_fiat_payment(&s.creator, &disputed_order);
+    s.client.dispute_fiat_payment(&s.creator, &disputed_order);
+
+    s.client.pause(&s.pauser);
+
+    let paused_take = s.client.try_take_order(&s.filler, &open_order);
+    assert!(paused_take.is_err());
+
+    s.client.cancel_order(&s.creator, &open_order);
+    set_timestamp(&s.env, 3_000);
+    s.client
+        .execute_fiat_transfer_timeout(&s.filler, &timed_out_order);
+    s.client
+        .resolve_dispute(&s.dispute_resolver, &disputed_order, &true);
+
+    assert_eq!(
+        s.client.get_order(&open_order).status,
+        OrderStatus::Cancelled
+    );
+    assert_eq!(
+        s.client.get_order(&timed_out_order).status,
+        OrderStatus::AwaitingFiller
+    );
+    assert_eq!(
+        s.client.get_order(&disputed_order).status,
+        OrderStatus::Completed
+    );
+    assert_eq!(s.token.balance(&s.client.address), 0);
+}
+
+#[test]
+fn test_pause_flags_pause_single_operations() {
+    let s = setup();
+
+    let order_id = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &100,
+        &1000,
+        &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+
+    let flags = PauseFlags {
+        new_orders: false,
+        takes: true,
+        settlements: false,
+        disputes: false,
+    };
+    let unauthorized = s.client.try_set_pause_flags(&s.creator, &flags);
+    assert!(unauthorized.is_err());
+
+    s.client.set_pause_flags(&s.pauser, &flags);
+    assert_eq!(s.client.get_pause_flags(), flags);
+
+    let paused_take = s.client.try_take_order(&s.filler, &order_id);
+    assert!(paused_take.is_err());
+
+    s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &100,
+        &1000,
+        &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+
+    s.client.set_pause_flags(&s.pauser, &PauseFlags::default());
+    s.client.take_order(&s.filler, &order_id);
+}
+
+#[test]
+fn test_emergency_exit_returns_deposits_after_delay() {
+    let s = setup();
+    let creator_before = s.token.balance(&s.creator);
+    let filler_before = s.token.balance(&s.filler);
+
+    set_timestamp(&s.env, 1_000);
+    let sell_order = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &400,
+        &1000,
+        &600_000,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    s.client
+        .take_order_with_amount(&s.filler, &sell_order, &150);
+
+    let buy_order = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &false,
+        &300,
+        &1000,
+        &600_000,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    s.client.take_order(&s.filler, &buy_order);
+    s.client.submit_fiat_payment(&s.creator, &buy_order);
+
+    let closed_exit = s.client.try_emergency_withdraw(&s.creator, &sell_order);
+    assert!(closed_exit.is_err());
+
+    let admin = s.client.get_config().admin;
+    assert!(s
+        .client
+        .try_set_emergency_delay(&s.pauser, &86_400)
+        .is_err());
+    s.client.set_emergency_delay(&admin, &86_400);
+    assert_last_event(
+        &s,
+        &EmergencyDelayUpdated {
+            version: EVENT_VERSION,
+            by: admin,
+            delay_secs: 86_400,
+        },
+    );
+
+    s.client.trigger_emergency(&s.pauser);
+    let emergency = s.client.get_emergency().unwrap();
+    assert_eq!(emergency.exit_at, 1_000 + 86_400);
+
+    let paused_create = s.client.try_create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &100,
+        &1000,
+        &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    assert!(paused_create.is_err());
+
+    let early_exit = s.client.try_emergency_withdraw(&s.creator, &sell_order);
+    assert!(early_exit.is_err());
+
+    set_timestamp(&s.env, 1_000 + 86_400);
+    let wrong_party = s.client.try_emergency_withdraw(&s.filler, &sell_order);
+    assert!(wrong_party.is_err());
+
+    s.client.emergency_withdraw(&s.creator, &sell_order);
+    s.client.emergency_withdraw(&s.filler, &buy_order);
+
+    assert_eq!(
+        s.client.get_order(&sell_order).status,
+        OrderStatus::Refunded
+    );
+    assert_eq!(s.client.get_order(&buy_order).status, OrderStatus::Refunded);
+    assert_eq!(s.token.balance(&s.creator), creator_before);
+    assert_eq!(s.token.balance(&s.filler), filler_before);
+    assert_eq!(s.token.balance(&s.client.address), 0);
+
+    let repeated_exit = s.client.try_emergency_withdraw(&s.creator, &sell_order);
+    assert!(repeated_exit.is_err());
+
+    s.client.cancel_emergency(&s.pauser);
+    assert_eq!(s.client.get_emergency(), None);
+}
+
+#[test]
+fn test_events_carry_version_topic() {
+    let s = setup();
+
+    s.client.pause(&s.pauser);
+
+    let expected_topics: Vec<Val> =
+        (Symbol::new(&s.env, "p2p_paused"), EVENT_VERSION).into_val(&s.env);
+    assert_eq!(
+        PausedEvt {
+            version: EVENT_VERSION,
+            by: s.pauser.clone(),
+        }
+        .topics(&s.env),
+        expected_topics
+    );
+    assert_last_event(
+        &s,
+        &PausedEvt {
+            version: EVENT_VERSION,
+            by: s.pauser.clone(),
+        },
+    );
+}
+
+#[test]
+fn test_from_crypto_lifecycle_events() {
+    let s = setup();
+    let token = s.token.address.clone();
+
+    set_timestamp(&s.env, 1_000);
+    let order_id = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &100,
+        &1200,
+        &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    assert_last_event(
+        &s,
+        &OrderCreated {
+            version: EVENT_VERSION,
+            order_id,
+            creator: s.creator.clone(),
+            token: token.clone(),
+            amount: 100,
+            deposited: 100,
+            exchange_rate: 1200,
+            from_crypto: true,
+            fiat_currency: FiatCurrency::Usd,
+            payment_method: PaymentMethod::BankTransfer,
+            deadline: 1_600,
+            status: OrderStatus::AwaitingFiller,
+            client_ref: None,
+        },
+    );
+
+    s.client.take_order_with_amount(&s.filler, &order_id, &40);
+    assert_last_event(
+        &s,
+        &OrderTaken {
+            version: EVENT_VERSION,
+            order_id,
+            creator: s.creator.clone(),
+            filler: s.filler.clone(),
+            token: token.clone(),
+            fill_amount: 40,
+            deposited: 0,
+            remaining_amount: 100,
+            fiat_transfer_deadline: 2_800,
+            status: OrderStatus::AwaitingPayment,
+        },
+    );
+
+    s.client.submit_fiat_payment(&s.filler, &order_id);
+    assert_last_event(
+        &s,
+        &FiatPaymentSubmitted {
+            version: EVENT_VERSION,
+            order_id,
+            submitted_by: s.filler.clone(),
+            creator: s.creator.clone(),
+            filler: s.filler.clone(),
+            fill_amount: 40,
+            status: OrderStatus::AwaitingConfirmation,
+        },
+    );
+
+    s.client.confirm_fiat_payment(&s.creator, &order_id);
+    assert_last_event(
+        &s,
+        &FiatPaymentConfirmed {
+            version: EVENT_VERSION,
+            order_id,
+            confirmed_by: s.creator.clone(),
+            creator: s.creator.clone(),
+            filler: s.filler.clone(),
+            token: token.clone(),
+            released_to: s.filler.clone(),
+            amount_released: 40,
+            filled_amount: 40,
+            remaining_amount: 60,
+            status: OrderStatus::AwaitingFiller,
+        },
+    );
+
+    s.client.take_order(&s.filler, &order_id);
+    set_timestamp(&s.env, 3_000);
+    s.client
+        .execute_fiat_transfer_timeout(&s.creator, &order_id);
+    assert_last_event(
+        &s,
+        &FiatTransferTimeout {
+            version: EVENT_VERSION,
+            order_id,
+            executed_by: s.creator.clone(),
+            creator: s.creator.clone(),
+            filler: s.filler.clone(),
+            token: token.clone(),
+            refunded_to: None,
+            refund_amount: 0,
+            remaining_amount: 60,
+            status: OrderStatus::AwaitingFiller,
+        },
+    );
+
+    s.client.cancel_order(&s.creator, &order_id);
+    assert_last_event(
+        &s,
+        &OrderCancelled {
+            version: EVENT_VERSION,
+            order_id,
+            cancelled_by: s.creator.clone(),
+            token,
+            refunded_to: Some(s.creator.clone()),
+            refund_amount: 60,
+            status: OrderStatus::Cancelled,
+        },
+    );
+}
+
+#[test]
+fn test_from_fiat_timeout_and_dispute_events() {
+    let s = setup();
+    let token = s.token.address.clone();
+
+    set_timestamp(&s.env, 1_000);
+    let order_id = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &false,
+        &300,
+        &1000,
+        &5_000,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    assert_last_event(
+        &s,
+        &OrderCreated {
+            version: EVENT_VERSION,
+            order_id,
+            creator: s.creator.clone(),
+            token: token.clone(),
+            amount: 300,
+            deposited: 0,
+            exchange_rate: 1000,
+            from_crypto: false,
+            fiat_currency: FiatCurrency::Usd,
+            payment_method: PaymentMethod::BankTransfer,
+            deadline: 6_000,
+            status: OrderStatus::AwaitingFiller,
+            client_ref: None,
+        },
+    );
+
+    s.client.take_order(&s.filler, &order_id);
+    assert_last_event(
+        &s,
+        &OrderTaken {
+            version: EVENT_VERSION,
+            order_id,
+            creator: s.creator.clone(),
+            filler: s.filler.clone(),
+            token: token.clone(),
+            fill_amount: 300,
+            deposited: 300,
+            remaining_amount: 300,
+            fiat_transfer_deadline: 2_800,
+            status: OrderStatus::AwaitingPayment,
+        },
+    );
+
+    set_timestamp(&s.env, 3_000);
+    s.client.execute_fiat_transfer_timeout(&s.filler, &order_id);
+    assert_last_event(
+        &s,
+        &FiatTransferTimeout {
+            version: EVENT_VERSION,
+            order_id,
+            executed_by: s.filler.clone(),
+            creator: s.creator.clone(),
+            filler: s.filler.clone(),
+            token: token.clone(),
+            refunded_to: Some(s.filler.clone()),
+            refund_amount: 300,
+            remaining_amount: 300,
+            status: OrderStatus::AwaitingFiller,
+        },
+    );
+
+    s.client.take_order(&s.filler, &order_id);
+    s.client.submit_fiat_payment(&s.creator, &order_id);
+    s.client.dispute_fiat_payment(&s.creator, &order_id);
+    assert_last_event(
+        &s,
+        &FiatPaymentDisputed {
+            version: EVENT_VERSION,
+            order_id,
+            disputed_by: s.creator.clone(),
+            creator: s.creator.clone(),
+            filler: s.filler.clone(),
+            fill_amount: 300,
+            status: OrderStatus::Disputed,
+        },
+    );
+
+    s.client
+        .resolve_dispute(&s.dispute_resolver, &order_id, &true);
+    assert_last_event(
+        &s,
+        &DisputeResolved {
+            version: EVENT_VERSION,
+            order_id,
+            resolved_by: s.dispute_resolver.clone(),
+            fiat_transfer_confirmed: true,
+            creator: s.creator.clone(),
+            filler: s.filler.clone(),
+            token,
+            paid_to: Some(s.creator.clone()),
+            amount: 300,
+            filled_amount: 300,
+            remaining_amount: 0,
+            status: OrderStatus::Completed,
+        },
+    );
+}
+
+#[test]
+fn test_liabilities_follow_deposits_and_payouts() {
+    let s = setup();
+    let token = s.token.address.clone();
+
+    set_timestamp(&s.env, 1_000);
+    let crypto_order = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &100,
+        &1000,
+        &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    let fiat_order = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &false,
+        &200,
+        &1000,
+        &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    assert_eq!(s.client.check_solvency(&token).liabilities, 100);
+
+    s.client.take_order(&s.filler, &fiat_order);
+    assert_eq!(s.client.check_solvency(&token).liabilities, 300);
+
+    s.client
+        .take_order_with_amount(&s.filler, &crypto_order, &40);
+    s.client.submit_fiat_payment(&s.filler, &crypto_order);
+    s.client.confirm_fiat_payment(&s.creator, &crypto_order);
+    assert_eq!(s.client.check_solvency(&token).liabilities, 260);
+
+    set_timestamp(&s.env, 3_000);
+    s.client
+        .execute_fiat_transfer_timeout(&s.filler, &fiat_order);
+    s.client.cancel_order(&s.creator, &crypto_order);
+
+    let report = s.client.check_solvency(&token);
+    assert_eq!(report.liabilities, 0);
+    assert_eq!(report.balance, 0);
+    assert_eq!(report.surplus, 0);
+    assert!(report.solvent);
+}
+
+#[test]
+fn test_sweep_surplus_leaves_user_funds() {
+    let s = setup();
+    let admin = s.client.get_config().admin;
+    let token = s.token.address.clone();
+    let treasury = Address::generate(&s.env);
+
+    s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &100,
+        &1000,
+        &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    assert!(s
+        .client
+        .try_sweep_surplus(&admin, &token, &treasury)
+        .is_err());
+
+    // Tokens sent straight to the contract are not owed to anyone.
+    s.token.transfer(&s.filler, &s.client.address, &25);
+    let report = s.client.check_solvency(&token);
+    assert_eq!(report.liabilities, 100);
+    assert_eq!(report.balance, 125);
+    assert_eq!(report.surplus, 25);
+
+    assert!(s
+        .client
+        .try_sweep_surplus(&s.pauser, &token, &treasury)
+        .is_err());
+    assert_eq!(s.client.sweep_surplus(&admin, &token, &treasury), 25);
+    assert_eq!(s.token.balance(&treasury), 25);
+    assert_eq!(s.token.balance(&s.client.address), 100);
+
+    let report = s.client.check_solvency(&token);
+    assert_eq!(report.surplus, 0);
+    assert!(report.solvent);
+}
+
+#[test]
+fn test_registries_list_seeded_markets() {
+    let s = setup();
+
+    let currencies = s.client.get_currencies();
+    assert_eq!(currencies.len(), 5);
+    let usd = currencies.get(0).unwrap();
+    assert_eq!(usd.currency, FiatCurrency::Usd);
+    assert_eq!(usd.iso_code, String::from_str(&s.env, "USD"));
+    assert_eq!(usd.decimals, 2);
+    assert!(usd.enabled);
+
+    let methods = s.client.get_payment_methods();
+    assert_eq!(methods.len(), 3);
+    let bank = methods.get(0).unwrap();
+    assert_eq!(bank.method, PaymentMethod::BankTransfer);
+    assert_eq!(bank.currencies.len(), 5);
+}
+
+#[test]
+fn test_create_order_requires_registered_market() {
+    let s = setup();
+    let admin = s.client.get_config().admin;
+    let brl = FiatCurrency::Other(986);
+    let pix = PaymentMethod::Other(10);
+
+    let unregistered = s.client.try_create_order(
+        &s.creator,
+        &brl,
+        &pix,
+        &true,
+        &100,
+        &1000,
+        &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    assert!(unregistered.is_err());
+    assert!(s
+        .client
+        .try_create_order_cli(&s.creator, &986, &0, &true, &100, &1000, &600)
+        .is_err());
+
+    let brl_code = String::from_str(&s.env, "BRL");
+    let pix_name = String::from_str(&s.env, "PIX");
+    assert!(s
+        .client
+        .try_set_currency(&s.pauser, &brl, &brl_code, &2, &true)
+        .is_err());
+    assert!(s
+        .client
+        .try_set_currency(&admin, &brl, &String::from_str(&s.env, "BR"), &2, &true)
+        .is_err());
+    assert!(s
+        .client
+        .try_set_payment_method(&admin, &pix, &pix_name, &vec![&s.env, brl.clone()], &true)
+        .is_err());
+
+    s.client.set_currency(&admin, &brl, &brl_code, &2, &true);
+    s.client
+        .set_payment_method(&admin, &pix, &pix_name, &vec![&s.env, brl.clone()], &true);
+    assert_eq!(s.client.get_currencies().len(), 6);
+    assert_eq!(s.client.get_payment_methods().len(), 4);
+
+    s.client.create_order(
+        &s.creator,
+        &brl,
+        &pix,
+        &true,
+        &100,
+        &1000,
+        &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+
+    // PIX only settles in BRL.
+    let wrong_currency = s.client.try_create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &pix,
+        &true,
+        &100,
+        &1000,
+        &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    assert!(wrong_currency.is_err());
+
+    s.client.set_currency(&admin, &brl, &brl_code, &2, &false);
+    assert_eq!(s.client.get_currencies().len(), 6);
+    assert!(!s.client.get_currencies().get(5).unwrap().enabled);
+    let disabled = s.client.try_create_order(
+        &s.creator,
+        &brl,
+        &pix,
+        &true,
+        &100,
+        &1000,
+        &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    assert!(disabled.is_err());
+}
+
+#[test]
+fn test_payment_timeout_prefers_most_specific_override() {
+    let s = setup();
+    let admin = s.client.get_config().admin;
+
+    let method = TimeoutScope::PaymentMethod(PaymentMethod::MobileWallet);
+    let currency = TimeoutScope::Currency(FiatCurrency::Ars);
+    let market = TimeoutScope::Market(PaymentMethod::Cash, FiatCurrency::Ars);
+
+    assert!(s
+        .client
+        .try_set_timeout_override(&s.pauser, &method, &Some(300))
+        .is_err());
+    assert!(s
+        .client
+        .try_set_timeout_override(&admin, &method, &Some(0))
+        .is_err());
+
+    s.client.set_timeout_override(&admin, &method, &Some(300));
+    s.client
+        .set_timeout_override(&admin, &currency, &Some(7_200));
+    s.client
+        .set_timeout_override(&admin, &market, &Some(10_800));
+    assert_eq!(s.client.get_timeout_override(&method), Some(300));
+
+    let timeout = |method: PaymentMethod, currency: FiatCurrency| {
+        s.client.get_payment_timeout(&method, &currency)
+    };
+    assert_eq!(
+        timeout(PaymentMethod::BankTransfer, FiatCurrency::Usd),
+        1_800
+    );
+    assert_eq!(timeout(PaymentMethod::MobileWallet, FiatCurrency::Usd), 300);
+    assert_eq!(
+        timeout(PaymentMethod::BankTransfer, FiatCurrency::Ars),
+        7_200
+    );
+    assert_eq!(timeout(PaymentMethod::MobileWallet, FiatCurrency::Ars), 300);
+    assert_eq!(timeout(PaymentMethod::Cash, FiatCurrency::Ars), 10_800);
+
+    set_timestamp(&s.env, 1_000);
+    let order_id = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Ars,
+        &PaymentMethod::Cash,
+        &true,
+        &100,
+        &1000,
+        &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    s.client.take_order(&s.filler, &order_id);
+    let order = s.client.get_order(&order_id);
+    assert_eq!(order.payment_timeout_secs, Some(10_800));
+    assert_eq!(order.fiat_transfer_deadline, Some(11_800));
+
+    s.client.set_timeout_override(&admin, &market, &None);
+    assert_eq!(s.client.get_timeout_override(&market), None);
+    assert_eq!(timeout(PaymentMethod::Cash, FiatCurrency::Ars), 7_200);
+}
+
+#[test]
+fn test_creator_selected_timeout_within_bounds() {
+    let s = setup();
+    let admin = s.client.get_config().admin;
+
+    let create = |timeout: Option<u64>| {
+        s.client.try_create_order(
+            &s.creator,
+            &FiatCurrency::Usd,
+            &PaymentMethod::Cash,
+            &true,
+            &100,
+            &1000,
+            &10_000,
+            &timeout,
+            &None,
+            &OrderVisibility::Public,
+        )
+    };
+
+    // Creators cannot pick a timeout until the admin sets bounds.
+    assert!(create(Some(3_600)).is_err());
+
+    assert!(s.client.try_set_timeout_bounds(&admin, &0, &3_600).is_err());
+    assert!(s.client.try_set_timeout_bounds(&admin, &900, &300).is_err());
+    assert!(s
+        .client
+        .try_set_timeout_bounds(&s.pauser, &300, &3_600)
+        .is_err());
+    s.client.set_timeout_bounds(&admin, &300, &3_600);
+    assert_eq!(s.client.get_timeout_bounds().unwrap().max_secs, 3_600);
+
+    assert!(create(Some(299)).is_err());
+    assert!(create(Some(3_601)).is_err());
+
+    set_timestamp(&s.env, 1_000);
+    let order_id = create(Some(3_600)).unwrap().unwrap();
+    assert_eq!(
+        s.client.get_order(&order_id).requested_timeout_secs,
+        Some(3_600)
+    );
+
+    s.client.take_order(&s.filler, &order_id);
+    let order = s.client.get_order(&order_id);
+    assert_eq!(order.payment_timeout_secs, Some(3_600));
+    assert_eq!(order.fiat_transfer_deadline, Some(4_600));
+
+    set_timestamp(&s.env, 2_801);
+    assert!(s
+        .client
+        .try_execute_fiat_transfer_timeout(&s.creator, &order_id)
+        .is_err());
+
+    set_timestamp(&s.env, 4_601);
+    s.client
+        .execute_fiat_transfer_timeout(&s.creator, &order_id);
+    let order = s.client.get_order(&order_id);
+    assert_eq!(order.payment_timeout_secs, None);
+    assert_eq!(order.requested_timeout_secs, Some(3_600));
+}
+
+#[test]
+fn test_migrate_records_timeout_for_active_fills() {
+    let s = setup();
+    let admin = s.client.get_config().admin;
+
+    // Seed a version 4 store holding an order with an active fill.
+    s.env.as_contract(&s.client.address, || {
+        let storage = s.env.storage().instance();
+        storage.set(&DataKey::SchemaVersion, &4u32);
+        storage.set(&DataKey::OrderCount, &1u64);
+        storage.set(
+            &DataKey::Order(0),
+            &OrderV2 {
+                order_id: 0,
+                creator: s.creator.clone(),
+                filler: Some(s.filler.clone()),
+                token: s.token.address.clone(),
+                amount: 100,
+                remaining_amount: 100,
+                filled_amount: 0,
+                active_fill_amount: Some(100),
+                exchange_rate: 1000,
+                from_crypto: true,
+                fiat_currency: FiatCurrency::Usd,
+                payment_method: PaymentMethod::BankTransfer,
+                status: OrderStatus::AwaitingPayment,
+                created_at: 0,
+                deadline: 600,
+                fiat_transfer_deadline: Some(1_800),
+                frozen: false,
+                visibility: OrderVisibility::Public,
+            },
+        );
+    });
+
+    assert_eq!(s.client.migrate(&admin, &50), CURRENT_SCHEMA_VERSION);
+    let order = s.client.get_order(&0);
+    assert_eq!(order.payment_timeout_secs, Some(1_800));
+    assert_eq!(order.requested_timeout_secs, None);
+    assert_eq!(order.fiat_transfer_deadline, Some(1_800));
+}
+
+/// Stands in for the escrow factory, which deploys from uploaded wasm:
+/// hands out natively registered escrow instances in order instead.
+#[contract]
+struct MockEscrowFactory;
+
+#[contractimpl]
+impl MockEscrowFactory {
+    pub fn add_escrow(e: Env, escrow: Address) {
+        let key = Symbol::new(&e, "escrows");
+        let mut escrows: Vec<Address> = e.storage().instance().get(&key).unwrap_or(vec![&e]);
+        escrows.push_back(escrow);
+        e.storage().instance().set(&key, &escrows);
+    }
+
+    pub fn tw_new_single_release_escrow(
+        e: Env,
+        deployer: Address,
+        _wasm_hash: BytesN<32>,
+        _salt: BytesN<32>,
+        init_fn: Symbol,
+        init_args: Vec<Val>,
+        _constructor_args: Vec<Val>,
+    ) -> (Address, Val) {
+        deployer.require_auth();
+        let key = Symbol::new(&e, "escrows");
+        let mut escrows: Vec<Address> = e.storage().instance().get(&key).unwrap();
+        let escrow = escrows.pop_front().unwrap();
+        e.storage().instance().set(&key, &escrows);
+
+        let res: Val = e.invoke_contract(&escrow, &init_fn, init_args);
+        (escrow, res)
+    }
+}
+
+/// Enables escrow settlement with room for `fills` escrows. Returns the
+/// Trustless Work fee address and the escrows in the order they are used.
+fn enable_escrow_settlement(s: &Setup, fills: u32) -> (Address, std::vec::Vec<Address>) {
+    let factory = MockEscrowFactoryClient::new(&s.env, &s.env.register(MockEscrowFactory, ()));
+    let mut escrows = std::vec::Vec::new();
+    for _ in 0..fills {
+        let escrow = s.env.register(escrow::EscrowContract, ());
+        factory.add_escrow(&escrow);
+        escrows.push(escrow);
+    }
+
+    let trustless_work = Address::generate(&s.env);
+    let settings = EscrowSettings {
+        factory: factory.address,
+        wasm_hash: BytesN::from_array(&s.env, &[0; 32]),
+        trustless_work: trustless_work.clone(),
+    };
+    let admin = s.client.get_config().admin;
+    assert!(s
+        .client
+        .try_set_escrow_settings(&s.pauser, &Some(settings.clone()))
+        .is_err());
+    s.client
+        .set_escrow_settings(&admin, &Some(settings.clone()));
+    assert_eq!(s.client.get_escrow_settings(), Some(settings));
+
+    (trustless_work, escrows)
+}
+
+fn get_escrow(s: &Setup, escrow: &Address) -> Escrow {
+    s.env
+        .invoke_contract(escrow, &Symbol::new(&s.env, "get_escrow"), vec![&s.env])
+}
+
+#[test]
+fn test_escrow_settlement_releases_fill_to_filler() {
+    let s = setup();
+    let (trustless_work, escrows) = enable_escrow_settlement(&s, 1);
+
+    set_timestamp(&s.env, 1_000);
+    let order_id = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &10_000,
+        &1000,
+        &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    s.client
+        .take_order_with_amount(&s.filler, &order_id, &4_000);
+
+    assert_last_event(
+        &s,
+        &FillEscrowOpened {
+            version: EVENT_VERSION,
+            order_id,
+            escrow: escrows[0].clone(),
+            amount: 4_000,
+        },
+    );
+    let fill_escrow = s.client.get_fill_escrow(&order_id).unwrap();
+    assert_eq!(fill_escrow.escrow, escrows[0]);
+    assert_eq!(fill_escrow.trustless_work, trustless_work);
+
+    let escrow = get_escrow(&s, &fill_escrow.escrow);
+    assert_eq!(escrow.amount, 4_000);
+    assert_eq!(escrow.roles.approver, s.client.address);
+    assert_eq!(escrow.roles.service_provider, s.filler);
+    assert_eq!(escrow.roles.receiver, s.filler);
+    assert_eq!(escrow.roles.dispute_resolver, s.dispute_resolver);
+    assert_eq!(escrow.roles.release_signer, s.client.address);
+    assert_eq!(s.token.balance(&fill_escrow.escrow), 4_000);
+    assert_eq!(s.token.balance(&s.client.address), 6_000);
+    assert!(s.client.check_solvency(&s.token.address).solvent);
+    assert_eq!(s.client.check_solvency(&s.token.address).liabilities, 6_000);
+
+    s.client.submit_fiat_payment(&s.filler, &order_id);
+    let escrow = get_escrow(&s, &fill_escrow.escrow);
+    assert!(!escrow.milestones.get(0).unwrap().status.is_empty());
+
+    let filler_before = s.token.balance(&s.filler);
+    s.client.confirm_fiat_payment(&s.creator, &order_id);
+
+    // Trustless Work keeps 30 bps of the release.
+    assert_eq!(s.token.balance(&s.filler), filler_before + 3_988);
+    assert_eq!(s.token.balance(&trustless_work), 12);
+    assert_eq!(s.token.balance(&fill_escrow.escrow), 0);
+    assert_eq!(s.client.get_fill_escrow(&order_id), None);
+
+    let order = s.client.get_order(&order_id);
+    assert_eq!(order.status, OrderStatus::AwaitingFiller);
+    assert_eq!(order.filled_amount, 4_000);
+    assert_eq!(order.remaining_amount, 6_000);
+    assert_eq!(s.token.balance(&s.client.address), 6_000);
+    assert_eq!(s.client.check_solvency(&s.token.address).liabilities, 6_000);
+}
+
+#[test]
+fn test_escrow_settlement_timeout_and_disputes_go_to_resolver() {
+    let s = setup();
+    let (trustless_work, _) = enable_escrow_settlement(&s, 2);
+
+    set_timestamp(&s.env, 1_000);
+    let order_id = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &10_000,
+        &1000,
+        &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    s.client
+        .take_order_with_amount(&s.filler, &order_id, &4_000);
+    let fill_escrow = s.client.get_fill_escrow(&order_id).unwrap();
+
+    // An expired escrowed fill cannot be refunded without the resolver.
+    set_timestamp(&s.env, 3_000);
+    s.client
+        .execute_fiat_transfer_timeout(&s.creator, &order_id);
+    assert_eq!(s.client.get_order(&order_id).status, OrderStatus::Disputed);
+    assert!(get_escrow(&s, &fill_escrow.escrow).flags.disputed);
+
+    s.client.trigger_emergency(&s.pauser);
+    set_timestamp(&s.env, 100_000);
+    assert!(s
+        .client
+        .try_emergency_withdraw(&s.creator, &order_id)
+        .is_err());
+    s.client.cancel_emergency(&s.pauser);
+
+    // The returned fill lands back in the creator's deposit, less the fee.
+    s.client
+        .resolve_dispute(&s.dispute_resolver, &order_id, &false);
+    let order = s.client.get_order(&order_id);
+    assert_eq!(order.status, OrderStatus::AwaitingFiller);
+    assert_eq!(order.amount, 9_988);
+    assert_eq!(order.remaining_amount, 9_988);
+    assert_eq!(s.token.balance(&trustless_work), 12);
+    assert_eq!(s.token.balance(&s.client.address), 9_988);
+    let report = s.client.check_solvency(&s.token.address);
+    assert_eq!(report.liabilities, 9_988);
+    assert_eq!(report.surplus, 0);
+
+    // A fiat-side fill is funded by the filler straight into its escrow.
+    let order_id = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &false,
+        &5_000,
+        &1000,
+        &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    s.client.take_order(&s.filler, &order_id);
+    let fill_escrow = s.client.get_fill_escrow(&order_id).unwrap();
+    assert_eq!(s.token.balance(&fill_escrow.escrow), 5_000);
+    assert_eq!(s.token.balance(&s.client.address), 9_988);
+
+    s.client.submit_fiat_payment(&s.creator, &order_id);
+    s.client.dispute_fiat_payment(&s.creator, &order_id);
+    assert!(get_escrow(&s, &fill_escrow.escrow).flags.disputed);
+
+    let creator_before = s.token.balance(&s.creator);
+    s.client
+        .resolve_dispute(&s.dispute_resolver, &order_id, &true);
+    assert_eq!(s.token.balance(&s.creator), creator_before + 4_985);
+    assert_eq!(s.token.balance(&trustless_work), 27);
+    assert_eq!(s.client.get_order(&order_id).status, OrderStatus::Completed);
+    assert_eq!(s.client.get_fill_escrow(&order_id), None);
+    assert_eq!(s.client.check_solvency(&s.token.address).liabilities, 9_988);
+}
+
+#[test]
+fn test_create_order_with_client_ref_is_idempotent() {
+    let s = setup();
+    let client_ref = BytesN::from_array(&s.env, &[7; 32]);
+    let create = |creator: &Address, amount: i128| {
+        s.client.create_order(
+            creator,
+            &FiatCurrency::Usd,
+            &PaymentMethod::BankTransfer,
+            &true,
+            &amount,
+            &1000,
+            &600,
+            &None,
+            &Some(client_ref.clone()),
+            &OrderVisibility::Public,
+        )
+    };
+
+    set_timestamp(&s.env, 1_000);
+    let order_id = create(&s.creator, 100);
+    assert_last_event(
+        &s,
+        &OrderCreated {
+            version: EVENT_VERSION,
+            order_id,
+            creator: s.creator.clone(),
+            token: s.token.address.clone(),
+            amount: 100,
+            deposited: 100,
+            exchange_rate: 1000,
+            from_crypto: true,
+            fiat_currency: FiatCurrency::Usd,
+            payment_method: PaymentMethod::BankTransfer,
+            deadline: 1_600,
+            status: OrderStatus::AwaitingFiller,
+            client_ref: Some(client_ref.clone()),
+        },
+    );
+
+    // A retry returns the first order without taking a second deposit.
+    assert_eq!(create(&s.creator, 100), order_id);
+    assert_eq!(s.client.get_order_count(), 1);
+    assert_eq!(s.token.balance(&s.client.address), 100);
+    assert_eq!(
+        s.client.get_order_by_ref(&s.creator, &client_ref).order_id,
+        order_id
+    );
+
+    // References are scoped to the creator.
+    let other_id = create(&s.filler, 50);
+    assert_ne!(other_id, order_id);
+    assert_eq!(s.client.get_order_by_ref(&s.filler, &client_ref).amount, 50);
+    assert!(s
+        .client
+        .try_get_order_by_ref(&s.creator, &BytesN::from_array(&s.env, &[8; 32]))
+        .is_err());
+}
+
+#[test]
+fn test_client_ref_is_not_reserved_by_a_failed_create() {
+    let s = setup();
+    let client_ref = BytesN::from_array(&s.env, &[7; 32]);
+    let create = |amount: i128| {
+        s.client.try_create_order(
+            &s.creator,
+            &FiatCurrency::Usd,
+            &PaymentMethod::BankTransfer,
+            &true,
+            &amount,
+            &1000,
+            &600,
+            &None,
+            &Some(client_ref.clone()),
+            &OrderVisibility::Public,
+        )
+    };
+
+    assert!(create(0).is_err());
+    assert!(s
+        .client
+        .try_get_order_by_ref(&s.creator, &client_ref)
+        .is_err());
+
+    // A blocked creator cannot create under a new reference either.
+    s.client.block_address(&s.compliance, &s.creator);
+    assert!(create(100).is_err());
+    s.client.unblock_address(&s.compliance, &s.creator);
+
+    let order_id = create(100).unwrap().unwrap();
+    assert_eq!(s.client.get_order_count(), 1);
+
+    // A retry after the order was cancelled still resolves to it rather
+    // than opening a second order.
+    s.client.cancel_order(&s.creator, &order_id);
+    let creator_before = s.token.balance(&s.creator);
+    assert_eq!(create(100).unwrap().unwrap(), order_id);
+    assert_eq!(s.client.get_order(&order_id).status, OrderStatus::Cancelled);
+    assert_eq!(s.token.balance(&s.creator), creator_before);
+}
+
+#[test]
+fn test_archive_order_compacts_finished_orders() {
+    let s = setup();
+    let admin = s.client.get_config().admin;
+
+    set_timestamp(&s.env, 1_000);
+    let create = |client_ref: Option<BytesN<32>>| {
+        s.client.create_order(
+            &s.creator,
+            &FiatCurrency::Usd,
+            &PaymentMethod::BankTransfer,
+            &true,
+            &100,
+            &1000,
+            &600,
+            &None,
+            &client_ref,
+            &OrderVisibility::Public,
+        )
+    };
+    let client_ref = BytesN::from_array(&s.env, &[1; 32]);
+    let completed_id = create(Some(client_ref.clone()));
+    let open_id = create(None);
+
+    s.client
+        .take_order_with_amount(&s.filler, &completed_id, &100);
+    assert!(s.client.try_archive_order(&completed_id).is_err());
+    s.client.submit_fiat_payment(&s.filler, &completed_id);
+    s.client.confirm_fiat_payment(&s.creator, &completed_id);
+    let finished = s.client.get_order(&completed_id);
+
+    assert!(s.client.try_archive_order(&open_id).is_err());
+    assert!(s.client.try_set_archive_retention(&admin, &0).is_err());
+    assert!(s
+        .client
+        .try_set_archive_retention(&s.pauser, &1_000)
+        .is_err());
+    s.client.set_archive_retention(&admin, &1_000);
+    assert_last_event(
+        &s,
+        &ArchiveRetentionUpdated {
+            version: EVENT_VERSION,
+            by: admin.clone(),
+            ledgers: 1_000,
+        },
+    );
+    assert_eq!(s.client.get_archive_retention(), 1_000);
+
+    set_timestamp(&s.env, 2_000);
+    s.client.archive_order(&completed_id);
+    let summary = s.client.get_order_summary(&completed_id);
+    assert_eq!(summary.status, OrderStatus::Completed);
+    assert_eq!(summary.filled_amount, 100);
+    assert_eq!(summary.archived_at, 2_000);
+    assert!(s.env.as_contract(&s.client.address, || {
+        !s.env
+            .storage()
+            .instance()
+            .has(&DataKey::Order(completed_id))
+    }));
+
+    // Views read through to the summary.
+    assert_eq!(s.client.get_order(&completed_id), finished);
+    assert_eq!(
+        s.client.get_order_by_ref(&s.creator, &client_ref).order_id,
+        completed_id
+    );
+    assert!(s.client.try_archive_order(&completed_id).is_err());
+    assert!(s
+        .client
+        .try_cancel_order(&s.creator, &completed_id)
+        .is_err());
+
+    s.client.cancel_order(&s.creator, &open_id);
+    s.client.archive_order(&open_id);
+    assert_eq!(s.client.get_order(&open_id).status, OrderStatus::Cancelled);
+
+    // Summaries expire with the retention period.
+    s.env.ledger().with_mut(|ledger| {
+        ledger.sequence_number += 1_001;
+    });
+    assert!(s.client.try_get_order(&completed_id).is_err());
+}
+
+#[test]
+fn test_archive_order_drops_every_per_order_instance_entry() {
+    let s = setup();
+    set_timestamp(&s.env, 1_000);
+    let instance_keys = |order_id: u64| {
+        s.env.as_contract(&s.client.address, || {
+            [
+                DataKey::Order(order_id),
+                DataKey::FillRate(order_id),
+                DataKey::FillExtension(order_id),
+                DataKey::FillEscrow(order_id),
+                DataKey::AdOrder(order_id),
+                DataKey::SettlementRelease(order_id),
+                DataKey::OrderOffers(order_id),
+            ]
+            .iter()
+            .filter(|key| s.env.storage().instance().has(*key))
+            .count()
+        })
+    };
+
+    // An ad fill carries its ad link and fill rate.
+    let ad_id = s.client.create_ad(
+        &s.creator,
+        &AdParams {
+            from_crypto: true,
+            fiat_currency: FiatCurrency::Usd,
+            payment_methods: vec![&s.env, PaymentMethod::BankTransfer],
+            exchange_rate: 1_000,
+            inventory: 1_000,
+            min_fill: 100,
+            max_fill: 400,
+            active_from_secs: 0,
+            active_to_secs: 0,
+            max_open_disputes: 0,
+        },
+    );
+    let ad_fill = s
+        .client
+        .take_ad(&s.filler, &ad_id, &300, &PaymentMethod::BankTransfer);
+    s.client.submit_fiat_payment(&s.filler, &ad_fill);
+    s.client.confirm_fiat_payment(&s.creator, &ad_fill);
+    assert!(instance_keys(ad_fill) > 1);
+    s.client.archive_order(&ad_fill);
+    assert_eq!(instance_keys(ad_fill), 0);
+
+    // Offers still open on a cancelled order are refunded on archiving.
+    let order_id = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &false,
+        &1_000,
+        &1_000,
+        &2_592_000,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    let filler_before = s.token.balance(&s.filler);
+    let offer_id = s
+        .client
+        .make_offer(&s.filler, &order_id, &200, &1_050, &600);
+    s.client.cancel_order(&s.creator, &order_id);
+    s.client.archive_order(&order_id);
+    assert_eq!(instance_keys(order_id), 0);
+    assert_eq!(s.token.balance(&s.filler), filler_before);
+    assert_eq!(s.client.get_offer(&offer_id).status, OfferStatus::Refunded);
+    assert_eq!(s.client.get_order_offers(&order_id).len(), 0);
+}
+
+#[test]
+fn test_order_fills_record_each_settlement() {
+    let s = setup();
+
+    set_timestamp(&s.env, 1_000);
+    let order_id = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &300,
+        &1200,
+        &6_000,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    let other = Address::generate(&s.env);
+
+    s.client.take_order_with_amount(&s.filler, &order_id, &100);
+    set_timestamp(&s.env, 1_100);
+    s.client.submit_fiat_payment(&s.filler, &order_id);
+    set_timestamp(&s.env, 1_200);
+    s.client.confirm_fiat_payment(&s.creator, &order_id);
+
+    s.client.take_order_with_amount(&other, &order_id, &50);
+    set_timestamp(&s.env, 5_000);
+    s.client
+        .execute_fiat_transfer_timeout(&s.creator, &order_id);
+
+    s.client.take_order_with_amount(&s.filler, &order_id, &200);
+    s.client.submit_fiat_payment(&s.filler, &order_id);
+    s.client.dispute_fiat_payment(&s.filler, &order_id);
+    let fills = s.client.get_order_fills(&order_id);
+    assert_eq!(fills.len(), 3);
+    assert_eq!(fills.get(2).unwrap().outcome, FillOutcome::Pending);
+
+    set_timestamp(&s.env, 5_500);
+    s.client
+        .resolve_dispute(&s.dispute_resolver, &order_id, &true);
+
+    let fills = s.client.get_order_fills(&order_id);
+    assert_eq!(
+        fills,
+        vec![
+            &s.env,
+            FillRecord {
+                filler: s.filler.clone(),
+                amount: 100,
+                exchange_rate: 1200,
+                taken_at: 1_000,
+                submitted_at: Some(1_100),
+                settled_at: Some(1_200),
+                deadline_extended_secs: 0,
+                outcome: FillOutcome::Confirmed,
+            },
+            FillRecord {
+                filler: other,
+                amount: 50,
+                exchange_rate: 1200,
+                taken_at: 1_200,
+                submitted_at: None,
+                settled_at: Some(5_000),
+                deadline_extended_secs: 0,
+                outcome: FillOutcome::TimedOut,
+            },
+            FillRecord {
+                filler: s.filler.clone(),
+                amount: 200,
+                exchange_rate: 1200,
+                taken_at: 5_000,
+                submitted_at: Some(5_000),
+                settled_at: Some(5_500),
+                deadline_extended_secs: 0,
+                outcome: FillOutcome::DisputeWon,
+            },
+        ]
+    );
+
+    // The history outlives the order itself.
+    s.client.archive_order(&order_id);
+    assert_eq!(s.client.get_order_fills(&order_id), fills);
+    assert!(s.client.get_order_fills(&99).is_empty());
+}
+
+#[test]
+fn test_order_fills_ignore_rejected_actions() {
+    let s = setup();
+    set_timestamp(&s.env, 1_000);
+    let order_id = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &300,
+        &1200,
+        &6_000,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+
+    assert!(s.client.try_take_order(&s.creator, &order_id).is_err());
+    assert!(s
+        .client
+        .try_take_order_with_amount(&s.filler, &order_id, &301)
+        .is_err());
+    assert!(s.client.get_order_fills(&order_id).is_empty());
+
+    s.client.take_order_with_amount(&s.filler, &order_id, &100);
+    assert!(s
+        .client
+        .try_submit_fiat_payment(&s.creator, &order_id)
+        .is_err());
+    assert!(s
+        .client
+        .try_confirm_fiat_payment(&s.filler, &order_id)
+        .is_err());
+    assert!(s
+        .client
+        .try_execute_fiat_transfer_timeout(&s.creator, &order_id)
+        .is_err());
+
+    let fills = s.client.get_order_fills(&order_id);
+    assert_eq!(fills.len(), 1);
+    let fill = fills.get(0).unwrap();
+    assert_eq!(fill.submitted_at, None);
+    assert_eq!(fill.settled_at, None);
+    assert_eq!(fill.outcome, FillOutcome::Pending);
+}
+
+#[test]
+fn test_market_stats_aggregate_settled_fills() {
+    let s = setup();
+    assert_eq!(
+        s.client
+            .get_market_stats(&s.token.address, &FiatCurrency::Usd),
+        None
+    );
+
+    set_timestamp(&s.env, 7_200);
+    let create = |rate: i128| {
+        s.client.create_order(
+            &s.creator,
+            &FiatCurrency::Usd,
+            &PaymentMethod::BankTransfer,
+            &true,
+            &10_000,
+            &rate,
+            &2_592_000,
+            &None,
+            &None,
+            &OrderVisibility::Public,
+        )
+    };
+    let settle = |order_id: u64, amount: i128| {
+        s.client
+            .take_order_with_amount(&s.filler, &order_id, &amount);
+        s.client.submit_fiat_payment(&s.filler, &order_id);
+        s.client.confirm_fiat_payment(&s.creator, &order_id);
+    };
+    let cheap = create(1_000);
+    let dear = create(1_300);
+
+    settle(cheap, 300);
+    set_timestamp(&s.env, 7_500);
+    settle(dear, 100);
+
+    // A rejected fill is not a trade.
+    s.client.take_order_with_amount(&s.filler, &cheap, &500);
+    s.client.submit_fiat_payment(&s.filler, &cheap);
+    s.client.dispute_fiat_payment(&s.filler, &cheap);
+    s.client
+        .resolve_dispute(&s.dispute_resolver, &cheap, &false);
+
+    let stats = s
+        .client
+        .get_market_stats(&s.token.address, &FiatCurrency::Usd)
+        .unwrap();
+    assert_eq!(stats.volume, 400);
+    assert_eq!(stats.trade_count, 2);
+    assert_eq!(stats.last_rate, 1_300);
+    assert_eq!(stats.last_trade_at, 7_500);
+    assert_eq!(
+        stats.buckets,
+        vec![
+            &s.env,
+            VolumeBucket {
+                start: 7_200,
+                volume: 400,
+                notional: 430_000,
+                vwap_rate: 1_075,
+            },
+        ]
+    );
+    assert_eq!(
+        s.client
+            .get_market_stats(&s.token.address, &FiatCurrency::Eur),
+        None
+    );
+
+    // Only the most recent day of hourly buckets is kept.
+    for hour in 3..30u64 {
+        set_timestamp(&s.env, hour * 3_600);
+        settle(cheap, 10);
+    }
+    let stats = s
+        .client
+        .get_market_stats(&s.token.address, &FiatCurrency::Usd)
+        .unwrap();
+    assert_eq!(stats.trade_count, 29);
+    assert_eq!(stats.volume, 670);
+    assert_eq!(stats.buckets.len(), 24);
+    assert_eq!(stats.buckets.get(0).unwrap().start, 6 * 3_600);
+    assert_eq!(stats.buckets.last().unwrap().vwap_rate, 1_000);
+}
+
+#[test]
+fn test_market_stats_skip_fills_that_do_not_settle() {
+    let s = setup();
+    set_timestamp(&s.env, 1_000);
+    let order_id = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &1_000,
+        &1_000,
+        &2_592_000,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+
+    // Rejected confirmations, timeouts and cancellations are not trades.
+    s.client.take_order_with_amount(&s.filler, &order_id, &100);
+    assert!(s
+        .client
+        .try_confirm_fiat_payment(&s.creator, &order_id)
+        .is_err());
+    set_timestamp(&s.env, 10_000);
+    s.client
+        .execute_fiat_transfer_timeout(&s.creator, &order_id);
+    s.client.cancel_order(&s.creator, &order_id);
+    assert_eq!(
+        s.client
+            .get_market_stats(&s.token.address, &FiatCurrency::Usd),
+        None
+    );
+}
+
+#[test]
+fn test_fees_accrue_referral_share_to_referrer() {
+    let s = setup();
+    let admin = s.client.get_config().admin;
+    let fee_recipient = Address::generate(&s.env);
+    let referrer = Address::generate(&s.env);
+
+    let fee_config = FeeConfig {
+        fee_bps: 100,
+        referral_share_bps: 5_000,
+        fee_recipient: fee_recipient.clone(),
+    };
+    assert!(s
+        .client
+        .try_set_fee_config(&s.creator, &fee_config)
+        .is_err());
+    assert!(s
+        .client
+        .try_set_fee_config(
+            &admin,
+            &FeeConfig {
+                fee_bps: 1_001,
+                ..fee_config.clone()
+            }
+        )
+        .is_err());
+    s.client.set_fee_config(&admin, &fee_config);
+    assert_eq!(s.client.get_fee_config(), Some(fee_config));
+
+    assert!(s
+        .client
+        .try_register_referrer(&s.filler, &s.filler)
+        .is_err());
+    s.client.register_referrer(&s.filler, &referrer);
+    assert!(s
+        .client
+        .try_register_referrer(&s.filler, &s.creator)
+        .is_err());
+    assert_eq!(s.client.get_referrer(&s.filler), Some(referrer.clone()));
+
+    let order_id = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &10_000,
+        &1_000,
+        &2_592_000,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    let filler_before = s.token.balance(&s.filler);
+    s.client
+        .take_order_with_amount(&s.filler, &order_id, &1_000);
+    s.client.submit_fiat_payment(&s.filler, &order_id);
+    s.client.confirm_fiat_payment(&s.creator, &order_id);
+    assert_last_event(
+        &s,
+        &FeeCharged {
+            version: EVENT_VERSION,
+            order_id,
+            payer: s.filler.clone(),
+            fee: 10,
+            referrer: Some(referrer.clone()),
+            referral_amount: 5,
+        },
+    );
+    assert_eq!(s.token.balance(&s.filler) - filler_before, 990);
+    assert_eq!(s.token.balance(&fee_recipient), 5);
+
+    // A fill won in a dispute pays the fee too; the creator has no referrer.
+    let fiat_order = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &false,
+        &2_000,
+        &1_000,
+        &2_592_000,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    s.client.take_order(&s.filler, &fiat_order);
+    s.client.submit_fiat_payment(&s.creator, &fiat_order);
+    s.client.dispute_fiat_payment(&s.creator, &fiat_order);
+    s.client
+        .resolve_dispute(&s.dispute_resolver, &fiat_order, &true);
+    assert_eq!(s.token.balance(&fee_recipient), 25);
+
+    let stats = s.client.get_referral_stats(&referrer);
+    assert_eq!(stats.referred_count, 1);
+    assert_eq!(stats.total_earned, 5);
+    assert_eq!(stats.claimable, 5);
+
+    assert_eq!(s.client.claim_referral_rewards(&referrer), 5);
+    assert_eq!(s.token.balance(&referrer), 5);
+    assert_eq!(s.client.get_referral_stats(&referrer).claimable, 0);
+    assert!(s.client.try_claim_referral_rewards(&referrer).is_err());
+
+    let report = s.client.check_solvency(&s.token.address);
+    assert_eq!(report.liabilities, 9_000);
+}
+
+#[test]
+fn test_escrow_fills_pay_the_protocol_fee_on_release() {
+    let s = setup();
+    let admin = s.client.get_config().admin;
+    let fee_recipient = Address::generate(&s.env);
+    s.client.set_fee_config(
+        &admin,
+        &FeeConfig {
+            fee_bps: 100,
+            referral_share_bps: 0,
+            fee_recipient: fee_recipient.clone(),
+        },
+    );
+    let (trustless_work, _) = enable_escrow_settlement(&s, 2);
+
+    set_timestamp(&s.env, 1_000);
+    let create = |from_crypto: bool| {
+        s.client.create_order(
+            &s.creator,
+            &FiatCurrency::Usd,
+            &PaymentMethod::BankTransfer,
+            &from_crypto,
+            &10_000,
+            &1000,
+            &600,
+            &None,
+            &None,
+            &OrderVisibility::Public,
+        )
+    };
+
+    let sell_order = create(true);
+    s.client
+        .take_order_with_amount(&s.filler, &sell_order, &4_000);
+    let fill_escrow = s.client.get_fill_escrow(&sell_order).unwrap();
+    assert_eq!(get_escrow(&s, &fill_escrow.escrow).platform_fee, 100);
+    s.client.submit_fiat_payment(&s.filler, &sell_order);
+    let filler_before = s.token.balance(&s.filler);
+    s.client.confirm_fiat_payment(&s.creator, &sell_order);
+
+    assert_last_event(
+        &s,
+        &FeeCharged {
+            version: EVENT_VERSION,
+            order_id: sell_order,
+            payer: s.filler.clone(),
+            fee: 40,
+            referrer: None,
+            referral_amount: 0,
+        },
+    );
+    assert_eq!(s.token.balance(&s.filler), filler_before + 3_948);
+    assert_eq!(s.token.balance(&fee_recipient), 40);
+    assert_eq!(s.token.balance(&trustless_work), 12);
+    assert_eq!(s.client.check_solvency(&s.token.address).liabilities, 6_000);
+    assert!(s.client.check_solvency(&s.token.address).solvent);
+
+    // A refunded fill gets the platform fee back.
+    let buy_order = create(false);
+    s.client
+        .take_order_with_amount(&s.filler, &buy_order, &2_000);
+    s.client.submit_fiat_payment(&s.creator, &buy_order);
+    s.client.dispute_fiat_payment(&s.creator, &buy_order);
+    let filler_before = s.token.balance(&s.filler);
+    s.client
+        .resolve_dispute(&s.dispute_resolver, &buy_order, &false);
+    assert_eq!(s.token.balance(&s.filler), filler_before + 1_994);
+    assert_eq!(s.token.balance(&fee_recipient), 40);
+    assert_eq!(s.client.check_solvency(&s.token.address).liabilities, 6_000);
+}
+
+#[test]
+fn test_blocked_traders_cannot_register_or_claim_referrals() {
+    let s = setup();
+    let admin = s.client.get_config().admin;
+    let referrer = Address::generate(&s.env);
+    s.client.set_fee_config(
+        &admin,
+        &FeeConfig {
+            fee_bps: 100,
+            referral_share_bps: 5_000,
+            fee_recipient: Address::generate(&s.env),
+        },
+    );
+
+    s.client.block_address(&s.compliance, &s.filler);
+    assert!(s
+        .client
+        .try_register_referrer(&s.filler, &referrer)
+        .is_err());
+    s.client.unblock_address(&s.compliance, &s.filler);
+    s.client.register_referrer(&s.filler, &referrer);
+
+    let order_id = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &1_000,
+        &1000,
+        &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    s.client.take_order(&s.filler, &order_id);
+    s.client.submit_fiat_payment(&s.filler, &order_id);
+    s.client.confirm_fiat_payment(&s.creator, &order_id);
+
+    s.client.block_address(&s.compliance, &referrer);
+    assert!(s.client.try_claim_referral_rewards(&referrer).is_err());
+    s.client.unblock_address(&s.compliance, &referrer);
+    assert_eq!(s.client.claim_referral_rewards(&referrer), 5);
+}
+
+#[test]
+fn test_merchant_ad_draws_fills_from_inventory_and_pauses_itself() {
+    let s = setup();
+    set_timestamp(&s.env, 1_000);
+    let params = AdParams {
+        from_crypto: true,
+        fiat_currency: FiatCurrency::Usd,
+        payment_methods: vec![&s.env, PaymentMethod::BankTransfer],
+        exchange_rate: 1_000,
+        inventory: 1_000,
+        min_fill: 100,
+        max_fill: 400,
+        active_from_secs: 0,
+        active_to_secs: 0,
+        max_open_disputes: 0,
+    };
+    let creator_before = s.token.balance(&s.creator);
+    let ad_id = s.client.create_ad(&s.creator, &params);
+    assert_eq!(s.token.balance(&s.creator), creator_before - 1_000);
+
+    let take = |amount: i128| {
+        s.client
+            .try_take_ad(&s.filler, &ad_id, &amount, &PaymentMethod::BankTransfer)
+    };
+    assert!(take(50).is_err());
+    assert!(take(500).is_err());
+    assert!(s
+        .client
+        .try_take_ad(&s.filler, &ad_id, &100, &PaymentMethod::MobileWallet)
+        .is_err());
+    assert!(s
+        .client
+        .try_take_ad(&s.creator, &ad_id, &100, &PaymentMethod::BankTransfer)
+        .is_err());
+
+    // Each fill is its own order and settles through the usual lifecycle.
+    let confirmed = take(400).unwrap().unwrap();
+    assert_event_published(
+        &s,
+        &OrderCreated {
+            version: EVENT_VERSION,
+            order_id: confirmed,
+            creator: s.creator.clone(),
+            token: s.token.address.clone(),
+            amount: 400,
+            deposited: 0,
+            exchange_rate: 1_000,
+            from_crypto: true,
+            fiat_currency: FiatCurrency::Usd,
+            payment_method: PaymentMethod::BankTransfer,
+            deadline: 1_000 + 1_800,
+            status: OrderStatus::AwaitingFiller,
+            client_ref: None,
+        },
+    );
+    assert_eq!(s.client.get_order_ad(&confirmed), Some(ad_id));
+    let order = s.client.get_order(&confirmed);
+    assert_eq!(order.creator, s.creator);
+    assert_eq!(order.amount, 400);
+    assert_eq!(order.status, OrderStatus::AwaitingPayment);
+    s.client.submit_fiat_payment(&s.filler, &confirmed);
+    s.client.confirm_fiat_payment(&s.creator, &confirmed);
+    assert_eq!(
+        s.client.get_order(&confirmed).status,
+        OrderStatus::Completed
+    );
+
+    let timed_out = take(400).unwrap().unwrap();
+    let disputed = take(200).unwrap().unwrap();
+    let ad = s.client.get_ad(&ad_id);
+    assert_eq!(ad.inventory, 0);
+    assert_eq!(ad.status, AdStatus::OutOfInventory);
+    assert!(take(100).is_err());
+
+    // A fill that times out goes back to the inventory.
+    set_timestamp(&s.env, 3_000);
+    s.client
+        .execute_fiat_transfer_timeout(&s.creator, &timed_out);
+    assert_eq!(
+        s.client.get_order(&timed_out).status,
+        OrderStatus::Cancelled
+    );
+    let ad = s.client.get_ad(&ad_id);
+    assert_eq!(ad.inventory, 400);
+    assert_eq!(ad.status, AdStatus::Active);
+
+    // Passing the dispute limit pauses the ad until the merchant resumes it.
+    s.client.submit_fiat_payment(&s.filler, &disputed);
+    s.client.dispute_fiat_payment(&s.filler, &disputed);
+    assert_eq!(s.client.get_merchant_open_disputes(&s.creator), 1);
+    assert_eq!(s.client.get_ad(&ad_id).status, AdStatus::TooManyDisputes);
+    assert!(take(100).is_err());
+    assert_contract_error(
+        s.client.try_resume_ad(&s.creator, &ad_id),
+        ContractError::TooManyOpenDisputes,
+    );
+
+    s.client
+        .resolve_dispute(&s.dispute_resolver, &disputed, &false);
+    assert_eq!(s.client.get_merchant_open_disputes(&s.creator), 0);
+    assert_eq!(s.client.get_ad(&ad_id).inventory, 600);
+    assert!(s.client.try_resume_ad(&s.filler, &ad_id).is_err());
+    s.client.resume_ad(&s.creator, &ad_id);
+    assert_eq!(s.client.get_ad(&ad_id).status, AdStatus::Active);
+
+    // Takes only land within the ad's active hours.
+    let office_hours = s.client.create_ad(
+        &s.creator,
+        &AdParams {
+            from_crypto: false,
+            active_from_secs: 9 * 3_600,
+            active_to_secs: 17 * 3_600,
+            ..params.clone()
+        },
+    );
+    set_timestamp(&s.env, 86_400 + 20 * 3_600);
+    assert!(s
+        .client
+        .try_take_ad(&s.filler, &office_hours, &100, &PaymentMethod::BankTransfer)
+        .is_err());
+    set_timestamp(&s.env, 86_400 + 10 * 3_600);
+    s.client
+        .take_ad(&s.filler, &office_hours, &100, &PaymentMethod::BankTransfer);
+    assert_eq!(s.client.get_merchant_ads(&s.creator).len(), 2);
+
+    let creator_before = s.token.balance(&s.creator);
+    s.client.close_ad(&s.creator, &ad_id);
+    assert_eq!(s.token.balance(&s.creator), creator_before + 600);
+    assert_eq!(s.client.get_ad(&ad_id).status, AdStatus::Closed);
+    assert_eq!(s.client.get_merchant_ads(&s.creator).len(), 1);
+    assert!(take(100).is_err());
+    assert_eq!(s.client.check_solvency(&s.token.address).liabilities, 100);
+}
+
+#[test]
+fn test_merchant_ad_rejections() {
+    let s = setup();
+    set_timestamp(&s.env, 1_000);
+    let params = AdParams {
+        from_crypto: true,
+        fiat_currency: FiatCurrency::Usd,
+        payment_methods: vec![&s.env, PaymentMethod::BankTransfer],
+        exchange_rate: 1_000,
+        inventory: 1_000,
+        min_fill: 100,
+        max_fill: 400,
+        active_from_secs: 9 * 3_600,
+        active_to_secs: 17 * 3_600,
+        max_open_disputes: 0,
+    };
+    assert_contract_error(s.client.try_get_ad(&0), ContractError::AdNotFound);
+
+    let mut methods = Vec::new(&s.env);
+    for _ in 0..6 {
+        methods.push_back(PaymentMethod::BankTransfer);
+    }
+    assert_contract_error(
+        s.client.try_create_ad(
+            &s.creator,
+            &AdParams {
+                payment_methods: methods,
+                ..params.clone()
+            },
+        ),
+        ContractError::TooManyPaymentMethods,
+    );
+
+    let ad_id = s.client.create_ad(&s.creator, &params);
+    assert_contract_error(
+        s.client.try_restock_ad(&s.filler, &ad_id, &100),
+        ContractError::Unauthorized,
+    );
+    assert_contract_error(
+        s.client.try_pause_ad(&s.filler, &ad_id),
+        ContractError::Unauthorized,
+    );
+    assert_contract_error(
+        s.client.try_close_ad(&s.filler, &ad_id),
+        ContractError::Unauthorized,
+    );
+
+    // 1_000 is 00:16 UTC, outside the ad's hours.
+    assert_contract_error(
+        s.client
+            .try_take_ad(&s.filler, &ad_id, &100, &PaymentMethod::BankTransfer),
+        ContractError::AdOutsideActiveHours,
+    );
+
+    s.client.pause_ad(&s.creator, &ad_id);
+    assert_contract_error(
+        s.client.try_pause_ad(&s.creator, &ad_id),
+        ContractError::AdNotActive,
+    );
+    set_timestamp(&s.env, 10 * 3_600);
+    assert_contract_error(
+        s.client
+            .try_take_ad(&s.filler, &ad_id, &100, &PaymentMethod::BankTransfer),
+        ContractError::AdNotActive,
+    );
+    s.client.resume_ad(&s.creator, &ad_id);
+
+    // Draining the inventory below a minimum fill keeps the ad from resuming.
+    s.client
+        .take_ad(&s.filler, &ad_id, &400, &PaymentMethod::BankTransfer);
+    s.client
+        .take_ad(&s.filler, &ad_id, &400, &PaymentMethod::BankTransfer);
+    s.client
+        .take_ad(&s.filler, &ad_id, &150, &PaymentMethod::BankTransfer);
+    assert_eq!(s.client.get_ad(&ad_id).status, AdStatus::OutOfInventory);
+    assert_contract_error(
+        s.client.try_resume_ad(&s.creator, &ad_id),
+        ContractError::AdOutOfInventory,
+    );
+
+    s.client.close_ad(&s.creator, &ad_id);
+    assert_contract_error(
+        s.client.try_restock_ad(&s.creator, &ad_id, &100),
+        ContractError::AdClosed,
+    );
+    assert_contract_error(
+        s.client.try_resume_ad(&s.creator, &ad_id),
+        ContractError::AdClosed,
+    );
+    assert_contract_error(
+        s.client.try_close_ad(&s.creator, &ad_id),
+        ContractError::AdClosed,
+    );
+
+    for _ in 0..10 {
+        s.client.create_ad(&s.creator, &params);
+    }
+    assert_contract_error(
+        s.client.try_create_ad(&s.creator, &params),
+        ContractError::TooManyAds,
+    );
+}
+
+#[test]
+fn test_counter_offer_fills_at_negotiated_rate() {
+    let s = setup();
+    set_timestamp(&s.env, 1_000);
+    let order_id = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &false,
+        &1_000,
+        &1_000,
+        &2_592_000,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+
+    assert!(s
+        .client
+        .try_make_offer(&s.creator, &order_id, &100, &1_050, &600)
+        .is_err());
+    assert!(s
+        .client
+        .try_make_offer(&s.filler, &order_id, &1_001, &1_050, &600)
+        .is_err());
+
+    // The filler sells crypto on this order, so offers carry a deposit.
+    let filler_before = s.token.balance(&s.filler);
+    let accepted = s
+        .client
+        .make_offer(&s.filler, &order_id, &600, &1_050, &600);
+    let rejected = s
+        .client
+        .make_offer(&s.filler, &order_id, &200, &1_100, &600);
+    let expiring = s.client.make_offer(&s.filler, &order_id, &100, &1_020, &60);
+    assert_eq!(s.token.balance(&s.filler), filler_before - 900);
+    assert_eq!(s.client.get_order_offers(&order_id).len(), 3);
+
+    s.client.reject_offer(&s.creator, &rejected);
+    assert_eq!(s.token.balance(&s.filler), filler_before - 700);
+    assert_eq!(s.client.get_offer(&rejected).status, OfferStatus::Rejected);
+
+    assert!(s.client.try_refund_offer(&expiring).is_err());
+    set_timestamp(&s.env, 1_060);
+    s.client.refund_offer(&expiring);
+    assert_eq!(s.token.balance(&s.filler), filler_before - 600);
+
+    assert!(s.client.try_accept_offer(&s.filler, &accepted).is_err());
+    s.client.accept_offer(&s.creator, &accepted);
+    // The deposit moved at make_offer, so the take itself deposits nothing.
+    assert_event_published(
+        &s,
+        &OrderTaken {
+            version: EVENT_VERSION,
+            order_id,
+            creator: s.creator.clone(),
+            filler: s.filler.clone(),
+            token: s.token.address.clone(),
+            fill_amount: 600,
+            deposited: 0,
+            remaining_amount: 1_000,
+            fiat_transfer_deadline: 1_060 + 1_800,
+            status: OrderStatus::AwaitingPayment,
+        },
+    );
+    assert!(s.client.get_order_offers(&order_id).is_empty());
+    let order = s.client.get_order(&order_id);
+    assert_eq!(order.status, OrderStatus::AwaitingPayment);
+    assert_eq!(order.filler, Some(s.filler.clone()));
+    assert_eq!(order.active_fill_amount, Some(600));
+    assert_eq!(order.exchange_rate, 1_000);
+    assert_eq!(s.token.balance(&s.filler), filler_before - 600);
+    assert_eq!(
+        s.client
+            .get_order_fills(&order_id)
+            .get(0)
+            .unwrap()
+            .exchange_rate,
+        1_050
+    );
+    assert!(s.client.try_accept_offer(&s.creator, &accepted).is_err());
+
+    s.client.submit_fiat_payment(&s.creator, &order_id);
+    s.client.confirm_fiat_payment(&s.filler, &order_id);
+    let stats = s
+        .client
+        .get_market_stats(&s.token.address, &FiatCurrency::Usd)
+        .unwrap();
+    assert_eq!(stats.last_rate, 1_050);
+    assert_eq!(s.client.check_solvency(&s.token.address).liabilities, 0);
+
+    // Offers on a cancelled order are refundable right away.
+    let stranded = s
+        .client
+        .make_offer(&s.filler, &order_id, &100, &1_000, &600);
+    s.client.cancel_order(&s.creator, &order_id);
+    s.client.refund_offer(&stranded);
+    assert_eq!(s.client.get_offer(&stranded).status, OfferStatus::Refunded);
+    assert_eq!(s.client.check_solvency(&s.token.address).liabilities, 0);
+}
+
+#[test]
+fn test_open_offers_are_capped_per_order_and_per_taker() {
+    let s = setup();
+    set_timestamp(&s.env, 1_000);
+    let order_id = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &1_000,
+        &1_000,
+        &2_592_000,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    assert_contract_error(s.client.try_get_offer(&0), ContractError::OfferNotFound);
+
+    // Offers on an order selling crypto carry no deposit, so each taker
+    // only holds a few of the order's slots.
+    for _ in 0..3 {
+        s.client.make_offer(&s.filler, &order_id, &100, &1_050, &60);
+    }
+    assert_contract_error(
+        s.client
+            .try_make_offer(&s.filler, &order_id, &100, &1_050, &60),
+        ContractError::TooManyOffersFromTaker,
+    );
+
+    let mut stale = std::vec::Vec::new();
+    for _ in 0..17 {
+        let taker = Address::generate(&s.env);
+        stale.push(s.client.make_offer(&taker, &order_id, &100, &1_050, &600));
+    }
+    assert_eq!(s.client.get_order_offers(&order_id).len(), 20);
+    let late = Address::generate(&s.env);
+    assert_contract_error(
+        s.client
+            .try_make_offer(&late, &order_id, &100, &1_050, &600),
+        ContractError::TooManyOffers,
+    );
+
+    // Once some expire, a full order refunds them to make room.
+    set_timestamp(&s.env, 1_060);
+    let offer_id = s.client.make_offer(&late, &order_id, &100, &1_050, &600);
+    assert_eq!(s.client.get_order_offers(&order_id).len(), 18);
+    assert_eq!(s.client.get_offer(&0).status, OfferStatus::Refunded);
+    assert_contract_error(
+        s.client.try_accept_offer(&s.creator, &0),
+        ContractError::OfferNotOpen,
+    );
+
+    // The creator can always clear a slot by rejecting an offer.
+    let next = Address::generate(&s.env);
+    s.client.make_offer(&next, &order_id, &100, &1_050, &600);
+    s.client.make_offer(&next, &order_id, &100, &1_050, &600);
+    assert_contract_error(
+        s.client
+            .try_make_offer(&late, &order_id, &100, &1_050, &600),
+        ContractError::TooManyOffers,
+    );
+    s.client.reject_offer(&s.creator, &stale[0]);
+    s.client.make_offer(&late, &order_id, &100, &1_050, &600);
+
+    assert_contract_error(
+        s.client.try_reject_offer(&s.filler, &offer_id),
+        ContractError::Unauthorized,
+    );
+    assert_contract_error(
+        s.client.try_refund_offer(&offer_id),
+        ContractError::OfferStillOpen,
+    );
+    assert_contract_error(
+        s.client.try_reject_offer(&s.creator, &stale[0]),
+        ContractError::OfferNotOpen,
+    );
+    set_timestamp(&s.env, 1_660);
+    assert_contract_error(
+        s.client.try_accept_offer(&s.creator, &offer_id),
+        ContractError::OfferExpired,
+    );
+    s.client.refund_offer(&offer_id);
+}
+
+#[test]
+fn test_extend_fiat_deadline_within_configured_total() {
+    let s = setup();
+    set_timestamp(&s.env, 1_000);
+    let order_id = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &false,
+        &1_000,
+        &1_000,
+        &2_592_000,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    s.client.take_order(&s.filler, &order_id);
+    assert_eq!(
+        s.client.get_order(&order_id).fiat_transfer_deadline,
+        Some(2_800)
+    );
+
+    // Extensions are off until the admin sets a maximum.
+    assert!(s
+        .client
+        .try_extend_fiat_deadline(&s.filler, &order_id, &4_000)
+        .is_err());
+    let admin = s.client.get_config().admin;
+    assert!(s
+        .client
+        .try_set_max_fiat_extension(&s.filler, &3_600)
+        .is_err());
+    s.client.set_max_fiat_extension(&admin, &3_600);
+
+    // Only the filler, who could claim the timeout, can grant more time.
+    assert!(s
+        .client
+        .try_extend_fiat_deadline(&s.creator, &order_id, &4_000)
+        .is_err());
+    s.client.extend_fiat_deadline(&s.filler, &order_id, &4_000);
+    assert_last_event(
+        &s,
+        &FiatDeadlineExtended {
+            version: EVENT_VERSION,
+            order_id,
+            extended_by: s.filler.clone(),
+            previous_deadline: 2_800,
+            new_deadline: 4_000,
+        },
+    );
+
+    set_timestamp(&s.env, 3_000);
+    assert!(s
+        .client
+        .try_execute_fiat_transfer_timeout(&s.filler, &order_id)
+        .is_err());
+    assert!(s
+        .client
+        .try_extend_fiat_deadline(&s.filler, &order_id, &3_500)
+        .is_err());
+    assert!(s
+        .client
+        .try_extend_fiat_deadline(&s.filler, &order_id, &6_401)
+        .is_err());
+    s.client.extend_fiat_deadline(&s.filler, &order_id, &6_400);
+
+    let fill = s.client.get_order_fills(&order_id).get(0).unwrap();
+    assert_eq!(fill.deadline_extended_secs, 3_600);
+
+    set_timestamp(&s.env, 6_401);
+    s.client.execute_fiat_transfer_timeout(&s.filler, &order_id);
+    assert_eq!(
+        s.client.get_order_fills(&order_id).get(0).unwrap().outcome,
+        FillOutcome::TimedOut
+    );
+
+    // The next fill starts with a fresh allowance.
+    s.client.take_order(&s.filler, &order_id);
+    s.client
+        .extend_fiat_deadline(&s.filler, &order_id, &(6_401 + 1_800 + 3_600));
+}
+
+#[test]
+fn test_extend_fiat_deadline_rejections() {
+    let s = setup();
+    set_timestamp(&s.env, 1_000);
+    let order_id = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &1_000,
+        &1_000,
+        &2_592_000,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    s.client.take_order(&s.filler, &order_id);
+
+    // MaxFiatExtension is 0 until the admin sets it.
+    assert_contract_error(
+        s.client
+            .try_extend_fiat_deadline(&s.creator, &order_id, &4_000),
+        ContractError::FiatExtensionDisabled,
+    );
+    let admin = s.client.get_config().admin;
+    s.client.set_max_fiat_extension(&admin, &600);
+
+    // On an order selling crypto the creator would claim the timeout, so
+    // neither the filler nor an outsider can extend.
+    assert_contract_error(
+        s.client
+            .try_extend_fiat_deadline(&s.filler, &order_id, &3_000),
+        ContractError::Unauthorized,
+    );
+    let outsider = Address::generate(&s.env);
+    assert_contract_error(
+        s.client
+            .try_extend_fiat_deadline(&outsider, &order_id, &3_000),
+        ContractError::Unauthorized,
+    );
+
+    assert_contract_error(
+        s.client
+            .try_extend_fiat_deadline(&s.creator, &order_id, &2_800),
+        ContractError::InvalidTimeout,
+    );
+    assert_contract_error(
+        s.client
+            .try_extend_fiat_deadline(&s.creator, &order_id, &3_401),
+        ContractError::FiatExtensionExceedsLimit,
+    );
+    s.client.extend_fiat_deadline(&s.creator, &order_id, &3_100);
+    assert_contract_error(
+        s.client
+            .try_extend_fiat_deadline(&s.creator, &order_id, &3_401),
+        ContractError::FiatExtensionExceedsLimit,
+    );
+
+    // Once the timeout has run there is no fill left to extend.
+    set_timestamp(&s.env, 3_101);
+    s.client
+        .execute_fiat_transfer_timeout(&s.creator, &order_id);
+    assert_contract_error(
+        s.client
+            .try_extend_fiat_deadline(&s.creator, &order_id, &4_000),
+        ContractError::InvalidOrderStatus,
+    );
+}
+
+#[test]
+fn test_settlement_hold_delays_release_and_allows_chargeback_dispute() {
+    let s = setup();
+    set_timestamp(&s.env, 1_000);
+    let admin = s.client.get_config().admin;
+    assert!(s
+        .client
+        .try_set_settlement_hold(&admin, &PaymentMethod::BankTransfer, &Some(0))
+        .is_err());
+    s.client
+        .set_settlement_hold(&admin, &PaymentMethod::BankTransfer, &Some(86_400));
+    assert_eq!(
+        s.client.get_settlement_hold(&PaymentMethod::BankTransfer),
+        Some(86_400)
+    );
+
+    let order_id = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &1_000,
+        &1_000,
+        &2_592_000,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    let filler_before = s.token.balance(&s.filler);
+    s.client.take_order_with_amount(&s.filler, &order_id, &400);
+    s.client.submit_fiat_payment(&s.filler, &order_id);
+    s.client.confirm_fiat_payment(&s.creator, &order_id);
+    assert_last_event(
+        &s,
+        &SettlementHeld {
+            version: EVENT_VERSION,
+            order_id,
+            confirmed_by: s.creator.clone(),
+            filler: s.filler.clone(),
+            amount: 400,
+            release_at: 87_400,
+        },
+    );
+    assert_eq!(s.client.get_order(&order_id).status, OrderStatus::Settling);
+    assert_eq!(s.token.balance(&s.filler), filler_before);
+    assert_eq!(
+        s.client.get_order_fills(&order_id).get(0).unwrap().outcome,
+        FillOutcome::Pending
+    );
+
+    assert!(s.client.try_finalize_settlement(&order_id).is_err());
+    set_timestamp(&s.env, 87_400);
+    s.client.finalize_settlement(&order_id);
+    let order = s.client.get_order(&order_id);
+    assert_eq!(order.status, OrderStatus::AwaitingFiller);
+    assert_eq!(order.filled_amount, 400);
+    assert_eq!(s.token.balance(&s.filler), filler_before + 400);
+    assert_eq!(s.client.get_settlement_release(&order_id), None);
+
+    // During the hold the fiat receiver can dispute a reversed payment.
+    s.client.take_order_with_amount(&s.filler, &order_id, &300);
+    s.client.submit_fiat_payment(&s.filler, &order_id);
+    s.client.confirm_fiat_payment(&s.creator, &order_id);
+    assert!(s
+        .client
+        .try_dispute_fiat_payment(&s.filler, &order_id)
+        .is_err());
+    s.client.dispute_fiat_payment(&s.creator, &order_id);
+    assert_eq!(s.client.get_order(&order_id).status, OrderStatus::Disputed);
+    set_timestamp(&s.env, 200_000);
+    assert!(s.client.try_finalize_settlement(&order_id).is_err());
+    s.client
+        .resolve_dispute(&s.dispute_resolver, &order_id, &false);
+    let order = s.client.get_order(&order_id);
+    assert_eq!(order.remaining_amount, 600);
+    assert_eq!(s.token.balance(&s.filler), filler_before + 400);
+
+    // Without a hold, confirmations release straight away again.
+    s.client
+        .set_settlement_hold(&admin, &PaymentMethod::BankTransfer, &None);
+    s.client.take_order_with_amount(&s.filler, &order_id, &100);
+    s.client.submit_fiat_payment(&s.filler, &order_id);
+    s.client.confirm_fiat_payment(&s.creator, &order_id);
+    assert_eq!(s.token.balance(&s.filler), filler_before + 500);
+}
+
+#[test]
+fn test_emergency_during_settlement_hold_releases_to_buyer() {
+    let s = setup();
+    set_timestamp(&s.env, 1_000);
+    let admin = s.client.get_config().admin;
+    s.client
+        .set_settlement_hold(&admin, &PaymentMethod::BankTransfer, &Some(86_400));
+    s.client.set_emergency_delay(&admin, &3_600);
+
+    let order_id = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &1_000,
+        &1_000,
+        &2_592_000,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    let creator_before = s.token.balance(&s.creator);
+    let filler_before = s.token.balance(&s.filler);
+    s.client.take_order_with_amount(&s.filler, &order_id, &400);
+    s.client.submit_fiat_payment(&s.filler, &order_id);
+    s.client.confirm_fiat_payment(&s.creator, &order_id);
+    assert_eq!(s.client.get_order(&order_id).status, OrderStatus::Settling);
+
+    // The creator already confirmed the fiat, so the held fill is not theirs
+    // to take back.
+    s.client.trigger_emergency(&s.pauser);
+    set_timestamp(&s.env, 1_000 + 3_600);
+    assert_contract_error(
+        s.client.try_emergency_withdraw(&s.creator, &order_id),
+        ContractError::InvalidOrderStatus,
+    );
+
+    set_timestamp(&s.env, 87_400);
+    s.client.finalize_settlement(&order_id);
+    assert_eq!(s.token.balance(&s.filler), filler_before + 400);
+
+    s.client.emergency_withdraw(&s.creator, &order_id);
+    assert_eq!(s.token.balance(&s.creator), creator_before + 600);
+    assert_eq!(s.client.get_order(&order_id).status, OrderStatus::Refunded);
+    assert_eq!(s.client.check_solvency(&s.token.address).liabilities, 0);
+}
+
+#[test]
+fn test_settlement_hold_rejections() {
+    let s = setup();
+    set_timestamp(&s.env, 1_000);
+    let admin = s.client.get_config().admin;
+    assert_contract_error(
+        s.client
+            .try_set_settlement_hold(&s.creator, &PaymentMethod::BankTransfer, &Some(60)),
+        ContractError::Unauthorized,
+    );
+    assert_contract_error(
+        s.client
+            .try_set_settlement_hold(&admin, &PaymentMethod::BankTransfer, &Some(0)),
+        ContractError::InvalidSettlementHold,
+    );
+    assert_contract_error(
+        s.client
+            .try_set_settlement_hold(&admin, &PaymentMethod::BankTransfer, &Some(2_592_001)),
+        ContractError::InvalidSettlementHold,
+    );
+    s.client
+        .set_settlement_hold(&admin, &PaymentMethod::BankTransfer, &Some(600));
+
+    let order_id = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &1_000,
+        &1_000,
+        &2_592_000,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    s.client.take_order(&s.filler, &order_id);
+    assert_contract_error(
+        s.client.try_finalize_settlement(&order_id),
+        ContractError::InvalidOrderStatus,
+    );
+    s.client.submit_fiat_payment(&s.filler, &order_id);
+    s.client.confirm_fiat_payment(&s.creator, &order_id);
+
+    assert_contract_error(
+        s.client.try_finalize_settlement(&order_id),
+        ContractError::SettlementHoldActive,
+    );
+    set_timestamp(&s.env, 1_599);
+    assert_contract_error(
+        s.client.try_finalize_settlement(&order_id),
+        ContractError::SettlementHoldActive,
+    );
+
+    // A held fill is already confirmed.
+    assert_contract_error(
+        s.client.try_confirm_fiat_payment(&s.creator, &order_id),
+        ContractError::InvalidOrderStatus,
+    );
+    set_timestamp(&s.env, 1_600);
+    s.client.finalize_settlement(&order_id);
+    assert_contract_error(
+        s.client.try_finalize_settlement(&order_id),
+        ContractError::InvalidOrderStatus,
+    );
+}
+
+#[test]
+fn test_insurance_pool_pays_claims_approved_by_threshold() {
+    let s = setup();
+    let admin = s.client.get_config().admin;
+    let fee_recipient = Address::generate(&s.env);
+    let approver_a = Address::generate(&s.env);
+    let approver_b = Address::generate(&s.env);
+    let outsider = Address::generate(&s.env);
+
+    s.client.set_fee_config(
+        &admin,
+        &FeeConfig {
+            fee_bps: 1_000,
+            referral_share_bps: 0,
+            fee_recipient: fee_recipient.clone(),
+        },
+    );
+    let insurance_config = InsuranceConfig {
+        fee_share_bps: 5_000,
+        approvers: vec![&s.env, approver_a.clone(), approver_b.clone()],
+        threshold: 2,
+        max_claim: 40,
+    };
+    assert!(s
+        .client
+        .try_set_insurance_config(
+            &admin,
+            &InsuranceConfig {
+                threshold: 3,
+                ..insurance_config.clone()
+            }
+        )
+        .is_err());
+    assert!(s
+        .client
+        .try_set_insurance_config(&s.creator, &insurance_config)
+        .is_err());
+    s.client.set_insurance_config(&admin, &insurance_config);
+
+    let order_id = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &1_000,
+        &1_000,
+        &2_592_000,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    s.client.take_order(&s.filler, &order_id);
+    s.client.submit_fiat_payment(&s.filler, &order_id);
+    s.client.confirm_fiat_payment(&s.creator, &order_id);
+    assert_last_event(
+        &s,
+        &InsuranceFunded {
+            version: EVENT_VERSION,
+            order_id,
+            token: s.token.address.clone(),
+            amount: 50,
+            pool_balance: 50,
+        },
+    );
+    assert_eq!(s.token.balance(&fee_recipient), 50);
+    assert_eq!(s.client.get_insurance_pool(&s.token.address), 50);
+    assert_eq!(s.client.check_solvency(&s.token.address).surplus, 0);
+
+    // Only the order's traders can claim, up to the per-claim cap.
+    assert!(s
+        .client
+        .try_file_insurance_claim(&outsider, &order_id, &10)
+        .is_err());
+    assert!(s
+        .client
+        .try_file_insurance_claim(&s.creator, &order_id, &41)
+        .is_err());
+    let claim = s.client.file_insurance_claim(&s.creator, &order_id, &40);
+    assert!(s
+        .client
+        .try_file_insurance_claim(&s.creator, &order_id, &10)
+        .is_err());
+
+    assert!(s
+        .client
+        .try_approve_insurance_claim(&outsider, &claim.claim_id)
+        .is_err());
+    let claim = s
+        .client
+        .approve_insurance_claim(&approver_a, &claim.claim_id);
+    assert_eq!(claim.status, ClaimStatus::Pending);
+    assert!(s
+        .client
+        .try_approve_insurance_claim(&approver_a, &claim.claim_id)
+        .is_err());
+
+    let creator_before = s.token.balance(&s.creator);
+    let claim = s
+        .client
+        .approve_insurance_claim(&approver_b, &claim.claim_id);
+    assert_last_event(
+        &s,
+        &InsuranceClaimClosed {
+            version: EVENT_VERSION,
+            order_id,
+            claim_id: claim.claim_id,
+            claimant: s.creator.clone(),
+            amount_paid: 40,
+            status: ClaimStatus::Paid,
+            pool_balance: 10,
+        },
+    );
+    assert_eq!(s.token.balance(&s.creator) - creator_before, 40);
+    assert_eq!(s.client.get_insurance_pool(&s.token.address), 10);
+
+    // A claim the pool cannot cover stays pending until rejected.
+    let claim = s.client.file_insurance_claim(&s.filler, &order_id, &30);
+    s.client
+        .approve_insurance_claim(&approver_a, &claim.claim_id);
+    assert!(s
+        .client
+        .try_approve_insurance_claim(&approver_b, &claim.claim_id)
+        .is_err());
+    s.client
+        .reject_insurance_claim(&approver_b, &claim.claim_id);
+    assert_eq!(
+        s.client.get_insurance_claim(&claim.claim_id).status,
+        ClaimStatus::Rejected
+    );
+    assert_eq!(s.client.get_order_claims(&order_id).len(), 2);
+    assert_eq!(s.client.get_insurance_pool(&s.token.address), 10);
+}
+
+#[test]
+fn test_insurance_approvals_from_removed_approvers_do_not_count() {
+    let s = setup();
+    let admin = s.client.get_config().admin;
+    let approver_a = Address::generate(&s.env);
+    let approver_b = Address::generate(&s.env);
+    let approver_c = Address::generate(&s.env);
+
+    s.client.set_fee_config(
+        &admin,
+        &FeeConfig {
+            fee_bps: 1_000,
+            referral_share_bps: 0,
+            fee_recipient: Address::generate(&s.env),
+        },
+    );
+    let insurance_config = InsuranceConfig {
+        fee_share_bps: 5_000,
+        approvers: vec![&s.env, approver_a.clone(), approver_b.clone()],
+        threshold: 2,
+        max_claim: 40,
+    };
+    s.client.set_insurance_config(&admin, &insurance_config);
+
+    let order_id = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &1_000,
+        &1_000,
+        &2_592_000,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    s.client.take_order(&s.filler, &order_id);
+    s.client.submit_fiat_payment(&s.filler, &order_id);
+    s.client.confirm_fiat_payment(&s.creator, &order_id);
+
+    let claim = s.client.file_insurance_claim(&s.creator, &order_id, &40);
+    s.client
+        .approve_insurance_claim(&approver_a, &claim.claim_id);
+
+    // Replacing approver A drops its approval from the count.
+    s.client.set_insurance_config(
+        &admin,
+        &InsuranceConfig {
+            approvers: vec![&s.env, approver_b.clone(), approver_c.clone()],
+            ..insurance_config
+        },
+    );
+    let claim = s
+        .client
+        .approve_insurance_claim(&approver_b, &claim.claim_id);
+    assert_eq!(claim.status, ClaimStatus::Pending);
+    assert_eq!(s.client.get_insurance_pool(&s.token.address), 50);
+
+    let creator_before = s.token.balance(&s.creator);
+    let claim = s
+        .client
+        .approve_insurance_claim(&approver_c, &claim.claim_id);
+    assert_eq!(claim.status, ClaimStatus::Paid);
+    assert_eq!(s.token.balance(&s.creator) - creator_before, 40);
+}
+
+#[test]
+fn test_insurance_claim_rejections() {
+    let s = setup();
+    let admin = s.client.get_config().admin;
+    let approver_a = Address::generate(&s.env);
+    let approver_b = Address::generate(&s.env);
+
+    let order_id = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &1_000,
+        &1_000,
+        &2_592_000,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    assert_contract_error(
+        s.client.try_file_insurance_claim(&s.creator, &order_id, &1),
+        ContractError::InsuranceNotConfigured,
+    );
+
+    let insurance_config = InsuranceConfig {
+        fee_share_bps: 4_500,
+        approvers: vec![&s.env, approver_a.clone(), approver_b.clone()],
+        threshold: 2,
+        max_claim: 10,
+    };
+    for invalid in [
+        InsuranceConfig {
+            fee_share_bps: 10_001,
+            ..insurance_config.clone()
+        },
+        InsuranceConfig {
+            threshold: 0,
+            ..insurance_config.clone()
+        },
+        InsuranceConfig {
+            approvers: vec![&s.env, approver_a.clone(), approver_a.clone()],
+            ..insurance_config.clone()
+        },
+        InsuranceConfig {
+            max_claim: 0,
+            ..insurance_config.clone()
+        },
+    ] {
+        assert_contract_error(
+            s.client.try_set_insurance_config(&admin, &invalid),
+            ContractError::InvalidInsuranceConfig,
+        );
+    }
+    s.client.set_insurance_config(&admin, &insurance_config);
+    s.client.set_fee_config(
+        &admin,
+        &FeeConfig {
+            fee_bps: 1_000,
+            referral_share_bps: 0,
+            fee_recipient: admin.clone(),
+        },
+    );
+    s.client.take_order(&s.filler, &order_id);
+    s.client.submit_fiat_payment(&s.filler, &order_id);
+    s.client.confirm_fiat_payment(&s.creator, &order_id);
+    assert_eq!(s.client.get_insurance_pool(&s.token.address), 45);
+
+    assert_contract_error(
+        s.client.try_get_insurance_claim(&0),
+        ContractError::ClaimNotFound,
+    );
+    assert_contract_error(
+        s.client.try_file_insurance_claim(&s.creator, &order_id, &0),
+        ContractError::InvalidAmount,
+    );
+    assert_contract_error(
+        s.client
+            .try_file_insurance_claim(&s.creator, &order_id, &11),
+        ContractError::ClaimTooLarge,
+    );
+    let claim = s.client.file_insurance_claim(&s.creator, &order_id, &10);
+    assert_contract_error(
+        s.client
+            .try_file_insurance_claim(&s.creator, &order_id, &10),
+        ContractError::ClaimAlreadyPending,
+    );
+    assert_contract_error(
+        s.client
+            .try_approve_insurance_claim(&s.filler, &claim.claim_id),
+        ContractError::Unauthorized,
+    );
+    assert_contract_error(
+        s.client
+            .try_reject_insurance_claim(&s.filler, &claim.claim_id),
+        ContractError::Unauthorized,
+    );
+    s.client
+        .approve_insurance_claim(&approver_a, &claim.claim_id);
+    assert_contract_error(
+        s.client
+            .try_approve_insurance_claim(&approver_a, &claim.claim_id),
+        ContractError::ClaimAlreadyApproved,
+    );
+    s.client
+        .approve_insurance_claim(&approver_b, &claim.claim_id);
+    assert_contract_error(
+        s.client
+            .try_reject_insurance_claim(&approver_a, &claim.claim_id),
+        ContractError::ClaimNotPending,
+    );
+
+    // Rejected claims do not use up the order's claims.
+    for _ in 0..6 {
+        let claim = s.client.file_insurance_claim(&s.filler, &order_id, &1);
+        s.client
+            .reject_insurance_claim(&approver_a, &claim.claim_id);
+    }
+    let pay = |amount: i128| {
+        let claim = s.client.file_insurance_claim(&s.filler, &order_id, &amount);
+        s.client
+            .approve_insurance_claim(&approver_a, &claim.claim_id);
+        s.client
+            .try_approve_insurance_claim(&approver_b, &claim.claim_id)
+            .map(|_| ())
+            .map_err(|_| claim.claim_id)
+    };
+    for _ in 0..3 {
+        pay(10).unwrap();
+    }
+    assert_eq!(s.client.get_insurance_pool(&s.token.address), 5);
+    let unpaid = pay(10).unwrap_err();
+    assert_contract_error(
+        s.client.try_approve_insurance_claim(&approver_b, &unpaid),
+        ContractError::InsufficientInsurancePool,
+    );
+    s.client.reject_insurance_claim(&approver_b, &unpaid);
+    pay(5).unwrap();
+
+    assert_contract_error(
+        s.client.try_file_insurance_claim(&s.creator, &order_id, &1),
+        ContractError::TooManyClaims,
+    );
+}
+
+#[test]
+fn test_overturned_ruling_slashes_resolver_stake_to_appellant() {
+    let s = setup();
+    let admin = s.client.get_config().admin;
+    let court = Address::generate(&s.env);
+    TokenAdminClient::new(&s.env, &s.token.address).mint(&s.dispute_resolver, &1_000);
+
+    let stake_config = ResolverStakeConfig {
+        min_stake: 1_000,
+        unbonding_secs: 200,
+        appeal_window_secs: 100,
+        slash_bps: 5_000,
+        appeal_court: court.clone(),
+    };
+    assert!(s
+        .client
+        .try_set_resolver_stake_config(
+            &admin,
+            &ResolverStakeConfig {
+                unbonding_secs: 50,
+                ..stake_config.clone()
+            }
+        )
+        .is_err());
+    s.client.set_resolver_stake_config(&admin, &stake_config);
+
+    let order_id = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &250,
+        &1_000,
+        &2_592_000,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    s.client.take_order(&s.filler, &order_id);
+    s.client.submit_fiat_payment(&s.filler, &order_id);
+    s.client.dispute_fiat_payment(&s.filler, &order_id);
+
+    // The resolver cannot rule until it has the minimum stake bonded.
+    assert!(!s.client.is_eligible_resolver(&s.dispute_resolver));
+    assert!(s
+        .client
+        .try_resolve_dispute(&s.dispute_resolver, &order_id, &true)
+        .is_err());
+    s.client.stake_resolver(&s.dispute_resolver, &1_000);
+    assert!(s.client.is_eligible_resolver(&s.dispute_resolver));
+
+    set_timestamp(&s.env, 1_000);
+    s.client
+        .resolve_dispute(&s.dispute_resolver, &order_id, &true);
+    assert_last_event(
+        &s,
+        &RulingRecorded {
+            version: EVENT_VERSION,
+            order_id,
+            ruling_id: 0,
+            resolver: s.dispute_resolver.clone(),
+            losing_party: s.creator.clone(),
+            appeal_deadline: 1_100,
+        },
+    );
+
+    assert!(s.client.try_appeal_ruling(&s.filler, &0).is_err());
+    s.client.appeal_ruling(&s.creator, &0);
+    assert!(s.client.try_decide_appeal(&s.filler, &0, &true).is_err());
+
+    // Unbonding cannot outrun an undecided appeal.
+    s.client.unbond_resolver_stake(&s.dispute_resolver, &1_000);
+    assert!(!s.client.is_eligible_resolver(&s.dispute_resolver));
+    set_timestamp(&s.env, 1_300);
+    assert!(s
+        .client
+        .try_withdraw_resolver_stake(&s.dispute_resolver)
+        .is_err());
+
+    let creator_before = s.token.balance(&s.creator);
+    assert_eq!(s.client.decide_appeal(&court, &0, &true), 500);
+    assert_eq!(s.token.balance(&s.creator) - creator_before, 500);
+    assert_eq!(s.client.get_ruling(&0).status, RulingStatus::Overturned);
+    assert!(s.client.try_decide_appeal(&admin, &0, &false).is_err());
+
+    assert_eq!(s.client.withdraw_resolver_stake(&s.dispute_resolver), 500);
+    assert_eq!(s.token.balance(&s.dispute_resolver), 500);
+    assert_eq!(s.client.check_solvency(&s.token.address).surplus, 0);
+}
+
+fn order_params(amount: i128) -> OrderParams {
+    OrderParams {
+        fiat_currency: FiatCurrency::Usd,
+        payment_method: PaymentMethod::BankTransfer,
+        from_crypto: true,
+        amount,
+        exchange_rate: 1_000,
+        duration_secs: 2_592_000,
+        requested_timeout_secs: None,
+        client_ref: None,
+        visibility: OrderVisibility::Public,
+    }
+}
+
+#[test]
+fn test_overturned_ruling_forfeits_excess_slash_to_insurance_pool() {
+    let s = setup();
+    let admin = s.client.get_config().admin;
+    let court = Address::generate(&s.env);
+    TokenAdminClient::new(&s.env, &s.token.address).mint(&s.dispute_resolver, &1_000);
+    s.client.set_resolver_stake_config(
+        &admin,
+        &ResolverStakeConfig {
+            min_stake: 1_000,
+            unbonding_secs: 200,
+            appeal_window_secs: 100,
+            slash_bps: 5_000,
+            appeal_court: court.clone(),
+        },
+    );
+    s.client.set_insurance_config(
+        &admin,
+        &InsuranceConfig {
+            fee_share_bps: 0,
+            approvers: vec![&s.env, admin.clone()],
+            threshold: 1,
+            max_claim: 100,
+        },
+    );
+    s.client.stake_resolver(&s.dispute_resolver, &1_000);
+
+    let order_id = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &250,
+        &1_000,
+        &2_592_000,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    s.client.take_order(&s.filler, &order_id);
+    s.client.submit_fiat_payment(&s.filler, &order_id);
+    s.client.dispute_fiat_payment(&s.filler, &order_id);
+    set_timestamp(&s.env, 1_000);
+    s.client
+        .resolve_dispute(&s.dispute_resolver, &order_id, &true);
+    s.client.appeal_ruling(&s.creator, &0);
+
+    // The appellant is made whole for the 250 ruled against it and the rest
+    // of the 500 slashed goes to the pool.
+    let creator_before = s.token.balance(&s.creator);
+    assert_eq!(s.client.decide_appeal(&court, &0, &true), 500);
+    assert_last_event(
+        &s,
+        &InsuranceFunded {
+            version: EVENT_VERSION,
+            order_id,
+            token: s.token.address.clone(),
+            amount: 250,
+            pool_balance: 250,
+        },
+    );
+    assert_eq!(s.token.balance(&s.creator) - creator_before, 250);
+    assert_eq!(s.client.get_insurance_pool(&s.token.address), 250);
+    assert_eq!(s.client.check_solvency(&s.token.address).surplus, 0);
+}
+
+#[test]
+fn test_resolver_stake_stays_locked_until_its_rulings_cannot_be_appealed() {
+    let s = setup();
+    let admin = s.client.get_config().admin;
+    let court = Address::generate(&s.env);
+    TokenAdminClient::new(&s.env, &s.token.address).mint(&s.dispute_resolver, &2_000);
+    let stake_config = ResolverStakeConfig {
+        min_stake: 1_000,
+        unbonding_secs: 200,
+        appeal_window_secs: 100,
+        slash_bps: 5_000,
+        appeal_court: court.clone(),
+    };
+
+    s.client.stake_resolver(&s.dispute_resolver, &2_000);
+    assert_contract_error(
+        s.client
+            .try_unbond_resolver_stake(&s.dispute_resolver, &1_000),
+        ContractError::ResolverStakingNotConfigured,
+    );
+    assert_contract_error(
+        s.client.try_set_resolver_stake_config(
+            &admin,
+            &ResolverStakeConfig {
+                slash_bps: 10_001,
+                ..stake_config.clone()
+            },
+        ),
+        ContractError::InvalidResolverStakeConfig,
+    );
+    s.client.set_resolver_stake_config(&admin, &stake_config);
+    assert_contract_error(
+        s.client
+            .try_unbond_resolver_stake(&s.dispute_resolver, &2_001),
+        ContractError::InsufficientStake,
+    );
+    assert_contract_error(
+        s.client.try_withdraw_resolver_stake(&s.dispute_resolver),
+        ContractError::NothingToWithdraw,
+    );
+
+    let mut order_ids = std::vec::Vec::new();
+    for _ in 0..2 {
+        let order_id = s.client.create_order(
+            &s.creator,
+            &FiatCurrency::Usd,
+            &PaymentMethod::BankTransfer,
+            &true,
+            &250,
+            &1_000,
+            &2_592_000,
+            &None,
+            &None,
+            &OrderVisibility::Public,
+        );
+        s.client.take_order(&s.filler, &order_id);
+        s.client.submit_fiat_payment(&s.filler, &order_id);
+        s.client.dispute_fiat_payment(&s.filler, &order_id);
+        order_ids.push(order_id);
+    }
+
+    // The resolver unbonds what it does not need, stays eligible and rules
+    // just before the unbonding period ends.
+    set_timestamp(&s.env, 1_000);
+    s.client.unbond_resolver_stake(&s.dispute_resolver, &1_000);
+    set_timestamp(&s.env, 1_100);
+    assert_contract_error(
+        s.client.try_withdraw_resolver_stake(&s.dispute_resolver),
+        ContractError::StakeStillUnbonding,
+    );
+    set_timestamp(&s.env, 1_150);
+    for order_id in order_ids.iter() {
+        s.client
+            .resolve_dispute(&s.dispute_resolver, order_id, &true);
+    }
+    assert_eq!(
+        s.client
+            .get_resolver_stake(&s.dispute_resolver)
+            .locked_until,
+        1_250
+    );
+
+    // Unbonding is over, but the rulings can still be appealed.
+    set_timestamp(&s.env, 1_200);
+    assert_contract_error(
+        s.client.try_withdraw_resolver_stake(&s.dispute_resolver),
+        ContractError::StakeLockedByRuling,
+    );
+    assert_contract_error(s.client.try_get_ruling(&2), ContractError::RulingNotFound);
+    assert_contract_error(
+        s.client.try_appeal_ruling(&s.filler, &0),
+        ContractError::Unauthorized,
+    );
+    assert_contract_error(
+        s.client.try_decide_appeal(&court, &0, &true),
+        ContractError::InvalidRulingStatus,
+    );
+
+    set_timestamp(&s.env, 1_250);
+    s.client.appeal_ruling(&s.creator, &0);
+    assert_contract_error(
+        s.client.try_appeal_ruling(&s.creator, &0),
+        ContractError::InvalidRulingStatus,
+    );
+    set_timestamp(&s.env, 1_251);
+    assert_contract_error(
+        s.client.try_appeal_ruling(&s.creator, &1),
+        ContractError::AppealWindowClosed,
+    );
+    assert_contract_error(
+        s.client.try_withdraw_resolver_stake(&s.dispute_resolver),
+        ContractError::AppealPending,
+    );
+
+    // The slash counts the stake the resolver tried to unbond early. It is
+    // taken from bonded stake first, so the unbonded stake is paid out once
+    // the appeal is decided.
+    assert_eq!(s.client.decide_appeal(&court, &0, &true), 1_000);
+    assert_eq!(s.client.withdraw_resolver_stake(&s.dispute_resolver), 1_000);
+}
+
+#[test]
+fn test_operator_acts_for_owner_within_scope_and_daily_cap() {
+    let s = setup();
+    let bot = Address::generate(&s.env);
+    let create_as_bot = |amount: i128| {
+        s.client
+            .try_create_order_for(&bot, &s.creator, &order_params(amount))
+    };
+
+    assert!(create_as_bot(100).is_err());
+    s.client.set_operator(
+        &s.creator,
+        &bot,
+        &OperatorGrant {
+            scopes: vec![
+                &s.env,
+                OperatorScope::CreateOrders,
+                OperatorScope::CancelOrders,
+                OperatorScope::ConfirmPayments,
+            ],
+            expires_at: Some(200_000),
+            daily_volume_cap: Some(1_500),
+        },
+    );
+    assert_eq!(
+        s.client.get_operators(&s.creator),
+        vec![&s.env, bot.clone()]
+    );
+
+    // Operator deposits come from the owner's allowance, never the bot.
+    s.token.approve(
+        &s.creator,
+        &s.client.address,
+        &10_000,
+        &(s.env.ledger().sequence() + 1_000),
+    );
+    let creator_before = s.token.balance(&s.creator);
+    let order_id = create_as_bot(1_000).unwrap().unwrap();
+    assert_eq!(s.client.get_order(&order_id).creator, s.creator);
+    assert_eq!(creator_before - s.token.balance(&s.creator), 1_000);
+    s.client.cancel_order(&bot, &order_id);
+    assert_eq!(s.token.balance(&s.creator), creator_before);
+    assert_eq!(s.token.balance(&bot), 0);
+
+    // The cap counts created volume per UTC day.
+    assert!(create_as_bot(600).is_err());
+    set_timestamp(&s.env, 86_400);
+    let order_id = create_as_bot(600).unwrap().unwrap();
+    s.client.take_order(&s.filler, &order_id);
+    s.client.submit_fiat_payment(&s.filler, &order_id);
+    assert!(s
+        .client
+        .try_confirm_fiat_payment(&Address::generate(&s.env), &order_id)
+        .is_err());
+    s.client.confirm_fiat_payment(&bot, &order_id);
+    assert_eq!(s.client.get_order(&order_id).status, OrderStatus::Completed);
+    assert_eq!(s.client.get_operator_usage(&s.creator, &bot).volume, 1_200);
+
+    // Grants expire and can be revoked.
+    set_timestamp(&s.env, 200_000);
+    assert!(create_as_bot(100).is_err());
+    s.client.revoke_operator(&s.creator, &bot);
+    assert_eq!(s.client.get_operator(&s.creator, &bot), None);
+}
+
+#[test]
+fn test_operator_confirms_escrowed_fill_without_owner_key() {
+    let s = setup();
+    let (_, escrows) = enable_escrow_settlement(&s, 1);
+    let bot = Address::generate(&s.env);
+    s.client.set_operator(
+        &s.creator,
+        &bot,
+        &OperatorGrant {
+            scopes: vec![&s.env, OperatorScope::ConfirmPayments],
+            expires_at: None,
+            daily_volume_cap: None,
+        },
+    );
+    let order_id = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &1_000,
+        &1_000,
+        &600,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    s.client.take_order(&s.filler, &order_id);
+    s.client.submit_fiat_payment(&s.filler, &order_id);
+
+    // Only the bot signs; the creator's key is never asked for.
+    let filler_before = s.token.balance(&s.filler);
+    s.client
+        .mock_auths(&[MockAuth {
+            address: &bot,
+            invoke: &MockAuthInvoke {
+                contract: &s.client.address,
+                fn_name: "confirm_fiat_payment",
+                args: (&bot, order_id).into_val(&s.env),
+                sub_invokes: &[],
+            },
+        }])
+        .confirm_fiat_payment(&bot, &order_id);
+    assert_eq!(s.token.balance(&s.filler), filler_before + 997);
+    assert_eq!(s.token.balance(&escrows[0]), 0);
+    assert_eq!(s.client.get_order(&order_id).status, OrderStatus::Completed);
+}
+
+#[test]
+fn test_operator_grant_rejections() {
+    let s = setup();
+    let bot = Address::generate(&s.env);
+    let grant = OperatorGrant {
+        scopes: vec![&s.env, OperatorScope::CreateOrders],
+        expires_at: Some(1_000),
+        daily_volume_cap: Some(500),
+    };
+    assert_contract_error(
+        s.client.try_set_operator(&s.creator, &s.creator, &grant),
+        ContractError::InvalidAddress,
+    );
+    for invalid in [
+        OperatorGrant {
+            scopes: Vec::new(&s.env),
+            ..grant.clone()
+        },
+        OperatorGrant {
+            expires_at: Some(0),
+            ..grant.clone()
+        },
+        OperatorGrant {
+            daily_volume_cap: Some(0),
+            ..grant.clone()
+        },
+    ] {
+        assert_contract_error(
+            s.client.try_set_operator(&s.creator, &bot, &invalid),
+            ContractError::InvalidOperatorGrant,
+        );
+    }
+    assert_contract_error(
+        s.client.try_revoke_operator(&s.creator, &bot),
+        ContractError::OperatorNotFound,
+    );
+
+    s.client.set_operator(&s.creator, &bot, &grant);
+    assert_contract_error(
+        s.client
+            .try_create_order_for(&bot, &s.creator, &order_params(501)),
+        ContractError::OperatorVolumeCapExceeded,
+    );
+    let order_id = s.client.create_order(
+        &s.creator,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &100,
+        &1_000,
+        &2_592_000,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    assert_contract_error(
+        s.client.try_cancel_order(&bot, &order_id),
+        ContractError::Unauthorized,
+    );
+    set_timestamp(&s.env, 1_000);
+    assert_contract_error(
+        s.client
+            .try_create_order_for(&bot, &s.creator, &order_params(100)),
+        ContractError::OperatorGrantExpired,
+    );
+
+    for _ in 0..9 {
+        s.client.set_operator(
+            &s.creator,
+            &Address::generate(&s.env),
+            &OperatorGrant {
+                expires_at: None,
+                ..grant.clone()
+            },
+        );
+    }
+    assert_contract_error(
+        s.client.try_set_operator(
+            &s.creator,
+            &Address::generate(&s.env),
+            &OperatorGrant {
+                expires_at: None,
+                ..grant.clone()
+            },
+        ),
+        ContractError::TooManyOperators,
+    );
+}
+
+#[test]
+fn test_batch_create_and_cancel_move_funds_once() {
+    let s = setup();
+    let creator_before = s.token.balance(&s.creator);
+
+    // An invalid item reverts an all-or-nothing batch.
+    let batch = vec![
+        &s.env,
+        order_params(100),
+        order_params(0),
+        order_params(300),
+    ];
+    assert!(s
+        .client
+        .try_create_orders(&s.creator, &batch, &true)
+        .is_err());
+
+    let order_ids = s.client.create_orders(&s.creator, &batch, &false);
+    assert_eq!(order_ids.len(), 3);
+    assert_eq!(order_ids.get(1).unwrap(), None);
+    let first = order_ids.get(0).unwrap().unwrap();
+    let third = order_ids.get(2).unwrap().unwrap();
+    assert_eq!(creator_before - s.token.balance(&s.creator), 400);
+
+    s.client.take_order(&s.filler, &first);
+    assert!(s
+        .client
+        .try_cancel_orders(&s.creator, &vec![&s.env, first, third], &true)
+        .is_err());
+    assert_eq!(
+        s.client
+            .cancel_orders(&s.creator, &vec![&s.env, first, third], &false),
+        vec![&s.env, third]
+    );
+    assert_eq!(creator_before - s.token.balance(&s.creator), 100);
+
+    // Paging over every order cancels only the caller's open ones.
+    let mine = s.client.create_orders(
+        &s.creator,
+        &vec![&s.env, order_params(50), order_params(70)],
+        &true,
+    );
+    let theirs = s.client.create_order(
+        &s.filler,
+        &FiatCurrency::Usd,
+        &PaymentMethod::BankTransfer,
+        &true,
+        &90,
+        &1_000,
+        &2_592_000,
+        &None,
+        &None,
+        &OrderVisibility::Public,
+    );
+    let next = s.client.cancel_all_my_orders(&s.creator, &0, &3);
+    assert_eq!(next, Some(3));
+    assert_eq!(s.client.cancel_all_my_orders(&s.creator, &3, &10), None);
+    for order_id in mine.iter() {
+        assert_eq!(
+            s.client.get_order(&order_id.unwrap()).status,
+            OrderStatus::Cancelled
+        );
+    }
+    assert_eq!(
+        s.client.get_order(&theirs).status,
+        OrderStatus::AwaitingFiller
+    );
+    assert_eq!(creator_before - s.token.balance(&s.creator), 100);
+}
+
+/// Token transfers in the last invocation's events.
+fn token_transfers(s: &Setup) -> usize {
+    s.env
+        .events()
+        .all()
+        .iter()
+        .filter(|(contract, topics, _)| {
+            *contract == s.token.address
+                && topics.get(0).is_some_and(|topic| {
+                    Symbol::try_from_val(&s.env, &topic) == Ok(Symbol::new(&s.env, "transfer"))
+                })
+        })
+        .count()
+}
+
+#[test]
+fn test_batches_are_capped_and_settle_in_one_transfer() {
+    let s = setup();
+    let creator_before = s.token.balance(&s.creator);
+
+    let mut batch = Vec::new(&s.env);
+    for _ in 0..21 {
+        batch.push_back(order_params(10));
+    }
+    assert_contract_error(
+        s.client.try_create_orders(&s.creator, &batch, &false),
+        ContractError::BatchTooLarge,
+    );
+    let mut ids = Vec::new(&s.env);
+    for order_id in 0..21 {
+        ids.push_back(order_id);
+    }
+    assert_contract_error(
+        s.client.try_cancel_orders(&s.creator, &ids, &false),
+        ContractError::BatchTooLarge,
+    );
+
+    // Failed items are skipped and the rest is deposited in one transfer.
+    batch.pop_back();
+    batch.set(3, order_params(0));
+    batch.set(7, order_params(-5));
+    let order_ids = s.client.create_orders(&s.creator, &batch, &false);
+    assert_eq!(token_transfers(&s), 1);
+    assert_eq!(creator_before - s.token.balance(&s.creator), 180);
+    assert_eq!(order_ids.get(3).unwrap(), None);
+    assert_eq!(order_ids.get(7).unwrap(), None);
+
+    // Refunds for the cancelled orders go out in one transfer too.
+    let mut ids = Vec::new(&s.env);
+    for order_id in order_ids.iter().flatten() {
+        ids.push_back(order_id);
+    }
+    s.client.take_order(&s.filler, &ids.get(0).unwrap());
+    ids.push_back(1_000);
+    let cancelled = s.client.cancel_orders(&s.creator, &ids, &false);
+    assert_eq!(token_transfers(&s), 1);
+    assert_eq!(cancelled.len(), 17);
+    assert_eq!(creator_before - s.token.balance(&s.creator), 10);
+}
+
+#[test]
+fn test_circuit_breaker_suspends_takes_when_disputes_spike() {
+    let s = setup();
+    let admin = s.client.get_config().admin;
+    let breaker_config = BreakerConfig {
+        window_secs: 1_200,
+        max_dispute_bps: 5_000,
+        min_samples: 3,
+    };
+    assert!(s
+        .client
+        .try_set_circuit_breaker_config(&s.pauser, &breaker_config)
+        .is_err());
+    s.client.set_circuit_breaker_config(&admin, &breaker_config);
+    set_timestamp(&s.env, 1_000);
+
+    let order_ids = s.client.create_orders(
+        &s.creator,
+        &vec![
+            &s.env,
+            order_params(100),
+            order_params(100),
+            order_params(100),
+            order_params(100),
+        ],
+        &true,
+    );
+    let order_id = |i: u32| order_ids.get(i).unwrap().unwrap();
+    for i in 0..3 {
+        s.client.take_order(&s.filler, &order_id(i));
+        s.client.submit_fiat_payment(&s.filler, &order_id(i));
+    }
+    s.client.confirm_fiat_payment(&s.creator, &order_id(0));
+    s.client.dispute_fiat_payment(&s.filler, &order_id(1));
+    // Two samples are below the minimum even at a 50% dispute rate.
+    assert_eq!(
+        s.client
+            .get_circuit_breaker_status(&FiatCurrency::Usd)
+            .tripped_at,
+        None
+    );
+
+    s.client.dispute_fiat_payment(&s.filler, &order_id(2));
+    assert_event_published(
+        &s,
+        &CircuitBreakerTripped {
+            version: EVENT_VERSION,
+            currency: FiatCurrency::Usd,
+            disputes: 2,
+            timeouts: 0,
+            settlements: 1,
+            tripped_at: 1_000,
+        },
+    );
+    assert_contract_error(
+        s.client.try_take_order(&s.filler, &order_id(3)),
+        ContractError::CircuitBreakerTripped,
+    );
+
+    assert_contract_error(
+        s.client
+            .try_reset_circuit_breaker(&admin, &FiatCurrency::Usd),
+        ContractError::Unauthorized,
+    );
+    s.client
+        .reset_circuit_breaker(&s.pauser, &FiatCurrency::Usd);
+    assert_contract_error(
+        s.client
+            .try_reset_circuit_breaker(&s.pauser, &FiatCurrency::Usd),
+        ContractError::BreakerNotTripped,
+    );
+    let status = s.client.get_circuit_breaker_status(&FiatCurrency::Usd);
+    assert_eq!(status.disputes, 0);
+    assert_eq!(status.tripped_at, None);
+    s.client.take_order(&s.filler, &order_id(3));
+}
+
+#[test]
+fn test_circuit_breaker_counts_escrowed_timeouts_and_freezes_as_disputes() {
+    let s = setup();
+    enable_escrow_settlement(&s, 4);
+    let admin = s.client.get_config().admin;
+    s.client.set_circuit_breaker_config(
+        &admin,
+        &BreakerConfig {
+            window_secs: 12_000,
+            max_dispute_bps: 5_000,
+            min_samples: 3,
+        },
+    );
+    set_timestamp(&s.env, 1_000);
+
+    let order_ids = s.client.create_orders(
+        &s.creator,
+        &vec![
+            &s.env,
+            order_params(100),
+            order_params(100),
+            order_params(100),
+            order_params(100),
+        ],
+        &true,
+    );
+    let order_id = |i: u32| order_ids.get(i).unwrap().unwrap();
+    for i in 0..3 {
+        s.client.take_order(&s.filler, &order_id(i));
+    }
+
+    // Expired escrowed fills escalate to disputes, and count as such.
+    set_timestamp(&s.env, 3_000);
+    s.client
+        .execute_fiat_transfer_timeout(&s.creator, &order_id(0));
+    s.client
+        .execute_fiat_transfer_timeout(&s.creator, &order_id(1));
+    s.client
+        .execute_fiat_transfer_timeout(&s.creator, &order_id(2));
+    assert_event_published(
+        &s,
+        &CircuitBreakerTripped {
+            version: EVENT_VERSION,
+            currency: FiatCurrency::Usd,
+            disputes: 3,
+            timeouts: 0,
+            settlements: 0,
+            tripped_at: 3_000,
+        },
+    );
+    assert_contract_error(
+        s.client.try_take_order(&s.filler, &order_id(3)),
+        ContractError::CircuitBreakerTripped,
+    );
+
+    // A fill frozen into a dispute counts too.
+    s.client
+        .reset_circuit_breaker(&s.pauser, &FiatCurrency::Usd);
+    s.client.take_order(&s.filler, &order_id(3));
+    s.client.block_address(&s.compliance, &s.filler);
+    s.client.freeze_order(&s.compliance, &order_id(3));
+    assert_eq!(
+        s.client
+            .get_circuit_breaker_status(&FiatCurrency::Usd)
+            .disputes,
+        1
+    );
+}
+
+#[test]
+fn test_circuit_breaker_counts_resolved_disputes_and_rejects_bad_config() {
+    let s = setup();
+    let admin = s.client.get_config().admin;
+    let breaker_config = BreakerConfig {
+        window_secs: 1_200,
+        max_dispute_bps: 9_000,
+        min_samples: 100,
+    };
+    for invalid in [
+        BreakerConfig {
+            window_secs: 11,
+            ..breaker_config.clone()
+        },
+        BreakerConfig {
+            max_dispute_bps: 10_001,
+            ..breaker_config.clone()
+        },
+        BreakerConfig {
+            min_samples: 0,
+            ..breaker_config.clone()
+        },
+    ] {
+        assert_contract_error(
+            s.client.try_set_circuit_breaker_config(&admin, &invalid),
+            ContractError::InvalidBreakerConfig,
+        );
+    }
+    s.client.set_circuit_breaker_config(&admin, &breaker_config);
+    set_timestamp(&s.env, 1_000);
+
+    let order_ids = s.client.create_orders(
+        &s.creator,
+        &vec![&s.env, order_params(100), order_params(100)],
+        &true,
+    );
+    for order_id in order_ids.iter() {
+        let order_id = order_id.unwrap();
+        s.client.take_order(&s.filler, &order_id);
+        s.client.submit_fiat_payment(&s.filler, &order_id);
+        s.client.dispute_fiat_payment(&s.filler, &order_id);
+    }
+
+    // A confirmed ruling settles the fill; a rejected one does not.
+    let first = order_ids.get(0).unwrap().unwrap();
+    let second = order_ids.get(1).unwrap().unwrap();
+    s.client.resolve_dispute(&s.dispute_resolver, &first, &true);
+    s.client
+        .resolve_dispute(&s.dispute_resolver, &second, &false);
+    let status = s.client.get_circuit_breaker_status(&FiatCurrency::Usd);
+    assert_eq!(status.disputes, 2);
+    assert_eq!(status.settlements, 1);
+    assert_eq!(status.timeouts, 0);
+    assert_contract_error(
+        s.client
+            .try_reset_circuit_breaker(&s.pauser, &FiatCurrency::Usd),
+        ContractError::BreakerNotTripped,
+    );
+}
@@ -56,6 +56,7 @@ make -C "${P2P_DIR}" p2p-init \
   ADMIN="${ADMIN_ADDR}" \
   DISPUTE_RESOLVER="${ADMIN_ADDR}" \
   PAUSER="${ADMIN_ADDR}" \
+  COMPLIANCE="${ADMIN_ADDR}" \
   TOKEN_CONTRACT_ID="${TOKEN_CONTRACT_ID}" \
   MAX_DURATION_SECS="${MAX_DURATION_SECS}" \
   FILLER_PAYMENT_TIMEOUT_SECS="${FILLER_PAYMENT_TIMEOUT_SECS}"